@@ -3,7 +3,10 @@ use serde::{Deserialize, Serialize};
 use strum::EnumString;
 
 use crate::{
-    app::FocusArea, common::layout::RootLayout, components::Component, search_modules::ListResult,
+    app::FocusArea,
+    common::{launch::LaunchError, layout::RootLayout},
+    components::Component,
+    search_modules::{ListResult, ModuleHelp},
 };
 
 // use crate::common::module_state::SearchResult;
@@ -16,12 +19,95 @@ pub enum NavigateDirection {
     Right,
     Home,
     End,
+    PageUp, // by the height of the list
+    PageDown,
+    HalfPageUp,
+    HalfPageDown,
+}
+// vim-like editing mode, see `input.editing_mode` in settings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputMode {
+    #[default]
+    Insert,
+    Normal,
+    Hint,     // typing a hint label launches the labelled result, see `ui.results.hint_keys`
+    Messages, // the messages view is open and takes the navigation keys
+    Help,     // the help overlay is open, typing filters it
+}
+impl InputMode {
+    /// Whether the mode is an overlay over the results, with only its own keybindings.
+    pub fn is_overlay(self) -> bool {
+        matches!(
+            self,
+            InputMode::Hint | InputMode::Messages | InputMode::Help
+        )
+    }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Search {
     Add(char),
     Remove(i8),      // number of characters to remove
     Execute(String), // execute search with given query
+    Set(String),     // replace the query, empty to clear
+    Submit(String),  // search without search as you type, remembered in the history
+}
+// readline style editing of the query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum EditCommand {
+    DeleteWordBack,
+    DeleteToStart,
+    DeleteToEnd,
+    WordBack,
+    WordForward,
+    LineStart,
+    LineEnd,
+}
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExecuteMode {
+    #[default]
+    Primary,
+    Secondary, // the result's alternative action, if it has one
+}
+
+/// How important a notice is, sets its colour and how long its toast is shown.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+/// A message for the user, shown as a toast and kept in the messages view.
+///
+/// Modules post these with [`Action::Notify`] through their action handler.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Notice {
+    pub severity: Severity,
+    pub message: String,
+}
+impl Notice {
+    pub fn info(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Info,
+            message: message.into(),
+        }
+    }
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
@@ -34,7 +120,11 @@ pub enum Action {
     //
     Search(Search),
     SearchResults(Vec<ListResult>),
-    ItemExecute(ListResult), // execute selected item in results
+    SearchTotal(usize), // number of items the modules searched through, i.e. all applications
+    ItemExecute(ListResult, bool), // launch a result, keeping rook open when true
+    ItemExecuteAll(Vec<ListResult>, bool), // launch the marked results one after another
+    Launched(Result<(), LaunchError>), // how the launch went, ends the launching spinner
+    Execute(ExecuteMode, Option<usize>, bool), // the selected result or the one at an index, keep open
     //
     Navigate(NavigateDirection, usize), // direction, number of lines
     Tick,
@@ -43,7 +133,17 @@ pub enum Action {
     Resume,
     ClearScreen,
     Suspend,
-    Error(String),
+    Error(String),                                // shown as an error notice
+    Notify(Notice),                               // a toast, kept in the messages view
+    ToggleMessages,                               // the scrollable view of the session's notices
+    ShowLaunchHistory, // the past launches in the messages view, with why they failed
+    LaunchHistory(Vec<(String, Option<String>)>), // past launches and errors, most recent first
+    ToggleHelp,        // the overlay listing the keybindings and modules
+    ModuleHelp(Vec<ModuleHelp>), // what each module responds to, for the help overlay
+    ReloadSettings,    // read the settings files again
+    EditSettings,      // open the settings file in $EDITOR, then reload it
+    SetTheme(String),  // switch to a theme by name, until rook is closed
+    ClearHistory,      // forget the past queries
     Focus(FocusArea),
     FocusNext,
     FocusPrevious,
@@ -51,26 +151,225 @@ pub enum Action {
     ToggleWizard,
     FocusToggle,
     Unfocus,
+    InputMode(InputMode),
+    PendingKeys(String), // keys of an unfinished key sequence, empty when there is none
+    ToggleScores,
+    ToggleMark, // mark the selected result for launching with the others, see `ItemExecuteAll`
+    ModuleCommand(String, String), // module name, command
+    ShowHints,  // label the visible results for launching by letter
+    HideHints,
+    Edit(EditCommand),
+    HistoryPrevious,            // recall an older query
+    HistoryNext,                // recall a newer query, or the query being typed
+    QueryHistory(Vec<String>),  // past queries, most recent first
+    Completion(Option<String>), // the completed query, shown as ghost text
+    CompletionAvailable(bool),  // whether ghost text is shown, enables [keybinds.completion]
+    AcceptCompletion,
+    ModulePrefixes(Vec<(String, String)>), // prefix and title of the module it scopes the query to
+    NextGroup,                             // select the first result of the next module's group
+    PreviousGroup,
 }
-impl From<&str> for Action {
-    fn from(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
+impl Action {
+    /// Parse an action from a keybinding string, with optional `:` separated arguments.
+    ///
+    /// ```text
+    /// navigate_down           navigate_down:10
+    /// execute                 execute:secondary:2
+    /// search:set:=            search:clear
+    /// module:maths_module:clear_history
+    /// ```
+    pub fn parse(s: &str) -> Result<Action, String> {
+        let mut parts = s.trim().splitn(2, ':');
+        let name = parts.next().unwrap_or_default().to_lowercase();
+        let args = parts.next();
+        let count = |args: Option<&str>| -> Result<usize, String> {
+            match args {
+                None => Ok(1),
+                Some(n) => n
+                    .parse()
+                    .map_err(|_| format!("Invalid count {:?} for {}", n, name)),
+            }
+        };
+
+        let action = match name.as_str() {
             "quit" => Action::Quit,
-            "navigate_up" => Action::Navigate(NavigateDirection::Up, 1),
-            "navigate_down" => Action::Navigate(NavigateDirection::Down, 1),
-            "navigate_left" => Action::Navigate(NavigateDirection::Left, 1),
-            "navigate_right" => Action::Navigate(NavigateDirection::Right, 1),
+            "navigate_up" => Action::Navigate(NavigateDirection::Up, count(args)?),
+            "navigate_down" => Action::Navigate(NavigateDirection::Down, count(args)?),
+            "navigate_left" => Action::Navigate(NavigateDirection::Left, count(args)?),
+            "navigate_right" => Action::Navigate(NavigateDirection::Right, count(args)?),
             "navigate_home" => Action::Navigate(NavigateDirection::Home, 1),
             "navigate_end" => Action::Navigate(NavigateDirection::End, 1),
+            "page_up" => Action::Navigate(NavigateDirection::PageUp, count(args)?),
+            "page_down" => Action::Navigate(NavigateDirection::PageDown, count(args)?),
+            "half_page_up" => Action::Navigate(NavigateDirection::HalfPageUp, count(args)?),
+            "half_page_down" => Action::Navigate(NavigateDirection::HalfPageDown, count(args)?),
+            "next_group" => Action::NextGroup,
+            "previous_group" => Action::PreviousGroup,
             "focus_next" => Action::FocusNext,
             "focus_previous" => Action::FocusPrevious,
             "suspend" => Action::Suspend,
             "toggle_wizard" => Action::ToggleWizard,
-            _ => Action::Error(format!("Unknown action variant: {}", s)),
+            "toggle_scores" => Action::ToggleScores,
+            "toggle_mark" => Action::ToggleMark,
+            "normal_mode" => Action::InputMode(InputMode::Normal),
+            "insert_mode" => Action::InputMode(InputMode::Insert),
+            "show_hints" => Action::ShowHints,
+            "hide_hints" => Action::HideHints,
+            "history_previous" => Action::HistoryPrevious,
+            "history_next" => Action::HistoryNext,
+            "accept_completion" => Action::AcceptCompletion,
+            "toggle_messages" => Action::ToggleMessages,
+            "show_launch_history" => Action::ShowLaunchHistory,
+            "toggle_help" => Action::ToggleHelp,
+            "reload_settings" => Action::ReloadSettings,
+            "edit_settings" => Action::EditSettings,
+            "clear_history" => Action::ClearHistory,
+            "set_theme" => match args {
+                Some(name) if !name.is_empty() => Action::SetTheme(name.to_string()),
+                _ => return Err(format!("Expected set_theme:<name>, got {:?}", s)),
+            },
+            // execute[:primary|secondary][:keep_open][:N], N is the number shown next to the
+            // result, counted from the top of the visible page. keep_open launches without closing
+            "execute" => {
+                let mut args = args
+                    .unwrap_or_default()
+                    .split(':')
+                    .filter(|a| !a.is_empty());
+                let mut mode = ExecuteMode::Primary;
+                let mut index = None;
+                let mut keep_open = false;
+                for arg in args.by_ref() {
+                    match arg.to_lowercase().as_str() {
+                        "primary" => mode = ExecuteMode::Primary,
+                        "secondary" => mode = ExecuteMode::Secondary,
+                        "keep_open" => keep_open = true,
+                        n => {
+                            let n: usize = n
+                                .parse()
+                                .map_err(|_| format!("Invalid execute argument {:?}", arg))?;
+                            index = Some(n.saturating_sub(1));
+                        }
+                    }
+                }
+                Action::Execute(mode, index, keep_open)
+            }
+            "execute_keep_open" => Action::Execute(ExecuteMode::Primary, None, true),
+            "search" => {
+                let (command, text) = match args.map(|a| a.split_once(':')) {
+                    Some(Some((command, text))) => (command, text),
+                    _ => (args.unwrap_or_default(), ""),
+                };
+                match command.to_lowercase().as_str() {
+                    "set" => Action::Search(Search::Set(text.to_string())),
+                    "clear" => Action::Search(Search::Set(String::new())),
+                    _ => return Err(format!("Unknown search command {:?}", command)),
+                }
+            }
+            // module:<module name>:<command>, handled by the module itself
+            "module" => match args.and_then(|a| a.split_once(':')) {
+                Some((module, command)) => {
+                    Action::ModuleCommand(module.to_string(), command.to_string())
+                }
+                None => return Err(format!("Expected module:<name>:<command>, got {:?}", s)),
+            },
+            // query editing, i.e. delete_word_back
+            _ => match name.parse::<EditCommand>() {
+                Ok(command) => Action::Edit(command),
+                Err(_) => return Err(format!("Unknown action variant: {}", s)),
+            },
+        };
+        Ok(action)
+    }
+}
+
+impl Action {
+    /// What a bindable action does, for the help overlay.
+    pub fn description(&self) -> String {
+        let times = |amount: usize| {
+            if amount > 1 {
+                format!(" {}", amount)
+            } else {
+                String::new()
+            }
+        };
+        match self {
+            Action::Quit => "Quit".into(),
+            Action::Navigate(direction, amount) => match direction {
+                NavigateDirection::Up => format!("Move up{}", times(*amount)),
+                NavigateDirection::Down => format!("Move down{}", times(*amount)),
+                NavigateDirection::Left => format!("Move left{}", times(*amount)),
+                NavigateDirection::Right => format!("Move right{}", times(*amount)),
+                NavigateDirection::Home => "Go to the first".into(),
+                NavigateDirection::End => "Go to the last".into(),
+                NavigateDirection::PageUp => format!("Page up{}", times(*amount)),
+                NavigateDirection::PageDown => format!("Page down{}", times(*amount)),
+                NavigateDirection::HalfPageUp => format!("Half a page up{}", times(*amount)),
+                NavigateDirection::HalfPageDown => {
+                    format!("Half a page down{}", times(*amount))
+                }
+            },
+            Action::Execute(mode, index, keep_open) => {
+                let mut text = match mode {
+                    ExecuteMode::Primary => "Launch".to_string(),
+                    ExecuteMode::Secondary => "Alternative action for".to_string(),
+                };
+                match index {
+                    Some(index) => text.push_str(&format!(" result {}", index + 1)),
+                    None => text.push_str(" the selection"),
+                }
+                if *keep_open {
+                    text.push_str(", staying open");
+                }
+                text
+            }
+            Action::Search(Search::Set(query)) if query.is_empty() => "Clear the query".into(),
+            Action::Search(Search::Set(query)) => format!("Set the query to {:?}", query),
+            Action::ModuleCommand(module, command) => {
+                format!("Run {} in {}", command.replace('_', " "), module)
+            }
+            Action::NextGroup => "Next module's results".into(),
+            Action::PreviousGroup => "Previous module's results".into(),
+            Action::FocusNext => "Focus the next section".into(),
+            Action::FocusPrevious => "Focus the previous section".into(),
+            Action::Suspend => "Suspend".into(),
+            Action::ToggleWizard => "Show or hide the side panel".into(),
+            Action::ToggleScores => "Show or hide the scores".into(),
+            Action::ToggleMark => "Mark the selection to launch with the others".into(),
+            Action::InputMode(InputMode::Normal) => "Vim normal mode".into(),
+            Action::InputMode(InputMode::Insert) => "Vim insert mode".into(),
+            Action::ShowHints => "Label the results to launch by typing".into(),
+            Action::HideHints => "Hide the labels".into(),
+            Action::HistoryPrevious => "Previous query from the history".into(),
+            Action::HistoryNext => "Next query from the history".into(),
+            Action::AcceptCompletion => "Accept the completion".into(),
+            Action::ToggleMessages => "Show or hide the messages".into(),
+            Action::ShowLaunchHistory => "Show the past launches".into(),
+            Action::ToggleHelp => "Show or hide this help".into(),
+            Action::ReloadSettings => "Reload the settings".into(),
+            Action::EditSettings => "Edit the settings file".into(),
+            Action::SetTheme(name) => format!("Switch to the {} theme", name),
+            Action::ClearHistory => "Clear the query history".into(),
+            Action::Edit(command) => match command {
+                EditCommand::DeleteWordBack => "Delete the word before the caret",
+                EditCommand::DeleteToStart => "Delete to the start of the query",
+                EditCommand::DeleteToEnd => "Delete to the end of the query",
+                EditCommand::WordBack => "Move the caret back a word",
+                EditCommand::WordForward => "Move the caret forward a word",
+                EditCommand::LineStart => "Move the caret to the start",
+                EditCommand::LineEnd => "Move the caret to the end",
+            }
+            .into(),
+            other => format!("{:?}", other),
         }
     }
 }
 
+impl From<&str> for Action {
+    fn from(s: &str) -> Self {
+        Action::parse(s).unwrap_or_else(Action::Error)
+    }
+}
+
 impl<'de> Deserialize<'de> for Action {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -80,3 +379,74 @@ impl<'de> Deserialize<'de> for Action {
         Ok(Action::from(s.as_str()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_actions() {
+        assert_eq!(Action::parse("quit"), Ok(Action::Quit));
+        assert_eq!(
+            Action::parse("navigate_down:10"),
+            Ok(Action::Navigate(NavigateDirection::Down, 10))
+        );
+        assert_eq!(
+            Action::parse("execute"),
+            Ok(Action::Execute(ExecuteMode::Primary, None, false))
+        );
+        assert_eq!(
+            Action::parse("execute:secondary:2"),
+            Ok(Action::Execute(ExecuteMode::Secondary, Some(1), false))
+        );
+        assert_eq!(
+            Action::parse("execute:keep_open"),
+            Ok(Action::Execute(ExecuteMode::Primary, None, true))
+        );
+        assert_eq!(
+            Action::parse("search:set:="),
+            Ok(Action::Search(Search::Set("=".into())))
+        );
+        assert_eq!(
+            Action::parse("search:set:a:b"),
+            Ok(Action::Search(Search::Set("a:b".into())))
+        );
+        assert_eq!(
+            Action::parse("module:maths_module:clear_history"),
+            Ok(Action::ModuleCommand(
+                "maths_module".into(),
+                "clear_history".into()
+            ))
+        );
+        assert_eq!(
+            Action::parse("delete_word_back"),
+            Ok(Action::Edit(EditCommand::DeleteWordBack))
+        );
+        assert_eq!(
+            Action::parse("set_theme:nord"),
+            Ok(Action::SetTheme("nord".into()))
+        );
+        assert_eq!(Action::parse("toggle_mark"), Ok(Action::ToggleMark));
+        assert!(Action::parse("set_theme").is_err());
+        assert!(Action::parse("navigate_down:many").is_err());
+        assert!(Action::parse("does_not_exist").is_err());
+        assert!(matches!(Action::from("does_not_exist"), Action::Error(_)));
+    }
+
+    #[test]
+    fn test_description() {
+        let description = |s: &str| Action::parse(s).unwrap().description();
+        assert_eq!(description("navigate_down"), "Move down");
+        assert_eq!(description("navigate_down:10"), "Move down 10");
+        assert_eq!(description("execute:2"), "Launch result 2");
+        assert_eq!(
+            description("execute_keep_open"),
+            "Launch the selection, staying open"
+        );
+        assert_eq!(description("search:clear"), "Clear the query");
+        assert_eq!(
+            description("module:maths_module:clear_history"),
+            "Run clear history in maths_module"
+        );
+    }
+}
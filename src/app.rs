@@ -1,5 +1,5 @@
 use color_eyre::Result;
-use crossterm::event::{KeyEvent, MouseEvent, MouseEventKind};
+use crossterm::event::{KeyEvent, KeyEventKind, MouseEvent, MouseEventKind};
 use ratatui::{
     layout::{Constraint, Position},
     prelude::Rect,
@@ -10,19 +10,37 @@ use std::{
     ops::{Add, Sub},
     rc::Rc,
     sync::Arc,
+    thread::JoinHandle,
+    time::{Duration, Instant},
 };
 use tokio::sync::{Mutex, mpsc};
 use tracing::{debug, info};
 
 use crate::{
-    action::{Action, Search},
-    components::{Component, results::ResultsBox, search::SearchBox, wizard::WizardBox},
+    action::{Action, InputMode, Notice, Search},
+    common::{
+        color_depth::{ColorDepth, limit_effects, quantize_buffer},
+        keymap::{KeyOutcome, KeySequenceMatcher, Keymap},
+        launch::LaunchError,
+        layout::draw_too_small,
+    },
+    components::{
+        Component, help::Help, notifications::Notifications, results::ResultsBox,
+        search::SearchBox, wizard::WizardBox,
+    },
     database::Database,
+    effects::{EffectEvent, Effects},
     search_modules::{
-        SearchModule, applications::desktop_files_module::DesktopFilesModule,
-        maths::maths_module::MathsModule,
+        ListResult, ModuleHelp, SearchModule,
+        applications::desktop_files_module::DesktopFilesModule,
+        commands::commands_module::CommandsModule, maths::maths_module::MathsModule,
+    },
+    settings::{
+        layers::{SettingsOverrides, user_settings_file},
+        settings::{EditingMode, ResultsDisplay, Settings, get_settings_path},
+        theme_import::ThemeWatcher,
+        themes::{load_theme, theme_files},
     },
-    settings::settings::{SerializableKeyEvent, Settings, get_settings_path},
     tui::{Event, Tui},
 };
 
@@ -69,20 +87,45 @@ impl Sub<u8> for FocusArea {
 
 pub struct App {
     settings: Settings,
+    overrides: SettingsOverrides, // the profile and --set values, kept for reloading the settings
     tick_rate: f64,
     frame_rate: f64,
+    height: Option<Constraint>, // inline viewport height, full screen when None
     components: Vec<Box<dyn Component>>,
     search_modules: Vec<Box<dyn SearchModule>>,
     should_quit: bool,
     should_suspend: bool,
     mode: Mode,
-    last_tick_key_events: Vec<KeyEvent>,
+    keymap: Keymap,
+    key_sequence: KeySequenceMatcher, // keys of an unfinished sequence like "g g"
+    input_mode: InputMode,
+    mode_before_overlay: InputMode, // restored when the hints or the messages view are hidden
+    pending_keys: String,           // last pending keys sent to the components
+    last_query: String,             // saved to the history when a result is launched
+    completion_available: bool,     // ghost text is shown, so Tab accepts it
+    module_prefixes: Vec<Option<String>>, // prefix of each search module, see `search.prefixes`
     last_tick_mouse_events: Vec<MouseEvent>,
     action_tx: mpsc::UnboundedSender<Action>,
     action_rx: mpsc::UnboundedReceiver<Action>,
     focused_area: Option<FocusArea>,
     database: Arc<Mutex<Database>>,
     root_layout: crate::common::layout::RootLayout,
+    theme_watcher: Option<ThemeWatcher>, // reloads the theme when its file changes
+    color_depth: ColorDepth,             // resolved colour depth of the terminal
+    effects: Effects,                    // running effects, drawn over the components
+    launching: bool,                     // a launch effect was started
+    pending_launch: Option<(ListResult, bool)>, // launched on the next frame, keeping rook open when true
+    queued_launches: Vec<ListResult>, // the rest of the marked results, launched after the pending one
+    batch_failed: bool,               // a launch of the marked results failed, so rook stays open
+    watched_launch: Option<WatchedLaunch>, // see `launch.failure_window`
+    closing: bool,                    // quitting once the close and launch effects finish
+}
+
+// a launched program watched off the UI thread for a failed exit
+struct WatchedLaunch {
+    result: ListResult,
+    keep_open: bool,
+    handle: JoinHandle<std::result::Result<(), LaunchError>>,
 }
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -92,9 +135,14 @@ pub enum Mode {
 }
 
 impl App {
-    pub async fn new(tick_rate: f64, frame_rate: f64) -> Result<Self> {
+    pub async fn new(
+        mut settings: Settings,
+        overrides: SettingsOverrides,
+        tick_rate: f64,
+        frame_rate: f64,
+        height: Option<Constraint>,
+    ) -> Result<Self> {
         let (action_tx, action_rx) = mpsc::unbounded_channel();
-        let settings = Settings::new();
         let database_path = get_settings_path()
             .join("rook.db")
             .to_string_lossy()
@@ -102,52 +150,108 @@ impl App {
         log::info!("Database path: {:?}", database_path);
         let database = Arc::new(Mutex::new(Database::new(&database_path)?));
         database.lock().await.initialise()?;
+        let theme_watcher = settings
+            .ui
+            .theme
+            .name
+            .as_deref()
+            .map(|name| ThemeWatcher::new(theme_files(name)));
+        let color_depth = settings.ui.color_depth.resolve();
+        log::info!("Colour depth: {:?}", color_depth);
+        limit_effects(&mut settings, color_depth);
+        let input_mode = InputMode::default();
+        let keymap = Keymap::for_mode(&settings, input_mode);
+        let key_sequence =
+            KeySequenceMatcher::new(Duration::from_millis(settings.input.chord_timeout));
 
         Ok(Self {
             tick_rate,
             frame_rate,
+            height,
             components: vec![
                 Box::new(SearchBox::new()),
                 Box::new(ResultsBox::new()),
                 Box::new(WizardBox::new()),
+                // overlays last, so they're drawn over the other components
+                Box::new(Help::new()),
+                Box::new(Notifications::new()),
             ],
             search_modules: vec![
                 Box::new(DesktopFilesModule::new()),
                 Box::new(MathsModule::new()),
+                Box::new(CommandsModule::new()),
             ],
             should_quit: false,
             should_suspend: false,
             settings,
+            overrides,
             mode: Mode::Home,
-            last_tick_key_events: Vec::new(),
+            keymap,
+            key_sequence,
+            input_mode,
+            mode_before_overlay: input_mode,
+            pending_keys: String::new(),
+            last_query: String::new(),
+            completion_available: false,
+            module_prefixes: Vec::new(),
             last_tick_mouse_events: Vec::new(),
             action_tx,
             action_rx,
             focused_area: Some(FocusArea::Search),
             database,
             root_layout: crate::common::layout::RootLayout::default(),
+            theme_watcher,
+            color_depth,
+            effects: Effects::default(),
+            launching: false,
+            pending_launch: None,
+            queued_launches: Vec::new(),
+            batch_failed: false,
+            watched_launch: None,
+            closing: false,
         })
     }
 
     pub async fn run(&mut self) -> Result<()> {
         for module in self.search_modules.iter_mut() {
+            module.register_action_handler(self.action_tx.clone())?;
             module.register_database_handler(self.database.clone())?;
             module.register_settings_handler(self.settings.clone())?;
 
             module.init()?;
         }
 
-        let mut tui = Tui::new()?
-            // .mouse(true) // uncomment this line to enable mouse support
+        let mut tui = Tui::new()?;
+        if let Some(height) = self.height {
+            tui = tui.inline(height)?;
+        }
+        let mut tui = tui
+            .mouse(self.settings.input.mouse)
             .tick_rate(self.tick_rate)
             .frame_rate(self.frame_rate);
         tui.enter()?;
+        let area = tui.get_frame().area();
+        self.effects
+            .trigger(&self.settings, EffectEvent::Open, area);
 
         for component in self.components.iter_mut() {
             component.register_action_handler(self.action_tx.clone())?;
             component.register_settings_handler(self.settings.clone())?;
             component.init(tui.size()?)?;
         }
+        self.send_module_prefixes();
+        for invalid in self.settings.keybinds.invalid() {
+            self.action_tx
+                .send(Action::Notify(Notice::warning(invalid)))
+                .unwrap();
+        }
+        let history = futures::executor::block_on(self.database.lock()).query_history()?;
+        self.action_tx.send(Action::QueryHistory(history)).unwrap();
+        if self.settings.input.editing_mode == EditingMode::Vim {
+            self.action_tx
+                .send(Action::InputMode(self.input_mode))
+                .unwrap();
+        }
 
         let action_tx = self.action_tx.clone();
         loop {
@@ -157,7 +261,6 @@ impl App {
                 tui.suspend()?;
                 action_tx.send(Action::Resume).unwrap();
                 action_tx.send(Action::ClearScreen).unwrap();
-                // tui.mouse(true);
                 tui.enter()?;
             } else if self.should_quit {
                 tui.stop()?;
@@ -194,8 +297,22 @@ impl App {
             Event::Tick => action_tx.send(Action::Tick).unwrap(),
             Event::Render => action_tx.send(Action::Render).unwrap(),
             Event::Resize(x, y) => action_tx.send(Action::Resize(x, y)).unwrap(),
-            Event::Mouse(mouse) => self.handle_mouse_event(mouse).unwrap(),
-            Event::Key(key) => self.handle_key_event(key).unwrap(),
+            Event::Mouse(mouse) => {
+                // an overlay, or a toast under the mouse, keeps the event from the components below
+                let captured = self
+                    .components
+                    .iter()
+                    .rposition(|component| component.captures_mouse(&mouse));
+                if let Some(index) = captured {
+                    if let Some(action) = self.components[index].handle_events(Some(event))? {
+                        action_tx.send(action).unwrap();
+                    }
+                    return Ok(());
+                }
+                self.handle_mouse_event(mouse).unwrap()
+            }
+            // keys are passed on to the components by handle_key_event, unless they're part of a binding
+            Event::Key(key) => return self.handle_key_event(key),
             _ => {}
         }
         for component in self.components.iter_mut() {
@@ -235,27 +352,41 @@ impl App {
     }
 
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
-        let action_tx = self.action_tx.clone();
-        let keymap = self.settings.keybinds.clone().get_event_mapping();
+        // key handling is different for OS. windows sends press/release events seperately
+        if key.kind != KeyEventKind::Press {
+            return self.forward_key(key);
+        }
+        let outcomes = self.key_sequence.push(&self.keymap, key, Instant::now());
+        self.handle_key_outcomes(outcomes)
+    }
 
-        let key_serialised: SerializableKeyEvent = key.into();
-        match keymap.get(&key_serialised) {
-            Some(action) => {
-                info!("Got action: {action:?}");
-                action_tx.send(action.clone()).unwrap();
+    fn handle_key_outcomes(&mut self, outcomes: Vec<KeyOutcome>) -> Result<()> {
+        for outcome in outcomes {
+            match outcome {
+                KeyOutcome::Action(action) => {
+                    info!("Got action: {action:?}");
+                    self.action_tx.send(action).unwrap();
+                }
+                KeyOutcome::Forward(key) => self.forward_key(key)?,
+            }
+        }
+        // update the pending keys indicator
+        let pending_keys = self.key_sequence.pending_text();
+        if pending_keys != self.pending_keys {
+            self.pending_keys = pending_keys.clone();
+            self.action_tx
+                .send(Action::PendingKeys(pending_keys))
+                .unwrap();
+        }
+        Ok(())
+    }
+
+    // pass a key that isn't bound to anything on to the components, i.e. typing in the search box
+    fn forward_key(&mut self, key: KeyEvent) -> Result<()> {
+        for component in self.components.iter_mut() {
+            if let Some(action) = component.handle_events(Some(Event::Key(key)))? {
+                self.action_tx.send(action).unwrap();
             }
-            _ => {
-                // // If the key was not handled as a single key action,
-                // // then consider it for multi-key combinations.
-                self.last_tick_key_events.push(key);
-
-                // // Check for multi-key combinations
-                // if let Some(action) = m.get(&self.last_tick_key_events[0].into()) {
-                //     info!("Got action: {action:?}");
-                //     action_tx.send(action.clone()).unwrap();
-                // }
-                log::info!("No action mapped for key: {:?}", key_serialised);
-            } // None => {}
         }
         Ok(())
     }
@@ -268,42 +399,154 @@ impl App {
             }
             match &action {
                 Action::Tick => {
-                    self.last_tick_key_events.drain(..);
+                    let outcomes = self.key_sequence.expire(&self.keymap, Instant::now());
+                    self.handle_key_outcomes(outcomes)?;
+                    self.reload_theme_if_changed()?;
+                }
+                Action::InputMode(mode) => {
+                    self.input_mode = *mode;
+                    self.rebuild_keymap();
+                }
+                Action::CompletionAvailable(available) => {
+                    self.completion_available = *available;
+                    self.rebuild_keymap();
+                }
+                Action::ShowHints if self.input_mode != InputMode::Hint => {
+                    self.mode_before_overlay = self.input_mode;
+                    action_tx.send(Action::InputMode(InputMode::Hint)).unwrap();
+                }
+                Action::HideHints if self.input_mode == InputMode::Hint => {
+                    action_tx
+                        .send(Action::InputMode(self.mode_before_overlay))
+                        .unwrap();
+                }
+                Action::ToggleMessages => self.toggle_overlay(InputMode::Messages),
+                Action::ShowLaunchHistory => {
+                    let database = futures::executor::block_on(self.database.lock());
+                    let launches = database.launch_history()?;
+                    drop(database);
+                    action_tx.send(Action::LaunchHistory(launches)).unwrap();
+                    if self.input_mode != InputMode::Messages {
+                        self.toggle_overlay(InputMode::Messages);
+                    }
+                }
+                Action::ToggleHelp => self.toggle_overlay(InputMode::Help),
+                Action::Quit if !self.closing => {
+                    let area = tui.get_frame().area();
+                    self.closing = self.effects.close(&self.settings, area) || self.launching;
+                    self.should_quit = !self.closing;
                 }
-                Action::Quit => self.should_quit = true,
                 Action::Suspend => self.should_suspend = true,
                 Action::Resume => self.should_suspend = false,
                 Action::ClearScreen => tui.terminal.clear()?,
                 Action::Resize(w, h) => self.handle_resize(tui, *w, *h)?,
-                Action::Render => self.render(tui)?,
+                Action::Render => {
+                    self.render(tui)?;
+                    self.launch_pending(tui)?;
+                }
+                Action::Search(Search::Submit(query)) => {
+                    self.remember_query(query)?;
+                    action_tx
+                        .send(Action::Search(Search::Execute(query.clone())))
+                        .unwrap();
+                }
                 Action::Search(Search::Execute(query)) => {
-                    self.search_modules.iter_mut().for_each(|module| {
-                        let has_results = module.search(query).unwrap_or_else(|err| {
-                            log::info!(
-                                "Module {} failed to search for query: {}: {:?}",
-                                module.name(),
-                                query,
-                                err
-                            );
-                            return false;
-                        });
-                        if has_results {
+                    self.last_query = query.clone();
+                    // a module's prefix, i.e. "=", leaves the query to that module alone
+                    let scoped = self
+                        .module_prefixes
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, prefix)| Some((i, prefix.as_deref()?)))
+                        .filter(|(_, prefix)| query.starts_with(prefix))
+                        .max_by_key(|(_, prefix)| prefix.len());
+                    let mut completion = None;
+                    let mut results = vec![];
+                    let mut has_results = false;
+                    let mut total = 0;
+                    for (i, module) in self.search_modules.iter_mut().enumerate() {
+                        let prefix = match scoped {
+                            Some((scoped, _)) if scoped != i => continue,
+                            Some((_, prefix)) => prefix,
+                            None if module.scoped_only() => continue,
+                            None => "",
+                        };
+                        let module_query = &query[prefix.len()..];
+                        let module_has_results =
+                            module.search(module_query).unwrap_or_else(|err| {
+                                log::info!(
+                                    "Module {} failed to search for query: {}: {:?}",
+                                    module.name(),
+                                    query,
+                                    err
+                                );
+                                return false;
+                            });
+                        if module_has_results {
                             log::info!(
                                 "Module {} found results for query: {}",
                                 module.name(),
                                 query
                             );
-                            let results = module.get_ui_results();
-                            info!("Results: {:?}", results);
-                            action_tx.send(Action::SearchResults(results)).unwrap();
+                            let module_results =
+                                Self::group_results(&self.settings, module.as_ref());
+                            info!("Results: {:?}", module_results);
+                            results.extend(module_results);
+                            has_results = true;
+                            total += module.total();
                         }
-                    });
+                        // completes from the last module with results
+                        if module_has_results || scoped.is_some() {
+                            completion = module
+                                .complete(module_query)
+                                .map(|completion| format!("{}{}", prefix, completion));
+                        }
+                    }
+                    // no results keeps the last ones, i.e. the maths history
+                    if has_results {
+                        action_tx.send(Action::SearchTotal(total)).unwrap();
+                        action_tx.send(Action::SearchResults(results)).unwrap();
+                    }
+                    action_tx.send(Action::Completion(completion)).unwrap();
                 }
-                Action::ItemExecute(result) => {
+                Action::ModuleCommand(name, command) => {
+                    let module = self
+                        .search_modules
+                        .iter_mut()
+                        .find(|module| module.name().eq_ignore_ascii_case(name));
+                    match module {
+                        Some(module) => match module.command(command) {
+                            Ok(true) => {
+                                let results = Self::group_results(&self.settings, module.as_ref());
+                                action_tx.send(Action::SearchTotal(module.total())).unwrap();
+                                action_tx.send(Action::SearchResults(results)).unwrap()
+                            }
+                            Ok(false) => {}
+                            Err(err) => {
+                                action_tx.send(Action::Error(format!("{:?}", err))).unwrap()
+                            }
+                        },
+                        None => action_tx
+                            .send(Action::Error(format!("No module named {:?}", name)))
+                            .unwrap(),
+                    }
+                }
+                Action::ItemExecute(result, keep_open) => {
                     info!("Executing result: {:?}", result);
-                    result.launch.as_ref()();
-                    // sleep::sleep(std::time::Duration::from_millis(100));
-                    action_tx.send(Action::Quit).unwrap();
+                    self.remember_query(&self.last_query.clone())?;
+                    self.tell_module(result);
+                    // launched after the next frame, which shows the spinner on its row
+                    self.pending_launch = Some((result.clone(), *keep_open));
+                }
+                Action::ItemExecuteAll(results, keep_open) if !results.is_empty() => {
+                    info!("Executing {} marked results", results.len());
+                    self.remember_query(&self.last_query.clone())?;
+                    for result in results {
+                        self.tell_module(result);
+                    }
+                    self.pending_launch = Some((results[0].clone(), *keep_open));
+                    self.queued_launches = results[1..].to_vec();
+                    self.batch_failed = false;
                 }
 
                 Action::FocusNext => {
@@ -314,13 +557,44 @@ impl App {
                     let new = self.focused_area.clone().unwrap_or(FocusArea::Search) - 1;
                     self.update_focus(Some(new))?;
                 }
-                Action::ToggleWizard => {
-                    if self.root_layout.left_right_split > 0 {
-                        self.root_layout.set_left_right_split(0);
-                    } else {
-                        self.root_layout.set_left_right_split(25);
+                Action::ToggleWizard => self.root_layout.toggle_side_panel(),
+                Action::ReloadSettings => match Settings::load(&self.overrides) {
+                    Ok(settings) => {
+                        self.apply_settings(settings)?;
+                        action_tx
+                            .send(Action::Notify(Notice::info("Reloaded the settings")))
+                            .unwrap();
                     }
-                    self.root_layout.queue_update();
+                    Err(err) => action_tx
+                        .send(Action::Notify(Notice::error(format!(
+                            "Could not reload the settings: {}",
+                            err
+                        ))))
+                        .unwrap(),
+                },
+                Action::EditSettings => self.edit_settings(tui)?,
+                Action::SetTheme(name) => match load_theme(name) {
+                    Ok(theme) => {
+                        self.settings.ui.theme = theme;
+                        self.theme_watcher = Some(ThemeWatcher::new(theme_files(name)));
+                        for component in self.components.iter_mut() {
+                            component.register_settings_handler(self.settings.clone())?;
+                        }
+                    }
+                    Err(err) => action_tx
+                        .send(Action::Notify(Notice::error(format!(
+                            "Could not load the theme {}: {}",
+                            name, err
+                        ))))
+                        .unwrap(),
+                },
+                Action::ClearHistory => {
+                    let database = futures::executor::block_on(self.database.lock());
+                    database.clear_query_history()?;
+                    action_tx.send(Action::QueryHistory(vec![])).unwrap();
+                    action_tx
+                        .send(Action::Notify(Notice::info("Cleared the query history")))
+                        .unwrap();
                 }
 
                 _ => {}
@@ -334,6 +608,288 @@ impl App {
         Ok(())
     }
 
+    // launch the result waiting for the spinner to be drawn, then watch it for a failed exit
+    fn launch_pending(&mut self, tui: &mut Tui) -> Result<()> {
+        if let Some((result, keep_open)) = self.pending_launch.take() {
+            match result.launch.as_ref()() {
+                Ok(Some(launched)) => {
+                    // watched off the UI thread, the spinner keeps turning meanwhile
+                    let window = Duration::from_millis(self.settings.launch.failure_window);
+                    let handle = std::thread::spawn(move || launched.watch(window));
+                    self.watched_launch = Some(WatchedLaunch {
+                        result,
+                        keep_open,
+                        handle,
+                    });
+                }
+                Ok(None) => self.finish_launch(tui, &result, keep_open, Ok(()))?,
+                Err(err) => self.finish_launch(tui, &result, keep_open, Err(err))?,
+            }
+        }
+        let watched = self.watched_launch.as_ref();
+        if watched.is_some_and(|watched| watched.handle.is_finished()) {
+            let WatchedLaunch {
+                result,
+                keep_open,
+                handle,
+            } = self.watched_launch.take().unwrap();
+            let outcome = handle.join().unwrap_or_else(|_| {
+                Err(LaunchError::Other(
+                    "watching the program failed".to_string(),
+                ))
+            });
+            self.finish_launch(tui, &result, keep_open, outcome)?;
+        }
+        Ok(())
+    }
+
+    // record the launch, then quit or clear the query for the next one, unless it failed
+    fn finish_launch(
+        &mut self,
+        tui: &mut Tui,
+        result: &ListResult,
+        keep_open: bool,
+        outcome: std::result::Result<(), LaunchError>,
+    ) -> Result<()> {
+        let error = outcome.as_ref().err().map(|err| err.to_string());
+        match &error {
+            Some(error) => {
+                log::error!("Failed to launch {}: {}", result.result, error);
+                let notice =
+                    Notice::error(format!("Failed to launch {}: {}", result.result, error));
+                self.action_tx.send(Action::Notify(notice)).unwrap();
+            }
+            None => info!("Launched {}", result.result),
+        }
+        let history_size = self.settings.launch.history_size;
+        if history_size > 0 {
+            let database = futures::executor::block_on(self.database.lock());
+            database.add_launch(
+                &result.result,
+                &self.last_query,
+                error.as_deref(),
+                history_size,
+            )?;
+        }
+        self.action_tx.send(Action::Launched(outcome)).unwrap();
+        // the marked results carry on after a failure, then rook stays open to show it
+        self.batch_failed |= error.is_some();
+        if !self.queued_launches.is_empty() {
+            let next = self.queued_launches.remove(0);
+            self.pending_launch = Some((next, keep_open));
+            return Ok(());
+        }
+        if std::mem::take(&mut self.batch_failed) {
+            return Ok(());
+        }
+        let area = tui.get_frame().area();
+        self.launching = self
+            .effects
+            .trigger(&self.settings, EffectEvent::Launch, area);
+        if keep_open || self.settings.launch.keep_open {
+            // ready for the next launch, like a scratchpad
+            self.action_tx
+                .send(Action::Search(Search::Set(String::new())))
+                .unwrap();
+        } else {
+            self.action_tx.send(Action::Quit).unwrap();
+        }
+        Ok(())
+    }
+
+    // use newly loaded settings everywhere, as on startup
+    fn apply_settings(&mut self, mut settings: Settings) -> Result<()> {
+        self.color_depth = settings.ui.color_depth.resolve();
+        limit_effects(&mut settings, self.color_depth);
+        self.theme_watcher = settings
+            .ui
+            .theme
+            .name
+            .as_deref()
+            .map(|name| ThemeWatcher::new(theme_files(name)));
+        self.key_sequence =
+            KeySequenceMatcher::new(Duration::from_millis(settings.input.chord_timeout));
+        self.settings = settings;
+        self.rebuild_keymap();
+        for component in self.components.iter_mut() {
+            component.register_settings_handler(self.settings.clone())?;
+        }
+        for module in self.search_modules.iter_mut() {
+            module.register_settings_handler(self.settings.clone())?;
+        }
+        self.send_module_prefixes();
+        for invalid in self.settings.keybinds.invalid() {
+            self.action_tx
+                .send(Action::Notify(Notice::warning(invalid)))
+                .unwrap();
+        }
+        Ok(())
+    }
+
+    // open the user settings file in $VISUAL or $EDITOR, reloading the settings after
+    fn edit_settings(&mut self, tui: &mut Tui) -> Result<()> {
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| "vi".to_string());
+        let mut words = editor.split_whitespace();
+        let program = words.next().unwrap_or("vi");
+        tui.exit()?;
+        let status = std::process::Command::new(program)
+            .args(words)
+            .arg(user_settings_file())
+            .status();
+        tui.enter()?;
+        tui.terminal.clear()?;
+        let action = match status {
+            Ok(status) if status.success() => Action::ReloadSettings,
+            Ok(status) => Action::Notify(Notice::error(format!("{} {}", program, status))),
+            Err(err) => Action::Notify(Notice::error(format!(
+                "Could not open {}: {}",
+                program, err
+            ))),
+        };
+        self.action_tx.send(action).unwrap();
+        Ok(())
+    }
+
+    // resolve the module prefixes and pass them and the module help on to the components
+    fn send_module_prefixes(&mut self) {
+        self.module_prefixes = self.resolve_module_prefixes();
+        let prefixes = self
+            .search_modules
+            .iter()
+            .zip(&self.module_prefixes)
+            .filter_map(|(module, prefix)| Some((prefix.clone()?, module.title().to_string())))
+            .collect();
+        self.action_tx
+            .send(Action::ModulePrefixes(prefixes))
+            .unwrap();
+        let module_help = self
+            .search_modules
+            .iter()
+            .zip(&self.module_prefixes)
+            .map(|(module, prefix)| ModuleHelp {
+                title: module.title().to_string(),
+                prefix: prefix.clone(),
+                triggers: module.triggers(),
+                secondary_action: module.secondary_action().map(str::to_string),
+            })
+            .collect();
+        self.action_tx
+            .send(Action::ModuleHelp(module_help))
+            .unwrap();
+    }
+
+    // let the module a result came from know it was launched, i.e. to remember a calculation
+    fn tell_module(&mut self, result: &ListResult) {
+        let module = self
+            .search_modules
+            .iter_mut()
+            .find(|module| module.title() == result.group);
+        if let Some(module) = module {
+            module.execute(result);
+        }
+    }
+
+    // the prefix of each module, from `search.prefixes` or the module's own
+    fn resolve_module_prefixes(&self) -> Vec<Option<String>> {
+        self.search_modules
+            .iter()
+            .map(|module| {
+                let name = module.name().to_lowercase();
+                match self.settings.search.prefixes.get(&name) {
+                    Some(prefix) => Some(prefix.clone()).filter(|prefix| !prefix.is_empty()),
+                    None => module.prefix().map(str::to_string),
+                }
+            })
+            .collect()
+    }
+
+    // the module's results under its title, capped at the group limit when grouped
+    fn group_results(settings: &Settings, module: &dyn SearchModule) -> Vec<ListResult> {
+        let results_settings = &settings.ui.results;
+        let limit = match results_settings.display {
+            ResultsDisplay::Flat => 0,
+            ResultsDisplay::Grouped => *results_settings
+                .group_limits
+                .get(&module.name().to_lowercase())
+                .unwrap_or(&results_settings.group_limit),
+        };
+        module
+            .get_ui_results()
+            .into_iter()
+            .take(if limit > 0 { limit } else { usize::MAX })
+            .map(|result| ListResult {
+                group: module.title().to_string(),
+                markable: module.batch_execute() && result.action.is_none(),
+                ..result
+            })
+            .collect()
+    }
+
+    // open an overlay, or close it back to the mode it was opened from
+    fn toggle_overlay(&mut self, overlay: InputMode) {
+        let mode = if self.input_mode == overlay {
+            self.mode_before_overlay
+        } else {
+            if !self.input_mode.is_overlay() {
+                self.mode_before_overlay = self.input_mode;
+            }
+            overlay
+        };
+        self.action_tx.send(Action::InputMode(mode)).unwrap();
+    }
+
+    fn rebuild_keymap(&mut self) {
+        let keymap = Keymap::for_mode(&self.settings, self.input_mode);
+        self.keymap = if self.completion_available && !self.input_mode.is_overlay() {
+            keymap.with_completion(&self.settings)
+        } else {
+            keymap
+        };
+    }
+
+    // save a query to the history and pass the new history on to the search box
+    fn remember_query(&mut self, query: &str) -> Result<()> {
+        let history_size = self.settings.search.history_size;
+        if history_size == 0 || query.trim().is_empty() {
+            return Ok(());
+        }
+        let history = {
+            let database = futures::executor::block_on(self.database.lock());
+            database.add_query_history(query, history_size)?;
+            database.query_history()?
+        };
+        self.action_tx.send(Action::QueryHistory(history)).unwrap();
+        Ok(())
+    }
+
+    fn reload_theme_if_changed(&mut self) -> Result<()> {
+        let Some(watcher) = self.theme_watcher.as_mut() else {
+            return Ok(());
+        };
+        if !watcher.changed() {
+            return Ok(());
+        }
+        let Some(name) = self.settings.ui.theme.name.clone() else {
+            return Ok(());
+        };
+        match load_theme(&name) {
+            Ok(theme) => {
+                log::info!("Theme {} changed, reloading", name);
+                self.settings.ui.theme = theme;
+                // the theme may inherit from another one now
+                self.theme_watcher = Some(ThemeWatcher::new(theme_files(&name)));
+                for component in self.components.iter_mut() {
+                    component.register_settings_handler(self.settings.clone())?;
+                }
+            }
+            // keep the current theme while the file is half written or invalid
+            Err(err) => log::warn!("Could not reload theme {}: {:?}", name, err),
+        }
+        Ok(())
+    }
+
     fn handle_resize(&mut self, tui: &mut Tui, w: u16, h: u16) -> Result<()> {
         tui.resize(Rect::new(0, 0, w, h))?;
         self.render(tui)?;
@@ -351,14 +907,31 @@ impl App {
                     .send(Action::UpdateLayout(self.root_layout.clone()))
                     .unwrap();
             }
+            if let Some(minimum) = self.root_layout.too_small {
+                draw_too_small(frame, minimum, &self.settings);
+                return;
+            }
             for component in self.components.iter_mut() {
                 if let Err(err) = component.draw(frame, frame.area()) {
                     let _ = self
                         .action_tx
                         .send(Action::Error(format!("Failed to draw: {:?}", err)));
                 }
+                for (event, area) in component.take_effects() {
+                    self.effects.trigger(&self.settings, event, area);
+                }
             }
+            let area = frame.area();
+            self.effects.process(frame.buffer_mut(), area);
+            quantize_buffer(
+                frame.buffer_mut(),
+                self.color_depth,
+                self.settings.ui.theme.background,
+            );
         })?;
+        if self.closing && !self.effects.is_running() {
+            self.should_quit = true;
+        }
         Ok(())
     }
 }
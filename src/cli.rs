@@ -1,4 +1,7 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use ratatui::layout::Constraint;
+
+use crate::settings::serialise::parse_size;
 
 // use crate::config::{get_config_dir, get_data_dir};
 
@@ -12,6 +15,59 @@ pub struct Cli {
     /// Frame rate, i.e. number of frames per second
     #[arg(short, long, value_name = "FLOAT", default_value_t = 60.0)]
     pub frame_rate: f64,
+
+    /// Render inline below the prompt instead of full screen, i.e. 40% or 20 lines
+    #[arg(long, value_name = "HEIGHT", value_parser = parse_size)]
+    pub height: Option<Constraint>,
+
+    /// Settings profile to apply on top of the user settings, from ~/.config/rook/profiles/NAME.toml
+    #[arg(long, value_name = "NAME", global = true)]
+    pub profile: Option<String>,
+
+    /// Override a single setting, i.e. --set ui.results.max_results=10
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    pub set: Vec<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Inspect rook's settings
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// List and preview themes
+    Theme {
+        #[command(subcommand)]
+        command: ThemeCommand,
+    },
+    /// Put text on the clipboard and keep it there until something else is copied
+    #[command(hide = true)]
+    Copy { text: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the effective settings after merging every layer
+    Show {
+        /// Print every setting along with the layer it came from
+        #[arg(long)]
+        resolved: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ThemeCommand {
+    /// List built in themes and themes in ~/.config/rook/themes
+    List,
+    /// Render a sample of the UI using a theme
+    Preview {
+        /// Name of the theme, as shown by `rook theme list`
+        name: String,
+    },
 }
 
 const VERSION_MESSAGE: &str = concat!(
@@ -1,12 +1,10 @@
 use color_eyre::Result;
 use color_eyre::eyre::eyre;
-use std::os::unix::process::CommandExt;
 use std::path::PathBuf;
-use std::thread::sleep;
-use std::time::Duration;
 use xdgkit::desktop_entry::DesktopEntry;
 
 use crate::app::App;
+use crate::common::launch::{self, LaunchError, LaunchResult};
 
 // Exec field codes as per the Desktop Entry Specification
 // See: https://specifications.freedesktop.org/desktop-entry-spec/latest/ar01s06.html
@@ -47,17 +45,17 @@ impl PartialEq for Application {
 }
 impl Eq for Application {}
 impl Application {
-    pub fn launch(&self) -> bool {
+    pub fn launch(&self) -> LaunchResult {
         let exec_string = match self.exec_string() {
             Some(s) => s,
             None => {
                 log::error!("No executable found for application: {}", self.name());
-                return false;
+                return Err(LaunchError::NoExecutable);
             }
         };
         let name = self.name();
 
-        // run the application using std::process::Command
+        // split the Exec line into the program and its arguments
         let exec_parts: Vec<&str> = exec_string
             .split_whitespace()
             .into_iter()
@@ -65,7 +63,7 @@ impl Application {
             .collect();
         if exec_parts.is_empty() {
             log::error!("No executable found for application: {}", name);
-            return false;
+            return Err(LaunchError::NoExecutable);
         }
 
         let mut cmd: Vec<&str> = vec![];
@@ -78,28 +76,8 @@ impl Application {
         }
         cmd.extend(exec_parts.iter());
 
-        let mut exec_command = std::process::Command::new(&cmd.iter().next().unwrap());
         log::info!("Launching application: {} with command: {:?}", name, cmd);
-        exec_command.stderr(std::process::Stdio::null());
-        exec_command.stdout(std::process::Stdio::null());
-        exec_command.stdin(std::process::Stdio::null());
-        unsafe {
-            exec_command.pre_exec(|| {
-                // Become independent of the parent process
-                if libc::setsid() < 0 {
-                    return Err(std::io::Error::last_os_error());
-                }
-
-                Ok(())
-            });
-        }
-
-        exec_command.spawn().is_err().then(|| {
-            return false;
-        });
-        sleep(Duration::from_millis(100)); // give some time for the application to launch
-
-        true
+        launch::spawn(&cmd)
     }
     pub fn name(&self) -> String {
         match self {
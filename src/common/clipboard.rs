@@ -0,0 +1,20 @@
+use arboard::{Clipboard, SetExtLinux};
+
+use crate::common::launch::{LaunchError, LaunchResult, spawn};
+
+/// Copy text to the clipboard from a `rook copy` process, watched like any other launch.
+///
+/// On X11 and Wayland the clipboard is served by the program that set it, so it's emptied when
+/// that program exits. The process keeps serving it after rook closes, until something else is
+/// copied. Wayland needs a compositor with the data control protocol, otherwise XWayland is used.
+pub fn copy(text: &str) -> LaunchResult {
+    let rook = std::env::current_exe().map_err(|err| LaunchError::Other(err.to_string()))?;
+    let rook = rook.to_string_lossy();
+    spawn(&[&rook, "copy", "--", text])
+}
+
+/// Set the clipboard and serve it until something else is copied, see [`copy`].
+pub fn serve(text: &str) -> Result<(), arboard::Error> {
+    Clipboard::new()?.set().wait().text(text)?;
+    Ok(())
+}
@@ -0,0 +1,348 @@
+use std::path::PathBuf;
+
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::settings::{EffectKind, Settings};
+
+// themes, fades and tachyonfx effects all produce rgb colours
+// terminals without truecolor either approximate them badly or ignore them, so after each frame is drawn
+// every cell is quantized to the nearest colour the terminal can show
+//
+// the colour depth is detected from NO_COLOR, COLORTERM and the terminfo `colors` capability,
+// and can be forced with `ui.color_depth` in settings
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorDepth {
+    #[default]
+    Auto,
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    Monochrome,
+}
+
+impl ColorDepth {
+    /// Resolve `Auto` to the colour depth of the current terminal.
+    pub fn resolve(self) -> ColorDepth {
+        match self {
+            ColorDepth::Auto => detect_color_depth(),
+            depth => depth,
+        }
+    }
+
+    /// Whether colour based effects (rainbow borders, fades) can be shown with a reasonable approximation.
+    pub fn supports_effects(self) -> bool {
+        matches!(self, ColorDepth::TrueColor | ColorDepth::Ansi256)
+    }
+}
+
+pub fn detect_color_depth() -> ColorDepth {
+    let var = |name: &str| std::env::var(name).ok();
+    let term = var("TERM");
+    let terminfo_colors = term.as_deref().and_then(terminfo_max_colors);
+    color_depth_from(
+        var("NO_COLOR").as_deref(),
+        var("COLORTERM").as_deref(),
+        term.as_deref(),
+        terminfo_colors,
+    )
+}
+
+fn color_depth_from(
+    no_color: Option<&str>,
+    colorterm: Option<&str>,
+    term: Option<&str>,
+    terminfo_colors: Option<i32>,
+) -> ColorDepth {
+    // https://no-color.org, any non empty value disables colour
+    if no_color.is_some_and(|v| !v.is_empty()) {
+        return ColorDepth::Monochrome;
+    }
+    if matches!(colorterm, Some("truecolor") | Some("24bit")) {
+        return ColorDepth::TrueColor;
+    }
+    match term {
+        Some("dumb") => return ColorDepth::Monochrome,
+        Some(t) if t.ends_with("-direct") => return ColorDepth::TrueColor,
+        _ => {}
+    }
+    match terminfo_colors {
+        Some(n) if n >= 1 << 24 => ColorDepth::TrueColor,
+        Some(n) if n >= 256 => ColorDepth::Ansi256,
+        Some(n) if n >= 8 => ColorDepth::Ansi16,
+        Some(_) => ColorDepth::Monochrome,
+        // no terminfo entry, guess from the name
+        None => match term {
+            Some(t) if t.contains("256color") => ColorDepth::Ansi256,
+            _ => ColorDepth::Ansi16,
+        },
+    }
+}
+
+fn terminfo_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Ok(dir) = std::env::var("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".terminfo"));
+    }
+    if let Ok(list) = std::env::var("TERMINFO_DIRS") {
+        for dir in list.split(':') {
+            // an empty entry means the default location
+            dirs.push(PathBuf::from(if dir.is_empty() {
+                "/usr/share/terminfo"
+            } else {
+                dir
+            }));
+        }
+    }
+    for dir in ["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"] {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs
+}
+
+// read the `colors` number capability from the compiled terminfo entry for `term`
+// see term(5) for the file format
+fn terminfo_max_colors(term: &str) -> Option<i32> {
+    let first = term.chars().next()?;
+    let bytes = terminfo_dirs().into_iter().find_map(|dir| {
+        // entries are stored under their first letter, or its hex code on macOS
+        std::fs::read(dir.join(first.to_string()).join(term))
+            .or_else(|_| std::fs::read(dir.join(format!("{:x}", first as u32)).join(term)))
+            .ok()
+    })?;
+    parse_terminfo_max_colors(&bytes)
+}
+
+fn parse_terminfo_max_colors(bytes: &[u8]) -> Option<i32> {
+    const MAX_COLORS: usize = 13; // index of `colors` in the number capabilities
+    let short = |at: usize| -> Option<i16> {
+        Some(i16::from_le_bytes([*bytes.get(at)?, *bytes.get(at + 1)?]))
+    };
+
+    let number_size = match short(0)? {
+        0o432 => 2,
+        0o1036 => 4, // extended number format, used for entries with more than 32767 colours
+        _ => return None,
+    };
+    let names_size = short(2)? as usize;
+    let bools_count = short(4)? as usize;
+    let numbers_count = short(6)? as usize;
+    if numbers_count <= MAX_COLORS {
+        return None;
+    }
+
+    let mut offset = 12 + names_size + bools_count;
+    offset += offset % 2; // numbers start on an even byte
+    let at = offset + MAX_COLORS * number_size;
+    let value = if number_size == 2 {
+        short(at)? as i32
+    } else {
+        i32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?)
+    };
+    (value >= 0).then_some(value)
+}
+
+/// Turn off effects that can't be approximated at the given colour depth.
+pub fn limit_effects(settings: &mut Settings, depth: ColorDepth) {
+    if depth.supports_effects() {
+        return;
+    }
+    log::info!("Disabling colour effects for colour depth {:?}", depth);
+    settings.ui.search.rainbow_border = false;
+    settings.ui.results.rainbow_border = false;
+    for effect in settings.ui.effects.all_mut() {
+        if effect.kind.uses_color() {
+            effect.kind = EffectKind::None;
+        }
+    }
+    settings.ui.results.fade_color_at_bottom = false;
+}
+
+// the xterm defaults for the 16 ansi colours
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// channel values of the 6x6x6 colour cube in the 256 colour palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+fn nearest_cube_level(value: u8) -> usize {
+    (0..CUBE_LEVELS.len())
+        .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - value as i32).abs())
+        .unwrap_or(0)
+}
+
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_16[index as usize].1,
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[((i / 6) % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+/// Nearest entry in the 256 colour palette, checking both the colour cube and the grayscale ramp.
+pub fn rgb_to_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    let (r, g, b) = (
+        nearest_cube_level(rgb.0),
+        nearest_cube_level(rgb.1),
+        nearest_cube_level(rgb.2),
+    );
+    let cube_index = (16 + 36 * r + 6 * g + b) as u8;
+
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray_index = 232 + (average.saturating_sub(8) / 10).min(23) as u8;
+
+    if distance(rgb, indexed_to_rgb(gray_index)) < distance(rgb, indexed_to_rgb(cube_index)) {
+        gray_index
+    } else {
+        cube_index
+    }
+}
+
+pub fn rgb_to_ansi16(rgb: (u8, u8, u8)) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+/// Convert a colour to the nearest colour available at `depth`.
+/// Monochrome drops all colour, see [`quantize_buffer`].
+pub fn quantize_color(color: Color, depth: ColorDepth) -> Color {
+    match (depth, color) {
+        (ColorDepth::Auto | ColorDepth::TrueColor, _) => color,
+        (ColorDepth::Monochrome, _) => Color::Reset,
+        (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(rgb_to_ansi256((r, g, b))),
+        (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => rgb_to_ansi16((r, g, b)),
+        (ColorDepth::Ansi16, Color::Indexed(i)) if i >= 16 => rgb_to_ansi16(indexed_to_rgb(i)),
+        _ => color,
+    }
+}
+
+/// Quantize every cell of a drawn frame.
+///
+/// In monochrome mode any cell with a background other than the theme background is reversed instead,
+/// so the selected row stays visible.
+pub fn quantize_buffer(buf: &mut Buffer, depth: ColorDepth, background: Color) {
+    if matches!(depth, ColorDepth::Auto | ColorDepth::TrueColor) {
+        return;
+    }
+    for cell in buf.content.iter_mut() {
+        if depth == ColorDepth::Monochrome && cell.bg != Color::Reset && cell.bg != background {
+            cell.modifier.insert(Modifier::REVERSED);
+        }
+        cell.fg = quantize_color(cell.fg, depth);
+        cell.bg = quantize_color(cell.bg, depth);
+        cell.underline_color = quantize_color(cell.underline_color, depth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_color_depth() {
+        assert_eq!(
+            color_depth_from(Some("1"), Some("truecolor"), None, None),
+            ColorDepth::Monochrome
+        );
+        assert_eq!(
+            color_depth_from(Some(""), Some("truecolor"), None, None),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            color_depth_from(None, None, Some("xterm-256color"), Some(256)),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(
+            color_depth_from(None, None, Some("xterm-256color"), None),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(
+            color_depth_from(None, None, Some("linux"), Some(8)),
+            ColorDepth::Ansi16
+        );
+        assert_eq!(
+            color_depth_from(None, None, Some("dumb"), None),
+            ColorDepth::Monochrome
+        );
+    }
+
+    #[test]
+    fn test_parse_terminfo() {
+        // legacy format: magic, names size, bools, numbers, strings, string table size
+        let mut entry: Vec<u8> = vec![];
+        for short in [0o432i16, 4, 1, 14, 0, 0] {
+            entry.extend(short.to_le_bytes());
+        }
+        entry.extend(b"abc\0"); // names
+        entry.push(1); // bools, followed by one byte of padding
+        entry.push(0);
+        for n in 0..14i16 {
+            entry.extend(if n == 13 { 256i16 } else { -1 }.to_le_bytes());
+        }
+        assert_eq!(parse_terminfo_max_colors(&entry), Some(256));
+        assert_eq!(parse_terminfo_max_colors(&entry[..20]), None);
+    }
+
+    #[test]
+    fn test_quantize_color() {
+        assert_eq!(rgb_to_ansi256((0, 0, 0)), 16);
+        assert_eq!(rgb_to_ansi256((255, 255, 255)), 231);
+        assert_eq!(rgb_to_ansi256((128, 128, 128)), 244);
+        assert_eq!(rgb_to_ansi256((95, 135, 175)), 16 + 36 + 12 + 3);
+        assert_eq!(rgb_to_ansi16((200, 10, 10)), Color::Red);
+        assert_eq!(
+            quantize_color(Color::Rgb(10, 10, 250), ColorDepth::Ansi16),
+            Color::Blue
+        );
+        assert_eq!(
+            quantize_color(Color::Cyan, ColorDepth::Ansi256),
+            Color::Cyan
+        );
+        assert_eq!(
+            quantize_color(Color::Rgb(1, 2, 3), ColorDepth::TrueColor),
+            Color::Rgb(1, 2, 3)
+        );
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    action::{Action, ExecuteMode, InputMode},
+    settings::settings::{EditingMode, KeySequence, SerializableKeyEvent, Settings},
+};
+
+// key sequences, i.e. "g g" or "Ctrl + x Ctrl + c"
+//
+// keys are collected until they match a binding, stop being the start of any binding, or the
+// chord timeout runs out. keys that don't end up as part of a binding are passed on to the
+// components, so typing a "g" into the search box still works when "g g" is bound
+//
+// when a sequence is both a binding and the start of a longer one, it fires after the timeout
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyMatch {
+    None,
+    Prefix,                // start of at least one longer binding
+    Exact(Action),         // a binding, and not the start of any other
+    ExactOrPrefix(Action), // a binding, and also the start of a longer one
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    bindings: HashMap<KeySequence, Action>,
+}
+
+impl Keymap {
+    pub fn new(bindings: HashMap<KeySequence, Action>) -> Self {
+        Self { bindings }
+    }
+
+    /// The keymap for the given mode: vim mode bindings take priority over the other bindings.
+    ///
+    /// Hint mode only has its own bindings, every other key is part of a hint label. So do the
+    /// messages view and the help overlay, which take the keys while they're open.
+    pub fn for_mode(settings: &Settings, mode: InputMode) -> Self {
+        if mode.is_overlay() {
+            return Self::new(settings.keybinds.get_mode_mapping(mode));
+        }
+        let mut bindings = settings.keybinds.get_event_mapping();
+        if settings.input.editing_mode == EditingMode::Vim {
+            bindings.extend(settings.keybinds.get_mode_mapping(mode));
+        }
+        if settings.ui.results.open_through_number {
+            for (number, action) in Self::number_bindings() {
+                bindings.entry(number).or_insert(action);
+            }
+        }
+        Self::new(bindings)
+    }
+
+    /// Add the bindings that apply while a completion is shown, i.e. Tab to accept it.
+    pub fn with_completion(mut self, settings: &Settings) -> Self {
+        self.bindings
+            .extend(settings.keybinds.get_completion_mapping());
+        self
+    }
+
+    // Ctrl + 1..9 launch the results shown on the page, Ctrl + 0 the 10th
+    fn number_bindings() -> impl Iterator<Item = (KeySequence, Action)> {
+        (0..10).map(|index| {
+            let digit = char::from_digit((index + 1) as u32 % 10, 10).unwrap();
+            let key = KeyEvent::new(KeyCode::Char(digit), KeyModifiers::CONTROL);
+            (
+                KeySequence(vec![key.into()]),
+                Action::Execute(ExecuteMode::Primary, Some(index), false),
+            )
+        })
+    }
+
+    pub fn lookup(&self, keys: &[SerializableKeyEvent]) -> KeyMatch {
+        let exact = self.bindings.get(&KeySequence(keys.to_vec())).cloned();
+        let prefix = self.bindings.keys().any(|sequence| {
+            sequence.keys().len() > keys.len() && sequence.keys().starts_with(keys)
+        });
+        match (exact, prefix) {
+            (Some(action), true) => KeyMatch::ExactOrPrefix(action),
+            (Some(action), false) => KeyMatch::Exact(action),
+            (None, true) => KeyMatch::Prefix,
+            (None, false) => KeyMatch::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyOutcome {
+    Action(Action),
+    Forward(KeyEvent), // not part of any binding, pass on to the components
+}
+
+/// Collects keys into sequences and matches them against a [`Keymap`].
+#[derive(Debug, Clone)]
+pub struct KeySequenceMatcher {
+    pending: Vec<KeyEvent>,
+    last_key: Option<Instant>,
+    timeout: Duration,
+}
+
+impl KeySequenceMatcher {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            pending: vec![],
+            last_key: None,
+            timeout,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// The keys of the unfinished sequence, i.e. "Ctrl + x".
+    pub fn pending_text(&self) -> String {
+        let keys: Vec<SerializableKeyEvent> =
+            self.pending.iter().map(|key| (*key).into()).collect();
+        KeySequence(keys).to_string()
+    }
+
+    fn pending_keys(&self) -> Vec<SerializableKeyEvent> {
+        self.pending.iter().map(|key| (*key).into()).collect()
+    }
+
+    pub fn push(&mut self, keymap: &Keymap, key: KeyEvent, now: Instant) -> Vec<KeyOutcome> {
+        let mut outcomes = self.expire(keymap, now);
+
+        let mut keys = self.pending_keys();
+        keys.push(key.into());
+        match keymap.lookup(&keys) {
+            KeyMatch::Exact(action) => {
+                self.pending.clear();
+                outcomes.push(KeyOutcome::Action(action));
+            }
+            KeyMatch::Prefix | KeyMatch::ExactOrPrefix(_) => {
+                self.pending.push(key);
+                self.last_key = Some(now);
+            }
+            KeyMatch::None if self.pending.is_empty() => outcomes.push(KeyOutcome::Forward(key)),
+            KeyMatch::None => {
+                // the sequence so far didn't lead anywhere, the new key may start a new one
+                outcomes.extend(self.flush(keymap));
+                outcomes.extend(self.push(keymap, key, now));
+            }
+        }
+        outcomes
+    }
+
+    /// Finish the pending sequence if the timeout has passed, called on every tick.
+    pub fn expire(&mut self, keymap: &Keymap, now: Instant) -> Vec<KeyOutcome> {
+        match self.last_key {
+            Some(last_key) if self.is_pending() && now.duration_since(last_key) >= self.timeout => {
+                self.flush(keymap)
+            }
+            _ => vec![],
+        }
+    }
+
+    fn flush(&mut self, keymap: &Keymap) -> Vec<KeyOutcome> {
+        let keys = self.pending_keys();
+        let pending = std::mem::take(&mut self.pending);
+        match keymap.lookup(&keys) {
+            KeyMatch::ExactOrPrefix(action) => vec![KeyOutcome::Action(action)],
+            _ => pending.into_iter().map(KeyOutcome::Forward).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::NavigateDirection;
+    use crate::settings::settings::keybinding;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn keymap() -> Keymap {
+        Keymap::new(HashMap::from([
+            (
+                keybinding("g g"),
+                Action::Navigate(NavigateDirection::Home, 1),
+            ),
+            (keybinding("Ctrl + x Ctrl + c"), Action::Quit),
+            (keybinding("z"), Action::ToggleWizard),
+            (keybinding("z z"), Action::Suspend),
+        ]))
+    }
+
+    #[test]
+    fn test_parse_key_sequence() {
+        let sequence = keybinding("Ctrl + x  Ctrl+c");
+        assert_eq!(sequence.keys().len(), 2);
+        assert_eq!(sequence.to_string(), "Ctrl + x Ctrl + c");
+        assert_eq!(keybinding("G").keys()[0].code, KeyCode::Char('G'));
+        assert_eq!(keybinding("Shift + g"), keybinding("G"));
+        assert!("".parse::<KeySequence>().is_err());
+    }
+
+    #[test]
+    fn test_keybinds_merge_with_defaults() {
+        let user = r#"
+            [keybinds]
+            quit = []
+            "navigate_down:10" = ["PageDown"]
+            [keybinds.normal]
+            navigate_end = ["Shift + e"]
+        "#;
+        let config = config::Config::builder()
+            .add_source(config::Config::try_from(&Settings::default()).unwrap())
+            .add_source(config::File::from_str(user, config::FileFormat::Toml))
+            .build()
+            .unwrap();
+        let mut settings: Settings = config.try_deserialize().unwrap();
+        let keymap = Keymap::for_mode(&settings, InputMode::Normal);
+        let lookup = |keys: &str| keymap.lookup(keybinding(keys).keys());
+
+        assert_eq!(lookup("Ctrl + q"), KeyMatch::None);
+        assert_eq!(
+            lookup("PageDown"),
+            KeyMatch::Exact(Action::Navigate(NavigateDirection::Down, 10))
+        );
+        assert_eq!(
+            lookup("Down"),
+            KeyMatch::Exact(Action::Navigate(NavigateDirection::Down, 1))
+        );
+        // normal mode bindings only apply in vim mode
+        assert_eq!(lookup("E"), KeyMatch::None);
+        settings.input.editing_mode = EditingMode::Vim;
+        let keymap = Keymap::for_mode(&settings, InputMode::Normal);
+        assert_eq!(
+            keymap.lookup(keybinding("E").keys()),
+            KeyMatch::Exact(Action::Navigate(NavigateDirection::End, 1))
+        );
+        assert_eq!(keymap.lookup(keybinding("g").keys()), KeyMatch::Prefix);
+    }
+
+    #[test]
+    fn test_user_bindings_win_over_defaults() {
+        // bound before page_down was a default action, which sorts after execute
+        let user = r#"
+            [keybinds]
+            execute = ["Enter", "PageDown"]
+        "#;
+        let config = config::Config::builder()
+            .add_source(config::Config::try_from(&Settings::default()).unwrap())
+            .add_source(config::File::from_str(user, config::FileFormat::Toml))
+            .build()
+            .unwrap();
+        let settings: Settings = config.try_deserialize().unwrap();
+        let keymap = Keymap::for_mode(&settings, InputMode::Insert);
+        let lookup = |keys: &str| keymap.lookup(keybinding(keys).keys());
+
+        let execute = Action::Execute(ExecuteMode::Primary, None, false);
+        assert_eq!(lookup("PageDown"), KeyMatch::Exact(execute.clone()));
+        assert_eq!(lookup("Enter"), KeyMatch::Exact(execute));
+        assert_eq!(
+            lookup("PageUp"),
+            KeyMatch::Exact(Action::Navigate(NavigateDirection::PageUp, 1))
+        );
+    }
+
+    #[test]
+    fn test_number_hint_and_completion_bindings() {
+        let mut settings = Settings::default();
+        let keymap = Keymap::for_mode(&settings, InputMode::Insert);
+        assert_eq!(
+            keymap.lookup(keybinding("Ctrl + 1").keys()),
+            KeyMatch::Exact(Action::Execute(ExecuteMode::Primary, Some(0), false))
+        );
+        assert_eq!(
+            keymap.lookup(keybinding("Ctrl + 0").keys()),
+            KeyMatch::Exact(Action::Execute(ExecuteMode::Primary, Some(9), false))
+        );
+
+        // user bindings win over the number keys
+        settings
+            .keybinds
+            .global
+            .insert("toggle_scores".into(), vec![keybinding("Ctrl + 2")]);
+        let keymap = Keymap::for_mode(&settings, InputMode::Insert);
+        assert_eq!(
+            keymap.lookup(keybinding("Ctrl + 2").keys()),
+            KeyMatch::Exact(Action::ToggleScores)
+        );
+        settings.ui.results.open_through_number = false;
+        let keymap = Keymap::for_mode(&settings, InputMode::Insert);
+        assert_eq!(keymap.lookup(keybinding("Ctrl + 1").keys()), KeyMatch::None);
+
+        let keymap = Keymap::for_mode(&settings, InputMode::Insert).with_completion(&settings);
+        assert_eq!(
+            keymap.lookup(keybinding("Tab").keys()),
+            KeyMatch::Exact(Action::AcceptCompletion)
+        );
+
+        // hint mode leaves letters for the labels
+        let keymap = Keymap::for_mode(&settings, InputMode::Hint);
+        assert_eq!(keymap.lookup(keybinding("q").keys()), KeyMatch::None);
+        assert_eq!(keymap.lookup(keybinding("Ctrl + q").keys()), KeyMatch::None);
+        assert_eq!(
+            keymap.lookup(keybinding("Esc").keys()),
+            KeyMatch::Exact(Action::HideHints)
+        );
+
+        // the messages view closes on Esc instead of quitting
+        let keymap = Keymap::for_mode(&settings, InputMode::Messages);
+        assert_eq!(
+            keymap.lookup(keybinding("Esc").keys()),
+            KeyMatch::Exact(Action::ToggleMessages)
+        );
+        assert_eq!(keymap.lookup(keybinding("Enter").keys()), KeyMatch::None);
+    }
+
+    #[test]
+    fn test_sequence_matches() {
+        let keymap = keymap();
+        let mut matcher = KeySequenceMatcher::new(Duration::from_millis(500));
+        let now = Instant::now();
+
+        assert!(matcher.push(&keymap, key('g'), now).is_empty());
+        assert_eq!(matcher.pending_text(), "g");
+        assert_eq!(
+            matcher.push(&keymap, key('g'), now),
+            vec![KeyOutcome::Action(Action::Navigate(
+                NavigateDirection::Home,
+                1
+            ))]
+        );
+        assert!(!matcher.is_pending());
+
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        matcher.push(&keymap, ctrl('x'), now);
+        assert_eq!(
+            matcher.push(&keymap, ctrl('c'), now),
+            vec![KeyOutcome::Action(Action::Quit)]
+        );
+    }
+
+    #[test]
+    fn test_unmatched_keys_are_forwarded() {
+        let keymap = keymap();
+        let mut matcher = KeySequenceMatcher::new(Duration::from_millis(500));
+        let now = Instant::now();
+
+        assert_eq!(
+            matcher.push(&keymap, key('a'), now),
+            vec![KeyOutcome::Forward(key('a'))]
+        );
+        matcher.push(&keymap, key('g'), now);
+        assert_eq!(
+            matcher.push(&keymap, key('o'), now),
+            vec![KeyOutcome::Forward(key('g')), KeyOutcome::Forward(key('o'))]
+        );
+    }
+
+    #[test]
+    fn test_sequence_timeout() {
+        let keymap = keymap();
+        let mut matcher = KeySequenceMatcher::new(Duration::from_millis(500));
+        let now = Instant::now();
+        let later = now + Duration::from_secs(1);
+
+        // "z" is bound, but also starts "z z", so it waits for the timeout
+        assert!(matcher.push(&keymap, key('z'), now).is_empty());
+        assert!(matcher.expire(&keymap, now).is_empty());
+        assert_eq!(
+            matcher.expire(&keymap, later),
+            vec![KeyOutcome::Action(Action::ToggleWizard)]
+        );
+
+        // an unfinished sequence is typed as normal once it times out
+        matcher.push(&keymap, key('g'), now);
+        assert_eq!(
+            matcher.push(&keymap, key('g'), later),
+            vec![KeyOutcome::Forward(key('g'))]
+        );
+        assert!(matcher.is_pending());
+    }
+}
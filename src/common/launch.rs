@@ -0,0 +1,257 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+// lines of stderr kept when a program fails
+const STDERR_TAIL_LINES: usize = 5;
+// bytes of stderr kept while a program is watched, the lines are taken from the end of these
+const STDERR_TAIL_BYTES: usize = 4096;
+// the launch log is emptied when a program is launched once it's grown past this
+const LAUNCH_LOG_MAX_BYTES: u64 = 1024 * 1024;
+
+/// Why a result couldn't be launched.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub enum LaunchError {
+    NoExecutable,             // nothing to run, i.e. a desktop file without Exec
+    NotFound(String),         // the program doesn't exist
+    PermissionDenied(String), // the program isn't executable
+    // failed soon after starting, see `launch.failure_window`, with the end of its stderr
+    Exited { status: Option<i32>, stderr: String },
+    Other(String),
+}
+
+impl std::fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LaunchError::NoExecutable => write!(f, "nothing to run"),
+            LaunchError::NotFound(program) => write!(f, "{} not found", program),
+            LaunchError::PermissionDenied(program) => {
+                write!(f, "permission denied for {}", program)
+            }
+            LaunchError::Exited { status, stderr } => {
+                match status {
+                    Some(status) => write!(f, "exited with status {}", status)?,
+                    None => write!(f, "killed by a signal")?,
+                }
+                match stderr.lines().last() {
+                    Some(line) => write!(f, ": {}", line),
+                    None => Ok(()),
+                }
+            }
+            LaunchError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// A launched program, watched for a failed exit for a moment after it starts.
+#[derive(Debug)]
+pub struct Launched {
+    child: Child,
+    stderr: Option<File>, // the launch log from where this program's output starts
+    stderr_tail: Vec<u8>, // the last `STDERR_TAIL_BYTES` read from stderr
+}
+
+/// What launching a result did, a program to watch or nothing, i.e. for a calculation.
+pub type LaunchResult = Result<Option<Launched>, LaunchError>;
+
+/// The file launched programs write their stderr to, `$XDG_STATE_HOME/rook/launch.log`.
+pub fn launch_log_path() -> PathBuf {
+    dirs::state_dir()
+        .or_else(dirs::cache_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("rook")
+        .join("launch.log")
+}
+
+/// Start a program in its own session, so it outlives rook.
+///
+/// Stdout is dropped and stderr is appended to the launch log, see [`launch_log_path`]. The
+/// program keeps writing to the file after rook quits, so it never blocks on a full pipe or gets
+/// killed by a broken one.
+pub fn spawn(command: &[&str]) -> LaunchResult {
+    spawn_logged(command, &launch_log_path())
+}
+
+fn spawn_logged(command: &[&str], log: &Path) -> LaunchResult {
+    let (program, args) = command.split_first().ok_or(LaunchError::NoExecutable)?;
+    let (stderr, stderr_reader) = match open_log(log, command) {
+        Ok((writer, reader)) => (Stdio::from(writer), Some(reader)),
+        Err(err) => {
+            log::warn!("Could not open the launch log {:?}: {}", log, err);
+            (Stdio::null(), None)
+        }
+    };
+    let mut exec_command = Command::new(program);
+    exec_command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(stderr);
+    unsafe {
+        exec_command.pre_exec(|| {
+            // Become independent of the parent process
+            if libc::setsid() < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let child = exec_command.spawn().map_err(|err| match err.kind() {
+        ErrorKind::NotFound => LaunchError::NotFound(program.to_string()),
+        ErrorKind::PermissionDenied => LaunchError::PermissionDenied(program.to_string()),
+        _ => LaunchError::Other(err.to_string()),
+    })?;
+    Ok(Some(Launched {
+        child,
+        stderr: stderr_reader,
+        stderr_tail: Vec::new(),
+    }))
+}
+
+// an appending writer for the program, and a reader for rook starting after the command's header
+fn open_log(path: &Path, command: &[&str]) -> std::io::Result<(File, File)> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut writer = OpenOptions::new().create(true).append(true).open(path)?;
+    if writer.metadata()?.len() > LAUNCH_LOG_MAX_BYTES {
+        writer.set_len(0)?;
+    }
+    writeln!(writer, "$ {}", command.join(" "))?;
+    let mut reader = File::open(path)?;
+    reader.seek(SeekFrom::End(0))?;
+    Ok((writer, reader))
+}
+
+impl Launched {
+    /// Wait up to `within` for the program to fail, blocking, so run it off the UI thread.
+    ///
+    /// A program still running, or one that exited successfully, i.e. a launcher handing off
+    /// to another process, counts as launched.
+    pub fn watch(mut self, within: Duration) -> Result<(), LaunchError> {
+        let start = Instant::now();
+        loop {
+            self.read_stderr();
+            match self.child.try_wait() {
+                Ok(Some(status)) if status.success() => return Ok(()),
+                Ok(Some(status)) => {
+                    self.read_stderr();
+                    return Err(LaunchError::Exited {
+                        status: status.code(),
+                        stderr: self.stderr_lines(),
+                    });
+                }
+                Ok(None) if start.elapsed() >= within => {
+                    // stop reading, the program keeps appending to the log
+                    self.stderr = None;
+                    // reaped when it exits, so it doesn't linger while rook stays open
+                    std::thread::spawn(move || self.child.wait());
+                    return Ok(());
+                }
+                Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+                Err(err) => return Err(LaunchError::Other(err.to_string())),
+            }
+        }
+    }
+
+    // read what's been written since the last read, keeping only the end of it
+    fn read_stderr(&mut self) {
+        let Some(stderr) = self.stderr.as_mut() else {
+            return;
+        };
+        let mut buf = [0; STDERR_TAIL_BYTES];
+        while let Ok(read @ 1..) = stderr.read(&mut buf) {
+            self.stderr_tail.extend_from_slice(&buf[..read]);
+            let excess = self.stderr_tail.len().saturating_sub(STDERR_TAIL_BYTES);
+            self.stderr_tail.drain(..excess);
+        }
+    }
+
+    fn stderr_lines(&self) -> String {
+        let text = String::from_utf8_lossy(&self.stderr_tail);
+        let lines: Vec<&str> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a launch log per test, so tests don't write to the real one or read each other's output
+    fn test_log(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rook-{}-{}.log", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn launch(command: &[&str], log: &Path) -> Result<(), LaunchError> {
+        spawn_logged(command, log)?
+            .map_or(Ok(()), |launched| launched.watch(Duration::from_secs(2)))
+    }
+
+    #[test]
+    fn test_launch_errors() {
+        let log = test_log("launch-errors");
+        assert_eq!(launch(&[], &log), Err(LaunchError::NoExecutable));
+        assert_eq!(
+            launch(&["rook-does-not-exist"], &log),
+            Err(LaunchError::NotFound("rook-does-not-exist".into()))
+        );
+        // a file that isn't executable
+        let path = std::env::temp_dir().join(format!("rook-test-{}", std::process::id()));
+        std::fs::write(&path, "").unwrap();
+        let program = path.to_string_lossy().to_string();
+        assert_eq!(
+            launch(&[&program], &log),
+            Err(LaunchError::PermissionDenied(program.clone()))
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        let command = ["sh", "-c", "echo one >&2; echo two >&2; exit 3"];
+        let error = launch(&command, &log).unwrap_err();
+        assert_eq!(
+            error,
+            LaunchError::Exited {
+                status: Some(3),
+                stderr: "one\ntwo".into()
+            }
+        );
+        assert_eq!(error.to_string(), "exited with status 3: two");
+
+        // only the end is kept, and earlier launches in the log aren't part of it
+        let error = launch(&["sh", "-c", "seq 100000 >&2; exit 1"], &log).unwrap_err();
+        assert_eq!(
+            error,
+            LaunchError::Exited {
+                status: Some(1),
+                stderr: "99996\n99997\n99998\n99999\n100000".into()
+            }
+        );
+        std::fs::remove_file(&log).unwrap();
+    }
+
+    #[test]
+    fn test_launch_success() {
+        let log = test_log("launch-success");
+        assert_eq!(launch(&["true"], &log), Ok(()));
+        // still running when the window ends, and writing to the log after it
+        let command = ["sh", "-c", "sleep 0.2; echo late >&2"];
+        let launched = spawn_logged(&command, &log).unwrap().unwrap();
+        assert_eq!(launched.watch(Duration::from_millis(50)), Ok(()));
+        std::thread::sleep(Duration::from_millis(500));
+        let contents = std::fs::read_to_string(&log).unwrap();
+        assert!(contents.ends_with("$ sh -c sleep 0.2; echo late >&2\nlate\n"));
+        std::fs::remove_file(&log).unwrap();
+    }
+}
@@ -2,37 +2,51 @@ use std::{collections::HashMap, hash::Hash, rc::Rc};
 
 use ratatui::{
     Frame,
-    layout::{Constraint, Layout, Margin, Rect, Size},
+    layout::{Alignment, Constraint, Layout, Margin, Rect, Size},
+    style::Style,
+    text::Line,
+    widgets::{Block, Paragraph, Wrap},
 };
 use serde::{Deserialize, Serialize, ser::SerializeStruct};
 
-use crate::{common::module_state::UISection, settings::settings::Settings};
+use crate::{
+    common::module_state::UISection,
+    settings::settings::{PanelPosition, Settings},
+};
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct RootLayout {
     #[serde(skip)]
-    pub left_right_split: u16,
+    pub side_panel_split: u16, // current size of the side panel, moves towards the target
     pub search_split: u16,
 
     pub search_box_area: Rect,
     pub results_box_area: Rect,
     pub wizard_box_area: Rect,
     need_update: bool,
-    pub transitioning_left_right_split: bool,
+    pub transitioning_side_panel_split: bool,
+    #[serde(skip)]
+    side_panel_hidden: bool, // hidden with ToggleWizard
     #[serde(skip)]
-    target_left_right_split: u16,
+    side_panel_position: Option<PanelPosition>, // after breakpoints, None before the first split
+    pub too_small: Option<Size>, // the minimum size, while the terminal is smaller
+    #[serde(skip)]
+    short: bool, // see `ui.layout.short_below_height`
 }
 
 impl Default for RootLayout {
     fn default() -> Self {
         Self {
-            left_right_split: 25,
+            side_panel_split: 0,
             search_split: 3,
             search_box_area: Rect::default(),
             results_box_area: Rect::default(),
             wizard_box_area: Rect::default(),
             need_update: true,
-            transitioning_left_right_split: false,
-            target_left_right_split: 25,
+            transitioning_side_panel_split: false,
+            side_panel_hidden: false,
+            side_panel_position: None,
+            too_small: None,
+            short: false,
         }
     }
 }
@@ -42,73 +56,167 @@ impl RootLayout {
         self.need_update = true;
     }
 
-    pub fn set_left_right_split(&mut self, split: u16) {
-        // self.left_right_split = split;
-        self.target_left_right_split = split;
-        self.transitioning_left_right_split = true;
+    /// Show or hide the side panel, sliding it in or out.
+    pub fn toggle_side_panel(&mut self) {
+        self.side_panel_hidden = !self.side_panel_hidden;
+        self.transitioning_side_panel_split = true;
         self.queue_update();
     }
 
+    // where the side panel goes at this width, None when it's hidden
+    fn side_panel_position(&self, area: Rect, settings: &Settings) -> Option<PanelPosition> {
+        let layout = &settings.ui.layout;
+        if self.side_panel_hidden || area.width < layout.hide_below_width {
+            return None;
+        }
+        let position = match layout.side_panel {
+            PanelPosition::Left if area.width < layout.stack_below_width => PanelPosition::Top,
+            PanelPosition::Right if area.width < layout.stack_below_width => PanelPosition::Bottom,
+            position => position,
+        };
+        // no rows to spare for a panel above or below
+        let takes_rows = layout.sections.contains(&UISection::Wizard)
+            || matches!(position, PanelPosition::Top | PanelPosition::Bottom);
+        if self.short && takes_rows {
+            return None;
+        }
+        Some(position)
+    }
+
+    // the side panel size in cells, across the width or the height of the area
+    fn side_panel_target(&self, area: Rect, settings: &Settings) -> u16 {
+        let stacked = settings.ui.layout.sections.contains(&UISection::Wizard);
+        let total = match self.side_panel_position(area, settings) {
+            None => return 0,
+            _ if stacked => area.height,
+            Some(PanelPosition::Left | PanelPosition::Right) => area.width,
+            Some(PanelPosition::Top | PanelPosition::Bottom) => area.height,
+        };
+        match settings.ui.layout.side_panel_size {
+            Constraint::Percentage(percent) => (total as u32 * percent as u32 / 100) as u16,
+            Constraint::Length(cells) => cells.min(total / 2),
+            _ => 0,
+        }
+    }
+
     pub fn calculate_split(&mut self, area: Rect, settings: &Settings) -> bool {
         let ui_settings = &settings.ui;
         let gap = ui_settings.layout.gap;
 
-        if self.transitioning_left_right_split {
-            let current_split = self.left_right_split;
-
-            if current_split == self.target_left_right_split {
-                self.transitioning_left_right_split = false;
-            } else {
-                // Simple linear transition for demonstration purposes
-                let current_value = current_split;
-                let target_value = self.target_left_right_split;
-
-                let step = if current_value < target_value { 2 } else { -2 };
-                let mut new_value = ((current_value as isize).saturating_add(step)) as u16;
-                if (current_value as isize - target_value as isize).abs() < 2 {
-                    new_value = target_value;
-                }
-                self.left_right_split = new_value;
-            }
+        // nothing fits, a message is shown instead, see [`draw_too_small`]
+        let minimum = calculate_minimum_size(settings);
+        let too_small =
+            (area.width < minimum.width || area.height < minimum.height).then_some(minimum);
+        if too_small != self.too_small {
+            self.too_small = too_small;
+            self.queue_update();
+        }
 
+        // short terminals, i.e. inline with --height, leave out the padding above and below
+        let short = area.height < ui_settings.layout.short_below_height;
+        if short != self.short {
+            self.short = short;
             self.queue_update();
         }
 
         // outer padding of app
         let padding = ui_settings.search.padding;
         let area = area.inner(Margin {
-            vertical: ui_settings.layout.padding,
+            vertical: if short { 0 } else { ui_settings.layout.padding },
             horizontal: ui_settings.layout.padding * 2,
         });
+
+        // slide the side panel towards its size, jump when it moves somewhere else
+        let position = self.side_panel_position(area, settings);
+        let target = self.side_panel_target(area, settings);
+        let moved = position.is_some()
+            && self.side_panel_position.is_some()
+            && position != self.side_panel_position;
+        if position.is_some() {
+            self.side_panel_position = position;
+        }
+        if !self.transitioning_side_panel_split || moved {
+            if self.side_panel_split != target {
+                self.side_panel_split = target;
+                self.queue_update();
+            }
+        } else if self.side_panel_split == target {
+            self.transitioning_side_panel_split = false;
+        } else {
+            let step = 2.min(self.side_panel_split.abs_diff(target));
+            if self.side_panel_split < target {
+                self.side_panel_split += step;
+            } else {
+                self.side_panel_split -= step;
+            }
+            self.queue_update();
+        }
+        let split = self.side_panel_split;
+        let panel_gap = if split > 0 { gap.saturating_sub(1) } else { 0 };
+
         // Calculate search bar height
         let mut search_bar_height = 2 + padding.saturating_mul(2);
         if gap > 0 || ui_settings.layout.sections.first() != Some(&UISection::Search) {
             search_bar_height += 1;
         }
 
+        // the wizard listed in the sections is stacked with them, not a side panel
+        let stacked = ui_settings.layout.sections.contains(&UISection::Wizard);
+        let (wizard_box_area, main_area) = match self.side_panel_position {
+            _ if stacked => (Rect::default(), area),
+            Some(PanelPosition::Left) | None => {
+                let [panel, _, main] = Layout::horizontal([
+                    Constraint::Length(split),
+                    Constraint::Length(panel_gap),
+                    Constraint::Fill(1),
+                ])
+                .areas(area);
+                (panel, main)
+            }
+            Some(PanelPosition::Right) => {
+                let [main, _, panel] = Layout::horizontal([
+                    Constraint::Fill(1),
+                    Constraint::Length(panel_gap),
+                    Constraint::Length(split),
+                ])
+                .areas(area);
+                (panel, main)
+            }
+            Some(PanelPosition::Top) => {
+                let [panel, _, main] = Layout::vertical([
+                    Constraint::Length(split),
+                    Constraint::Length(panel_gap),
+                    Constraint::Fill(1),
+                ])
+                .areas(area);
+                (panel, main)
+            }
+            Some(PanelPosition::Bottom) => {
+                let [main, _, panel] = Layout::vertical([
+                    Constraint::Fill(1),
+                    Constraint::Length(panel_gap),
+                    Constraint::Length(split),
+                ])
+                .areas(area);
+                (panel, main)
+            }
+        };
+
         // Build constraints for each section, inserting gaps between them
         let mut vertical_constraints = Vec::new();
         for (i, section) in ui_settings.layout.sections.iter().enumerate() {
             vertical_constraints.push(match section {
                 UISection::Search => Constraint::Length(search_bar_height),
                 UISection::Results => Constraint::Fill(1),
-                _ => Constraint::Length(0),
+                UISection::Wizard => Constraint::Length(split),
             });
             if i < ui_settings.layout.sections.len() - 1 {
                 vertical_constraints.push(Constraint::Length(gap.saturating_sub(1)));
             }
         }
 
-        // Split the area into chunks
-        let horizontal_layout = Layout::horizontal(vec![
-            Constraint::Length(self.left_right_split),
-            Constraint::Length(gap.saturating_sub(1)),
-            Constraint::Fill(1),
-        ])
-        .split(area);
-
         let vertical_layout = Layout::vertical(vertical_constraints);
-        let chunks = vertical_layout.split(horizontal_layout[2]);
+        let chunks = vertical_layout.split(main_area);
 
         // Map sections to their corresponding chunks (skip gap chunks)
         let mut section_areas = HashMap::new();
@@ -125,7 +233,9 @@ impl RootLayout {
         let results_box_area = *section_areas
             .get(&UISection::Results)
             .unwrap_or(&Rect::new(0, 0, 0, 0));
-        let wizard_box_area = horizontal_layout[0];
+        let wizard_box_area = *section_areas
+            .get(&UISection::Wizard)
+            .unwrap_or(&wizard_box_area);
 
         if (self.search_box_area == search_box_area
             && self.results_box_area == results_box_area
@@ -136,13 +246,9 @@ impl RootLayout {
             return false;
         }
 
-        self.search_box_area = *section_areas
-            .get(&UISection::Search)
-            .unwrap_or(&Rect::new(0, 0, 0, 0));
-        self.results_box_area = *section_areas
-            .get(&UISection::Results)
-            .unwrap_or(&Rect::new(0, 0, 0, 0));
-        self.wizard_box_area = horizontal_layout[0];
+        self.search_box_area = search_box_area;
+        self.results_box_area = results_box_area;
+        self.wizard_box_area = wizard_box_area;
         true
     }
 }
@@ -207,11 +313,124 @@ impl RootLayout {
 //     }
 // }
 
+/// The smallest terminal the layout fits in, from the padding and gap settings.
 pub fn calculate_minimum_size(settings: &Settings) -> Size {
-    let ui_settings = &settings.ui;
-    let ui_gap = ui_settings.layout.gap;
+    let mut min_width = 20;
+    let mut min_height = 5;
+
+    min_height += ((settings.ui.layout.gap.saturating_sub(1)) * 2) + 1;
+    min_height += 1; // the outer padding is left out when short
+
+    min_height += settings.ui.results.padding.saturating_sub(1) * 2;
+    min_height += settings.ui.search.padding * 2;
+
+    min_width += settings.ui.layout.padding * 2;
+    min_width += settings.ui.search.padding * 2;
+    min_width += settings.ui.results.padding.saturating_sub(1) * 2;
     Size {
-        width: 80,
-        height: 24,
+        width: min_width,
+        height: min_height,
+    }
+}
+
+/// Shown instead of the layout while the terminal is smaller than its minimum size.
+pub fn draw_too_small(frame: &mut Frame, minimum: Size, settings: &Settings) {
+    let area = frame.area();
+    let theme = &settings.ui.theme;
+    let text = vec![
+        Line::from("Terminal too small").style(Style::default().fg(theme.text)),
+        Line::from(format!(
+            "{}x{}, needs {}x{}",
+            area.width, area.height, minimum.width, minimum.height
+        ))
+        .style(Style::default().fg(theme.text_muted)),
+    ];
+    let [_, middle, _] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(text.len() as u16),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+    frame.render_widget(
+        Block::default().style(Style::default().bg(theme.background)),
+        area,
+    );
+    frame.render_widget(
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+        middle,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(settings: &Settings, width: u16, height: u16) -> RootLayout {
+        let mut layout = RootLayout::default();
+        layout.calculate_split(Rect::new(0, 0, width, height), settings);
+        layout
+    }
+
+    #[test]
+    fn test_side_panel_positions() {
+        let mut settings = Settings::default();
+        settings.ui.layout.padding = 0;
+        let layout = split(&settings, 100, 30);
+        assert_eq!(layout.wizard_box_area, Rect::new(0, 0, 25, 30));
+        assert_eq!(layout.search_box_area.x, 25);
+
+        settings.ui.layout.side_panel = PanelPosition::Right;
+        settings.ui.layout.side_panel_size = Constraint::Percentage(30);
+        let layout = split(&settings, 100, 30);
+        assert_eq!(layout.wizard_box_area, Rect::new(70, 0, 30, 30));
+        assert_eq!(layout.results_box_area.width, 70);
+
+        settings.ui.layout.side_panel = PanelPosition::Bottom;
+        settings.ui.layout.side_panel_size = Constraint::Length(8);
+        let layout = split(&settings, 100, 30);
+        assert_eq!(layout.wizard_box_area, Rect::new(0, 22, 100, 8));
+
+        // listed in the sections, the wizard is stacked with them
+        settings.ui.layout.sections =
+            vec![UISection::Search, UISection::Wizard, UISection::Results];
+        let layout = split(&settings, 100, 30);
+        assert_eq!(layout.wizard_box_area.y, layout.search_box_area.bottom());
+        assert_eq!(layout.wizard_box_area.height, 8);
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut settings = Settings::default();
+        settings.ui.layout.padding = 0;
+        settings.ui.layout.stack_below_width = 80;
+        settings.ui.layout.hide_below_width = 40;
+        settings.ui.layout.side_panel_size = Constraint::Percentage(20);
+        let layout = split(&settings, 70, 30);
+        assert_eq!(layout.wizard_box_area, Rect::new(0, 0, 70, 6));
+        let layout = split(&settings, 30, 30);
+        assert!(layout.wizard_box_area.is_empty());
+        assert_eq!(layout.search_box_area.width, 30);
+
+        assert_eq!(split(&settings, 30, 30).too_small, None);
+        assert_eq!(
+            split(&settings, 10, 30).too_small,
+            Some(calculate_minimum_size(&settings))
+        );
+    }
+
+    #[test]
+    fn test_short() {
+        let mut settings = Settings::default();
+        settings.ui.layout.padding = 2;
+        settings.ui.layout.side_panel = PanelPosition::Bottom;
+        let layout = split(&settings, 80, 12);
+        assert_eq!(layout.search_box_area.y, 0);
+        assert!(layout.wizard_box_area.is_empty());
+
+        let layout = split(&settings, 80, 30);
+        assert_eq!(layout.search_box_area.y, 2);
+        assert!(!layout.wizard_box_area.is_empty());
     }
 }
@@ -1,4 +1,8 @@
 pub mod application;
+pub mod clipboard;
+pub mod color_depth;
 
+pub mod keymap;
+pub mod launch;
 pub mod layout;
 pub mod module_state;
@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Padding, Paragraph},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    action::{Action, ExecuteMode, InputMode, NavigateDirection},
+    components::{
+        Component,
+        util::{Truncation, centered, fit_width, truncate},
+    },
+    search_modules::ModuleHelp,
+    settings::settings::{EditingMode, KeySequence, Settings},
+};
+
+// keys and what they do, i.e. "Ctrl + q, Esc" and "Quit"
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HelpEntry {
+    keys: String,
+    description: String,
+}
+impl HelpEntry {
+    fn new(keys: &str, description: &str) -> Self {
+        Self {
+            keys: keys.to_string(),
+            description: description.to_string(),
+        }
+    }
+
+    fn matches(&self, filter: &str) -> bool {
+        self.keys.to_lowercase().contains(filter)
+            || self.description.to_lowercase().contains(filter)
+    }
+}
+
+// the entries of a context, i.e. the vim normal mode bindings or a module
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HelpGroup {
+    title: String,
+    entries: Vec<HelpEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HelpLine {
+    Title(String),
+    Entry(HelpEntry),
+    Blank,
+}
+
+/// The help overlay, listing the keybindings of every mode and what each module responds to,
+/// filtered by typing. Toggled with `toggle_help`.
+#[derive(Debug, Default)]
+pub struct Help {
+    settings: Option<Settings>,
+    modules: Vec<ModuleHelp>, // see [`Action::ModuleHelp`]
+    groups: Vec<HelpGroup>,   // built from the settings when opened
+    open: bool,
+    filter: String,
+    scroll: usize,      // first line shown
+    page_height: usize, // lines that fit, set when drawn
+}
+
+impl Help {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the entries of each group matching the filter, under the group titles
+    fn lines(&self) -> Vec<HelpLine> {
+        let filter = self.filter.to_lowercase();
+        let mut lines = vec![];
+        for group in self.groups.iter() {
+            let whole_group = group.title.to_lowercase().contains(&filter);
+            let entries: Vec<&HelpEntry> = group
+                .entries
+                .iter()
+                .filter(|entry| whole_group || entry.matches(&filter))
+                .collect();
+            if entries.is_empty() {
+                continue;
+            }
+            if !lines.is_empty() {
+                lines.push(HelpLine::Blank);
+            }
+            lines.push(HelpLine::Title(group.title.clone()));
+            lines.extend(entries.into_iter().cloned().map(HelpLine::Entry));
+        }
+        lines
+    }
+
+    fn scroll(&mut self, direction: NavigateDirection, amount: usize) {
+        let page = self.page_height.max(1);
+        let max_scroll = self.lines().len().saturating_sub(page);
+        self.scroll = match direction {
+            NavigateDirection::Up => self.scroll.saturating_sub(amount),
+            NavigateDirection::Down => self.scroll.saturating_add(amount),
+            NavigateDirection::PageUp => self.scroll.saturating_sub(page * amount),
+            NavigateDirection::PageDown => self.scroll.saturating_add(page * amount),
+            NavigateDirection::HalfPageUp => self.scroll.saturating_sub(page / 2 * amount),
+            NavigateDirection::HalfPageDown => self.scroll.saturating_add(page / 2 * amount),
+            NavigateDirection::Home => 0,
+            NavigateDirection::End => usize::MAX,
+            NavigateDirection::Left | NavigateDirection::Right => self.scroll,
+        }
+        .min(max_scroll);
+    }
+}
+
+// the keys bound to each action, one entry per action
+fn binding_entries(mapping: HashMap<KeySequence, Action>) -> Vec<HelpEntry> {
+    let mut keys_by_action: HashMap<Action, Vec<String>> = HashMap::new();
+    for (keys, action) in mapping {
+        keys_by_action
+            .entry(action)
+            .or_default()
+            .push(keys.to_string());
+    }
+    let mut entries: Vec<HelpEntry> = keys_by_action
+        .into_iter()
+        .map(|(action, mut keys)| {
+            // single keys before combinations
+            keys.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+            HelpEntry::new(&keys.join(", "), &action.description())
+        })
+        .collect();
+    entries.sort_by(|a, b| a.description.cmp(&b.description));
+    entries
+}
+
+// every context with its entries, from the live keybindings and the modules
+fn help_groups(settings: &Settings, modules: &[ModuleHelp]) -> Vec<HelpGroup> {
+    let keybinds = &settings.keybinds;
+    let group = |title: &str, entries: Vec<HelpEntry>| HelpGroup {
+        title: title.to_string(),
+        entries,
+    };
+
+    let event_mapping = keybinds.get_event_mapping();
+    let secondary_keys: Vec<String> = event_mapping
+        .iter()
+        .filter(|(_, action)| matches!(action, Action::Execute(ExecuteMode::Secondary, None, _)))
+        .map(|(keys, _)| keys.to_string())
+        .chain(settings.input.mouse.then(|| "Right click".to_string()))
+        .collect();
+    let mut global = binding_entries(event_mapping);
+    if settings.ui.results.open_through_number {
+        global.push(HelpEntry::new(
+            "Ctrl + 1..9, Ctrl + 0",
+            "Launch the result with that number",
+        ));
+    }
+    let mut groups = vec![group("Global", global)];
+    if settings.input.editing_mode == EditingMode::Vim {
+        groups.push(group(
+            "Vim normal mode",
+            binding_entries(keybinds.get_mode_mapping(InputMode::Normal)),
+        ));
+        groups.push(group(
+            "Vim insert mode",
+            binding_entries(keybinds.get_mode_mapping(InputMode::Insert)),
+        ));
+    }
+    groups.push(group(
+        "While a completion is shown",
+        binding_entries(keybinds.get_completion_mapping()),
+    ));
+    let mut hint = binding_entries(keybinds.get_mode_mapping(InputMode::Hint));
+    hint.push(HelpEntry::new(
+        &settings.ui.results.hint_keys,
+        "Type a label to launch its result",
+    ));
+    groups.push(group("While hints are shown", hint));
+    groups.push(group(
+        "Messages view",
+        binding_entries(keybinds.get_mode_mapping(InputMode::Messages)),
+    ));
+    let mut help = binding_entries(keybinds.get_mode_mapping(InputMode::Help));
+    help.push(HelpEntry::new("Type", "Filter this help"));
+    groups.push(group("Help", help));
+
+    for module in modules {
+        let mut entries = vec![];
+        if let Some(prefix) = &module.prefix {
+            entries.push(HelpEntry::new(
+                prefix,
+                &format!("Search only {}", module.title),
+            ));
+        }
+        for trigger in module.triggers.iter() {
+            entries.push(HelpEntry::new(&trigger.example, &trigger.description));
+        }
+        if let Some(secondary) = &module.secondary_action {
+            entries.push(HelpEntry::new(&secondary_keys.join(", "), secondary));
+        }
+        groups.push(group(&module.title, entries));
+    }
+    groups.retain(|group| !group.entries.is_empty());
+    groups
+}
+
+impl Component for Help {
+    fn register_settings_handler(&mut self, settings: Settings) -> Result<()> {
+        self.settings = Some(settings);
+        Ok(())
+    }
+
+    // keys that aren't bound in [keybinds.help] type the filter
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if !self.open || key.kind != KeyEventKind::Press {
+            return Ok(None);
+        }
+        match key.code {
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.filter.push(c);
+                self.scroll = 0;
+            }
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.scroll = 0;
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn captures_mouse(&self, _mouse: &MouseEvent) -> bool {
+        self.open
+    }
+
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
+        match mouse.kind {
+            MouseEventKind::ScrollUp if self.open => self.scroll(NavigateDirection::Up, 1),
+            MouseEventKind::ScrollDown if self.open => self.scroll(NavigateDirection::Down, 1),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::ModuleHelp(modules) => self.modules = modules,
+            Action::InputMode(mode) => {
+                let open = mode == InputMode::Help;
+                if open && !self.open {
+                    let settings = self.settings.as_ref().unwrap();
+                    self.groups = help_groups(settings, &self.modules);
+                    self.filter.clear();
+                    self.scroll = 0;
+                }
+                self.open = open;
+            }
+            Action::Navigate(direction, amount) if self.open => self.scroll(direction, amount),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        if !self.open {
+            return Ok(());
+        }
+        let theme = &self.settings.as_ref().unwrap().ui.theme;
+        let popup = centered(
+            area,
+            area.width.saturating_sub(4).min(90),
+            area.height.saturating_sub(2),
+        );
+        if popup.width < 20 || popup.height < 5 {
+            return Ok(());
+        }
+        let lines = self.lines();
+        // the filter and a blank line above the entries
+        self.page_height = popup.height.saturating_sub(4) as usize;
+        self.scroll = self
+            .scroll
+            .min(lines.len().saturating_sub(self.page_height));
+
+        let mut block = Block::bordered()
+            .border_type(theme.border_type)
+            .border_style(Style::default().fg(theme.border))
+            .title(Span::styled(" Help ", Style::default().fg(theme.title)))
+            .padding(Padding::horizontal(1))
+            .style(Style::default().bg(theme.background).fg(theme.text));
+        if lines.len() > self.page_height {
+            block = block.title_bottom(
+                Line::from(format!(
+                    " {}-{} / {} ",
+                    self.scroll + 1,
+                    (self.scroll + self.page_height).min(lines.len()),
+                    lines.len()
+                ))
+                .alignment(Alignment::Right)
+                .style(Style::default().fg(theme.text_muted)),
+            );
+        }
+        let width = popup.width.saturating_sub(4) as usize;
+        let keys_width = lines
+            .iter()
+            .filter_map(|line| match line {
+                HelpLine::Entry(entry) => Some(entry.keys.width()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+            .min(width / 2);
+
+        let mut text = vec![
+            if self.filter.is_empty() {
+                Line::styled("Type to filter", Style::default().fg(theme.text_muted))
+            } else {
+                Line::styled(
+                    format!("Filter: {}", self.filter),
+                    Style::default().fg(theme.text_accent),
+                )
+            },
+            Line::default(),
+        ];
+        if lines.is_empty() {
+            text.push(Line::styled(
+                format!("Nothing matches {:?}", self.filter),
+                Style::default().fg(theme.text_muted),
+            ));
+        }
+        for line in lines.into_iter().skip(self.scroll).take(self.page_height) {
+            text.push(match line {
+                HelpLine::Title(title) => Line::styled(
+                    truncate(&title, width, Truncation::End),
+                    Style::default()
+                        .fg(theme.title)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                HelpLine::Entry(entry) => Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "  {}  ",
+                            fit_width(&entry.keys, keys_width, Truncation::End)
+                        ),
+                        Style::default().fg(theme.accent),
+                    ),
+                    Span::raw(truncate(
+                        &entry.description,
+                        width.saturating_sub(keys_width + 4),
+                        Truncation::End,
+                    )),
+                ]),
+                HelpLine::Blank => Line::default(),
+            });
+        }
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(text).block(block), popup);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search_modules::Trigger;
+
+    fn help() -> Help {
+        let mut help = Help::new();
+        help.register_settings_handler(Settings::default()).unwrap();
+        help.update(Action::ModuleHelp(vec![ModuleHelp {
+            title: "Calculator".into(),
+            prefix: Some("=".into()),
+            triggers: vec![Trigger::new("2*(3+4)", "Calculate")],
+            secondary_action: None,
+        }]))
+        .unwrap();
+        help.update(Action::InputMode(InputMode::Help)).unwrap();
+        help
+    }
+
+    #[test]
+    fn test_help_groups() {
+        let help = help();
+        let titles: Vec<&str> = help.groups.iter().map(|g| g.title.as_str()).collect();
+        assert_eq!(titles.first(), Some(&"Global"));
+        assert!(titles.contains(&"Calculator"));
+        // vim bindings only show with vim editing
+        assert!(!titles.contains(&"Vim normal mode"));
+
+        let global = &help.groups[0].entries;
+        let quit = global.iter().find(|e| e.description == "Quit").unwrap();
+        assert_eq!(quit.keys, "Esc, Ctrl + q");
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut help = help();
+        for c in "calc".chars() {
+            help.handle_key_event(KeyEvent::from(KeyCode::Char(c)))
+                .unwrap();
+        }
+        // a matching group title keeps all of its entries
+        assert_eq!(
+            help.lines(),
+            vec![
+                HelpLine::Title("Calculator".into()),
+                HelpLine::Entry(HelpEntry::new("=", "Search only Calculator")),
+                HelpLine::Entry(HelpEntry::new("2*(3+4)", "Calculate")),
+            ]
+        );
+
+        help.filter = "ctrl + q".into();
+        assert_eq!(
+            help.lines(),
+            vec![
+                HelpLine::Title("Global".into()),
+                HelpLine::Entry(HelpEntry::new("Esc, Ctrl + q", "Quit")),
+            ]
+        );
+        help.handle_key_event(KeyEvent::from(KeyCode::Backspace))
+            .unwrap();
+        assert_eq!(help.filter, "ctrl + ");
+    }
+}
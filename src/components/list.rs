@@ -1,18 +1,24 @@
 use color_eyre::Result;
-use crossterm::event::{KeyCode, KeyEventKind, MouseButton, MouseEventKind};
+use crossterm::event::{KeyModifiers, MouseButton, MouseEventKind};
 use ratatui::{
     buffer::Buffer,
     layout::{Position, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{ListItem, StatefulWidget},
+    widgets::{ListItem, Scrollbar, ScrollbarOrientation, ScrollbarState, StatefulWidget},
 };
 
+use std::time::Instant;
+use unicode_width::UnicodeWidthStr;
+
 use crate::{
-    action::Action,
+    action::{Action, ExecuteMode, NavigateDirection},
     components::{
         list,
-        util::{IconMode, calculate_color_fade, loading_spinner, number_to_icon},
+        util::{
+            IconMode, calculate_color_fade, hint_labels, layout_columns, loading_spinner,
+            number_to_icon,
+        },
     },
     search_modules::ListResult,
     settings,
@@ -25,7 +31,36 @@ pub struct ListState {
     selected: Option<usize>,
     area: Rect,
     results: Option<Vec<ListResult>>,
+    hints: Option<Vec<String>>, // hint labels of the visible results, while in hint mode
+    typed_hint: String,
+    scrollbar_area: Rect, // empty when the results fit
+    dragging_scrollbar: bool,
+    grouped: bool,  // a header above each module's results, see `ui.results.display`
+    rows: Vec<Row>, // what is drawn on each line, the offset counts these
+    executing: Option<(usize, Instant)>, // the result being launched and since when, for the spinner
+    marked: Vec<ListResult>, // marked for launching together, in the order they were marked
+}
+
+// marks outlive the results they were made on, so results are told apart by module and name
+pub fn same_result(a: &ListResult, b: &ListResult) -> bool {
+    a.group == b.group && a.result == b.result
+}
+
+// a line of the list, selection and launching only ever use result indices
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Row {
+    Header(String),
+    Result(usize),
 }
+
+/// What a key typed in hint mode did, see [`ListState::type_hint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HintMatch {
+    Partial,        // start of at least one label, wait for the next key
+    Launch(Action), // a full label
+    None,           // not a label, the hints should be hidden
+}
+
 impl ListState {
     pub fn scroll_up_by(&mut self, amount: u16) {
         let selected = self.selected.unwrap_or_default();
@@ -42,125 +77,349 @@ impl ListState {
         self.select(Some(0));
     }
     pub fn select(&mut self, index: Option<usize>) {
+        // keep the selection within the results, i.e. select_last
+        let index = match (index, &self.results) {
+            (Some(index), Some(results)) if !results.is_empty() => {
+                Some(index.min(results.len() - 1))
+            }
+            (index, _) => index,
+        };
         self.selected = index;
         log::info!("Selected index: {:?}", self.selected);
-        if index.is_none() {
+        self.scroll_to_selected();
+    }
+    // move the page so the selection is on it
+    fn scroll_to_selected(&mut self) {
+        if self.selected.is_none() {
             self.offset = 0;
         } else if let Some(selected) = self.selected {
             let area_height = self.area.height as usize;
-            if selected < self.offset {
-                self.offset = selected;
-            } else if selected >= self.offset + area_height {
-                self.offset = selected.saturating_sub(area_height).saturating_add(1);
+            let row = self.row_of(selected);
+            // scrolling up to the first result of a group also shows its header
+            let top = match row.checked_sub(1).and_then(|above| self.rows.get(above)) {
+                Some(Row::Header(_)) => row - 1,
+                _ => row,
+            };
+            if top < self.offset {
+                self.offset = top;
+            } else if row >= self.offset + area_height {
+                self.offset = row.saturating_sub(area_height).saturating_add(1);
             }
         }
     }
+    /// Select the first result of the next group.
+    pub fn select_next_group(&mut self) {
+        let selected = self.selected.unwrap_or_default();
+        if let Some(start) = self
+            .group_starts()
+            .into_iter()
+            .find(|start| *start > selected)
+        {
+            self.select(Some(start));
+        }
+    }
+    /// Select the first result of the group, or of the previous group when already there.
+    pub fn select_previous_group(&mut self) {
+        let selected = self.selected.unwrap_or_default();
+        let starts = self.group_starts();
+        if let Some(start) = starts.into_iter().rev().find(|start| *start < selected) {
+            self.select(Some(start));
+        }
+    }
+    // index of the first result of each group
+    fn group_starts(&self) -> Vec<usize> {
+        let results = self.results().map_or(&[][..], |results| results.as_slice());
+        (0..results.len())
+            .filter(|i| *i == 0 || results[i - 1].group != results[*i].group)
+            .collect()
+    }
     pub const fn selected(&self) -> Option<usize> {
         self.selected
     }
+    /// Show a spinner in place of the number of the result being launched, None to stop.
+    pub fn set_executing(&mut self, index: Option<usize>) {
+        self.executing = index.map(|index| (index, Instant::now()));
+    }
+    pub fn executing(&self) -> Option<usize> {
+        self.executing.map(|(index, _)| index)
+    }
     pub const fn offset(&self) -> usize {
         self.offset
     }
 
+    /// Show new results, keeping the marks so results of several queries can be launched together.
     pub fn set_results(&mut self, results: Vec<ListResult>) {
+        // a marked result found again is kept as it is now, i.e. with a new score
+        for marked in self.marked.iter_mut() {
+            if let Some(result) = results.iter().find(|result| same_result(result, marked)) {
+                *marked = result.clone();
+            }
+        }
         self.results = Some(results);
+        self.build_rows();
+    }
+    /// Mark a result, or unmark it when it is marked, if its module allows it.
+    pub fn toggle_mark(&mut self, index: usize) {
+        let Some(result) = self.results().and_then(|results| results.get(index)) else {
+            return;
+        };
+        if let Some(position) = self.marked.iter().position(|m| same_result(m, result)) {
+            self.marked.remove(position);
+        } else if result.markable {
+            self.marked.push(result.clone());
+        }
+    }
+    pub fn marked(&self) -> &[ListResult] {
+        &self.marked
+    }
+    fn is_marked(&self, index: usize) -> bool {
+        let Some(result) = self.results().and_then(|results| results.get(index)) else {
+            return false;
+        };
+        self.marked.iter().any(|marked| same_result(marked, result))
+    }
+    pub fn set_grouped(&mut self, grouped: bool) {
+        self.grouped = grouped;
+        self.build_rows();
+    }
+    fn build_rows(&mut self) {
+        let results = self.results.as_deref().unwrap_or_default();
+        let mut rows = Vec::with_capacity(results.len());
+        for (i, result) in results.iter().enumerate() {
+            let new_group = i == 0 || results[i - 1].group != result.group;
+            if self.grouped && new_group && !result.group.is_empty() {
+                rows.push(Row::Header(result.group.clone()));
+            }
+            rows.push(Row::Result(i));
+        }
+        self.rows = rows;
+    }
+    // the line a result is drawn on
+    fn row_of(&self, index: usize) -> usize {
+        self.rows
+            .iter()
+            .position(|row| *row == Row::Result(index))
+            .unwrap_or(index)
+    }
+    /// The line the selected result is drawn on, None when it's not on the page.
+    pub fn selected_area(&self) -> Option<Rect> {
+        let line = self.row_of(self.selected?).checked_sub(self.offset)?;
+        (line < self.area.height as usize).then(|| Rect {
+            y: self.area.y + line as u16,
+            height: 1,
+            ..self.area
+        })
     }
     pub fn results(&self) -> Option<&Vec<ListResult>> {
         self.results.as_ref()
     }
 
-    pub fn handle_key_event(
-        &mut self,
-        key_event: &crossterm::event::KeyEvent,
-        settings: &settings::settings::Settings,
-    ) -> Result<Option<Action>> {
-        if key_event.kind != KeyEventKind::Press {
-            return Ok(None);
+    // indices of the results on the page, from the offset to the bottom of the list
+    fn visible_results(&self) -> Vec<usize> {
+        self.rows
+            .iter()
+            .skip(self.offset)
+            .take(self.area.height as usize)
+            .filter_map(|row| match row {
+                Row::Result(index) => Some(*index),
+                Row::Header(_) => None,
+            })
+            .collect()
+    }
+    fn visible_len(&self) -> usize {
+        self.visible_results().len()
+    }
+
+    /// Label the visible results with `keys`, i.e. `ui.results.hint_keys`.
+    pub fn show_hints(&mut self, keys: &str) {
+        self.hints = Some(hint_labels(self.visible_len(), keys));
+        self.typed_hint.clear();
+    }
+    pub fn hide_hints(&mut self) {
+        self.hints = None;
+        self.typed_hint.clear();
+    }
+    /// The hint label of a visible row and how much of it has been typed.
+    pub fn hint(&self, row: usize) -> Option<(&str, usize)> {
+        let label = self.hints.as_ref()?.get(row)?;
+        let typed = if label.starts_with(&self.typed_hint) {
+            self.typed_hint.len()
+        } else {
+            0
+        };
+        Some((label, typed))
+    }
+
+    pub fn type_hint(&mut self, c: char) -> HintMatch {
+        let Some(hints) = &self.hints else {
+            return HintMatch::None;
+        };
+        self.typed_hint.push(c);
+        if let Some(row) = hints.iter().position(|label| *label == self.typed_hint) {
+            return match self.execute(ExecuteMode::Primary, Some(row), false) {
+                Some(action) => HintMatch::Launch(action),
+                None => HintMatch::None,
+            };
+        }
+        if hints
+            .iter()
+            .any(|label| label.starts_with(&self.typed_hint))
+        {
+            HintMatch::Partial
+        } else {
+            HintMatch::None
         }
-        match key_event.code {
-            KeyCode::Up => {
-                self.scroll_up_by(1);
+    }
 
-                Ok(None)
-            }
-            KeyCode::Down => {
-                self.scroll_down_by(1);
+    // number of lines in `count` pages divided into `parts`, at least one
+    fn page(&self, parts: u16, count: usize) -> u16 {
+        (self.area.height / parts)
+            .max(1)
+            .saturating_mul(count.min(u16::MAX as usize) as u16)
+    }
 
-                Ok(None)
+    /// Handle list navigation and execution, returning the action to launch a result.
+    pub fn handle_action(&mut self, action: &Action) -> Option<Action> {
+        match action {
+            Action::Navigate(direction, amount) => {
+                match direction {
+                    NavigateDirection::Up => self.scroll_up_by(*amount as u16),
+                    NavigateDirection::Down => self.scroll_down_by(*amount as u16),
+                    NavigateDirection::Home => self.select_first(),
+                    NavigateDirection::End => self.select_last(),
+                    NavigateDirection::PageUp => self.scroll_up_by(self.page(1, *amount)),
+                    NavigateDirection::PageDown => self.scroll_down_by(self.page(1, *amount)),
+                    NavigateDirection::HalfPageUp => self.scroll_up_by(self.page(2, *amount)),
+                    NavigateDirection::HalfPageDown => self.scroll_down_by(self.page(2, *amount)),
+                    _ => {}
+                }
+                None
+            }
+            Action::NextGroup => {
+                self.select_next_group();
+                None
+            }
+            Action::PreviousGroup => {
+                self.select_previous_group();
+                None
             }
-            KeyCode::Enter => {
+            // marks and moves on, so several results are marked by repeating the key
+            Action::ToggleMark => {
                 if let Some(selected) = self.selected() {
-                    if let Some(results) = &self.results() {
-                        return Ok(Some(Action::ItemExecute(results[selected].clone())));
-                    }
+                    self.toggle_mark(selected);
+                    self.scroll_down_by(1);
                 }
-                Ok(None)
+                None
+            }
+            // with marked results the selection launches all of them, which uses up the marks
+            Action::Execute(ExecuteMode::Primary, None, keep_open) if !self.marked.is_empty() => {
+                let marked = std::mem::take(&mut self.marked);
+                Some(Action::ItemExecuteAll(marked, *keep_open))
             }
-            _ => Ok(None),
+            Action::Execute(mode, index, keep_open) => self.execute(*mode, *index, *keep_open),
+            _ => None,
+        }
+    }
+
+    // launch the selected result, or the result at an index on the visible page
+    fn execute(&self, mode: ExecuteMode, index: Option<usize>, keep_open: bool) -> Option<Action> {
+        let index = match index {
+            Some(index) => *self.visible_results().get(index)?,
+            None => self.selected()?,
+        };
+        self.launch(mode, index, keep_open)
+    }
+
+    /// The action to launch the result at an index of the results.
+    pub fn launch(&self, mode: ExecuteMode, index: usize, keep_open: bool) -> Option<Action> {
+        let result = self.results()?.get(index)?;
+        match mode {
+            // a command runs inside rook, it isn't launched
+            ExecuteMode::Primary if result.action.is_some() => result.action.as_deref().cloned(),
+            ExecuteMode::Primary => Some(Action::ItemExecute(result.clone(), keep_open)),
+            ExecuteMode::Secondary => match &result.secondary {
+                // run the secondary action through the usual launch path
+                Some(secondary) => Some(Action::ItemExecute(
+                    ListResult {
+                        launch: secondary.clone(),
+                        ..result.clone()
+                    },
+                    keep_open,
+                )),
+                None => {
+                    log::info!("{} has no secondary action", result.result);
+                    None
+                }
+            },
+        }
+    }
+
+    /// The index of the result at a position on the screen, if there is one.
+    pub fn row_at(&self, position: Position) -> Option<usize> {
+        if !self.area.contains(position) {
+            return None;
+        }
+        let row = self.offset + position.y.saturating_sub(self.area.y) as usize;
+        match self.rows.get(row)? {
+            Row::Result(index) => Some(*index),
+            Row::Header(_) => None,
         }
     }
 
+    // select the result at the same fraction of the list as the row is of the scrollbar
+    fn scroll_to(&mut self, row: u16) {
+        let len = self.results().map_or(0, |results| results.len());
+        if len == 0 {
+            return;
+        }
+        let track = self.scrollbar_area.height.saturating_sub(1).max(1) as usize;
+        let row = row.saturating_sub(self.scrollbar_area.y).min(track as u16) as usize;
+        self.select(Some((row * (len - 1) + track / 2) / track));
+    }
+
     pub fn handle_mouse_event(
         &mut self,
         mouse_event: &crossterm::event::MouseEvent,
-        settings: &settings::settings::Settings,
     ) -> Result<Option<Action>> {
-        let results = &self.results().unwrap();
+        let position = Position {
+            x: mouse_event.column,
+            y: mouse_event.row,
+        };
         match mouse_event.kind {
-            crossterm::event::MouseEventKind::ScrollDown => {
-                self.scroll_down_by(1);
-                // }
-                Ok(None)
+            MouseEventKind::ScrollDown => self.scroll_down_by(1),
+            MouseEventKind::ScrollUp => self.scroll_up_by(1),
+            MouseEventKind::Down(MouseButton::Left) if self.scrollbar_area.contains(position) => {
+                self.dragging_scrollbar = true;
+                self.scroll_to(position.y);
             }
-            crossterm::event::MouseEventKind::ScrollUp => {
-                self.scroll_up_by(1);
-                // }
-                Ok(None)
+            // keeps following the mouse when it leaves the scrollbar
+            MouseEventKind::Drag(MouseButton::Left) if self.dragging_scrollbar => {
+                self.scroll_to(position.y)
             }
-            MouseEventKind::Moved => {
-                let relative_y = mouse_event.row.saturating_sub(self.area.y);
-                if !self.area.contains(Position {
-                    x: mouse_event.column,
-                    y: mouse_event.row,
-                }) {
-                    self.select(None);
-                    return Ok(None);
-                }
-                let index = relative_y as usize + self.offset();
-
-                // Adjust the index based on the current offset
-                if index < results.len() {
+            MouseEventKind::Up(MouseButton::Left) => self.dragging_scrollbar = false,
+            MouseEventKind::Down(MouseButton::Left)
+                if mouse_event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                if let Some(index) = self.row_at(position) {
                     self.select(Some(index));
-                } else {
-                    self.select(None);
+                    self.toggle_mark(index);
                 }
-
-                Ok(None)
             }
-            MouseEventKind::Down(button) => {
-                if button == MouseButton::Right {
-                    log::trace!("Right click, ignoring");
-                    return Ok(None);
-                }
-                if button == MouseButton::Middle {
-                    log::trace!("Middle click, ignoring");
-                    return Ok(None);
-                }
-                if !self.area.contains(Position {
-                    x: mouse_event.column,
-                    y: mouse_event.row,
-                }) {
-                    return Ok(None);
+            // hovering selects, leaving the list keeps the last selection
+            MouseEventKind::Moved => {
+                if let Some(index) = self.row_at(position) {
+                    self.select(Some(index));
                 }
-                if let Some(selected) = self.selected() {
-                    if let Some(results) = &self.results() {
-                        return Ok(Some(Action::ItemExecute(results[selected].clone())));
-                    }
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(index) = self.row_at(position) {
+                    self.select(Some(index));
+                    return Ok(self.launch(ExecuteMode::Primary, index, false));
                 }
-                Ok(None)
             }
-            _ => Ok(None),
+            _ => {}
         }
+        Ok(None)
     }
 }
 
@@ -186,12 +445,24 @@ impl List {
     ) -> Vec<ListItem<'static>> {
         let theme = settings.ui.theme.get_results_colors();
         let available_height = area.height as usize;
-        let mut i = 1;
-        let results = list_state.results().unwrap();
-        let items: Vec<ListItem<'static>> = results
+        let Some(results) = list_state.results() else {
+            return vec![]; // nothing searched yet
+        };
+        let visible = list_state.visible_results();
+        // only the rows on the page
+        let items: Vec<ListItem<'static>> = list_state
+            .rows
             .iter()
-            // .map(|(score, idx)| {
-            .map(|item| {
+            .enumerate()
+            .skip(list_state.offset())
+            .take(available_height)
+            .map(|(line, row)| {
+                let index = match row {
+                    Row::Header(title) => return Self::header(title, settings, area.width),
+                    Row::Result(index) => *index,
+                };
+                let i = line + 1;
+                let item = &results[index];
                 let result = &item.result;
                 // let score = &r.score;
                 let score = &item.score.to_string();
@@ -199,23 +470,43 @@ impl List {
                 let mut muted_color = theme.text_muted.unwrap();
                 let mut selected_color = theme.accent.unwrap();
 
-                // get number icon
+                // get number icon, counted from the top of the page to match Ctrl + number
                 // mode configurable in settings
-                let mut prepend_icon = number_to_icon(i, number_mode);
+                let row = visible.iter().position(|visible| *visible == index); // row on the page
+                let hint = row.and_then(|row| list_state.hint(row));
+                let mut prepend_icon = match (row, hint) {
+                    (_, Some((label, _))) => label.to_string(),
+                    (Some(row), None) if settings.ui.results.numbered => {
+                        number_to_icon(row + 1, number_mode)
+                    }
+                    _ => " ".to_string(),
+                };
                 // if executing, use loading spinner
-                if executing_item.is_some() && i == executing_item.unwrap() + 1 {
+                if executing_item == Some(index) {
                     prepend_icon = loading_spinner(tick);
                 }
 
-                // pad score to end i.e. "App Name       123"
-                let line_width = area.width as usize;
-                let mut name_width = line_width.saturating_sub(score.len() - 1);
-                if prepend_icon.trim().is_empty() {
-                    name_width = name_width.saturating_sub(4); // extra space if no icon
+                // pad score to end i.e. "App Name       123", after the icon and a space
+                let icon_width = hint
+                    .map_or(prepend_icon.as_str(), |(label, _)| label)
+                    .width()
+                    + 1;
+                let score = if settings.ui.results.show_scores {
+                    score.as_str()
                 } else {
-                    name_width = name_width.saturating_sub(prepend_icon.len() + 1); // +1 for space
-                }
-                let padded_name = format!("{:<width$}", result, width = name_width);
+                    ""
+                };
+                // a marked result has a dot before its name
+                let marked = list_state.is_marked(index);
+                let mark = if marked { "● " } else { "" };
+                // i.e. the keybinding of a command, before the score
+                let detail = item.detail.as_deref().unwrap_or_default();
+                let (padded_name, score) = layout_columns(
+                    result,
+                    &[detail, score],
+                    (area.width as usize).saturating_sub(icon_width + mark.width()),
+                    settings.ui.results.truncation,
+                );
 
                 // calculate list color fade
                 if settings.ui.results.fade_color_at_bottom && available_height >= 10 {
@@ -231,56 +522,381 @@ impl List {
                     );
                 }
 
-                // construct line
-                let line = Line::from(vec![
-                    // number index
-                    Span::styled(
+                // number index, or the hint label with the typed part muted
+                let icon_spans = match hint {
+                    Some((label, typed)) => vec![
+                        Span::styled(label[..typed].to_string(), Style::default().fg(muted_color)),
+                        Span::styled(
+                            format!("{} ", &label[typed..]),
+                            Style::default()
+                                .fg(selected_color)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                    ],
+                    None => vec![Span::styled(
                         format!("{} ", prepend_icon),
                         Style::default().fg(selected_color),
-                    ),
-                    Span::styled(padded_name.clone(), Style::default().fg(text_color)), // name
-                    if settings.ui.results.show_scores {
-                        Span::styled(score.clone(), Style::default().fg(muted_color))
-                    } else {
-                        Span::raw("")
-                    },
-                ])
-                .style(Style::default().bg(
-                    if list_state.selected() == Some(i.saturating_sub(1)) {
-                        selected_color
-                    } else {
-                        theme.background.unwrap()
-                    },
-                ));
-                i += 1;
+                    )],
+                };
+
+                // the selected line is drawn on the accent colour
+                let selected = list_state.selected() == Some(index);
+                let mark_color = if selected { text_color } else { selected_color };
+                let name_style = if marked {
+                    Style::default().fg(text_color).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(text_color)
+                };
+
+                // construct line
+                let line = Line::from_iter(icon_spans.into_iter().chain([
+                    Span::styled(mark, Style::default().fg(mark_color)),
+                    Span::styled(padded_name, name_style), // name
+                    Span::styled(score, Style::default().fg(muted_color)),
+                ]))
+                .style(Style::default().bg(if selected {
+                    selected_color
+                } else {
+                    theme.background.unwrap()
+                }));
                 ListItem::new(line)
             })
             .collect::<Vec<ListItem>>();
 
         items
     }
+
+    // the title of a group, followed by a rule to the end of the line
+    fn header(
+        title: &str,
+        settings: &crate::settings::settings::Settings,
+        width: u16,
+    ) -> ListItem<'static> {
+        let theme = settings.ui.theme.get_results_colors();
+        let rule_width = (width as usize).saturating_sub(title.width() + 1);
+        ListItem::new(Line::from(vec![
+            Span::styled(
+                format!("{} ", title),
+                Style::default()
+                    .fg(theme.accent.unwrap())
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                "─".repeat(rule_width),
+                Style::default().fg(theme.text_muted.unwrap()),
+            ),
+        ]))
+        .style(Style::default().bg(theme.background.unwrap()))
+    }
 }
 
 impl StatefulWidget for List {
     type State = ListState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let settings = self.settings.as_ref().unwrap();
+        let len = state.rows.len();
+
+        // scrollbar in the last column, apart from the scores, only when the results don't fit
+        let mut area = area;
+        state.scrollbar_area = Rect::default();
+        if settings.ui.results.scrollbar && len > area.height as usize && area.width > 2 {
+            area.width -= 2;
+            state.scrollbar_area = Rect {
+                x: area.right() + 1,
+                width: 1,
+                ..area
+            };
+        }
         state.area = area;
-        // rendering logic here
+        state.scroll_to_selected(); // the height may have changed
 
+        // the spinner turns every 100 ms
+        let tick = state
+            .executing
+            .map_or(0, |(_, since)| since.elapsed().as_millis() as u64 / 100);
         let items = Self::construct_list(
             state,
             self.settings.as_ref().unwrap(),
             self.settings.as_ref().unwrap().ui.results.number_mode,
-            None,
+            state.executing(),
             area,
-            0,
+            tick,
         );
 
         let list = ratatui::widgets::List::new(items);
-        let mut true_state = ratatui::widgets::ListState::default().with_offset(state.offset());
-        true_state.select(state.selected());
+        // the items start at the offset already
+        let mut true_state = ratatui::widgets::ListState::default();
+        true_state.select(
+            state
+                .selected()
+                .and_then(|selected| state.row_of(selected).checked_sub(state.offset())),
+        );
         // let state = ratatui::widgets::ListState::default();
         list.render(area, buf, &mut true_state);
+
+        if !state.scrollbar_area.is_empty() {
+            let theme = settings.ui.theme.get_results_colors();
+            let mut scrollbar_state = ScrollbarState::new(len.saturating_sub(area.height as usize))
+                .viewport_content_length(area.height as usize)
+                .position(state.offset());
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .track_style(Style::default().fg(theme.text_muted.unwrap()))
+                .thumb_style(Style::default().fg(theme.accent.unwrap()))
+                .render(state.scrollbar_area, buf, &mut scrollbar_state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyModifiers, MouseEvent};
+
+    fn list_state(len: usize) -> ListState {
+        let mut state = ListState {
+            area: Rect::new(2, 5, 20, 4),
+            scrollbar_area: Rect::new(22, 5, 1, 4),
+            ..Default::default()
+        };
+        state.set_results(
+            (0..len)
+                .map(|i| ListResult {
+                    result: i.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+        );
+        state
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn test_mouse_rows() {
+        let mut state = list_state(10);
+        state.select(Some(6)); // scrolls the page down to 3..7
+        assert_eq!(state.offset(), 3);
+        assert_eq!(state.row_at(Position::new(2, 5)), Some(3));
+        assert_eq!(state.row_at(Position::new(10, 8)), Some(6));
+        assert_eq!(state.row_at(Position::new(10, 9)), None);
+        assert_eq!(state.row_at(Position::new(1, 5)), None);
+
+        state
+            .handle_mouse_event(&mouse(MouseEventKind::Moved, 4, 6))
+            .unwrap();
+        assert_eq!(state.selected(), Some(4));
+        // leaving the list keeps the selection
+        state
+            .handle_mouse_event(&mouse(MouseEventKind::Moved, 0, 0))
+            .unwrap();
+        assert_eq!(state.selected(), Some(4));
+
+        let click = mouse(MouseEventKind::Down(MouseButton::Left), 4, 7);
+        match state.handle_mouse_event(&click).unwrap() {
+            Some(Action::ItemExecute(result, _)) => assert_eq!(result.result, "5"),
+            action => panic!("expected a launch, got {:?}", action),
+        }
+    }
+
+    #[test]
+    fn test_scrollbar_drag() {
+        let mut state = list_state(31);
+        let event = |state: &mut ListState, kind, row| {
+            state.handle_mouse_event(&mouse(kind, 22, row)).unwrap();
+        };
+        event(&mut state, MouseEventKind::Down(MouseButton::Left), 8);
+        assert_eq!(state.selected(), Some(30));
+        // dragging keeps scrolling past the ends of the scrollbar
+        event(&mut state, MouseEventKind::Drag(MouseButton::Left), 0);
+        assert_eq!(state.selected(), Some(0));
+        event(&mut state, MouseEventKind::Drag(MouseButton::Left), 6);
+        assert_eq!(state.selected(), Some(10));
+        event(&mut state, MouseEventKind::Up(MouseButton::Left), 6);
+        event(&mut state, MouseEventKind::Drag(MouseButton::Left), 8);
+        assert_eq!(state.selected(), Some(10));
+    }
+
+    #[test]
+    fn test_grouped_rows() {
+        let mut state = list_state(0);
+        state.set_grouped(true);
+        let group = |group: &str, i: usize| ListResult {
+            result: i.to_string(),
+            group: group.to_string(),
+            ..Default::default()
+        };
+        state.set_results(vec![
+            group("Calculator", 0),
+            group("Applications", 1),
+            group("Applications", 2),
+            group("Applications", 3),
+        ]);
+        // header, 0, header, 1 on the first page
+        assert_eq!(state.row_at(Position::new(2, 5)), None);
+        assert_eq!(state.row_at(Position::new(2, 6)), Some(0));
+        assert_eq!(state.row_at(Position::new(2, 7)), None);
+        assert_eq!(state.row_at(Position::new(2, 8)), Some(1));
+        match state.execute(ExecuteMode::Primary, Some(1), false) {
+            Some(Action::ItemExecute(result, _)) => assert_eq!(result.result, "1"),
+            action => panic!("expected a launch, got {:?}", action),
+        }
+
+        state.select(Some(0));
+        state.select_next_group();
+        assert_eq!(state.selected(), Some(1));
+        state.select_next_group();
+        assert_eq!(state.selected(), Some(1));
+        state.select(Some(3));
+        assert_eq!(state.offset(), 2);
+        state.select_previous_group();
+        assert_eq!(state.selected(), Some(1));
+        state.select_previous_group();
+        assert_eq!(state.selected(), Some(0));
+        // the group's header is scrolled back into view
+        assert_eq!(state.offset(), 0);
+    }
+
+    #[test]
+    fn test_header_width() {
+        let settings = settings::settings::Settings::default();
+        for title in ["Applications", "日本語の結果"] {
+            assert_eq!(List::header(title, &settings, 30).width(), 30);
+        }
+    }
+
+    #[test]
+    fn test_paging() {
+        let mut state = list_state(20);
+        state.select(Some(0));
+        let mut navigate = |direction| {
+            state.handle_action(&Action::Navigate(direction, 1));
+            (state.selected(), state.offset())
+        };
+        assert_eq!(navigate(NavigateDirection::PageDown), (Some(4), 1));
+        assert_eq!(navigate(NavigateDirection::HalfPageDown), (Some(6), 3));
+        assert_eq!(navigate(NavigateDirection::PageUp), (Some(2), 2));
+        assert_eq!(navigate(NavigateDirection::HalfPageUp), (Some(0), 0));
+
+        // only the page is built
+        let settings = settings::settings::Settings::default();
+        let items = List::construct_list(&state, &settings, IconMode::Normal, None, state.area, 0);
+        assert_eq!(items.len(), 4);
+    }
+
+    #[test]
+    fn test_launching_spinner() {
+        let mut state = list_state(3);
+        match state.execute(ExecuteMode::Primary, Some(1), true) {
+            Some(Action::ItemExecute(result, keep_open)) => {
+                assert_eq!(result.result, "1");
+                assert!(keep_open);
+            }
+            action => panic!("expected a launch, got {:?}", action),
+        }
+
+        let settings = settings::settings::Settings::default();
+        let spinner = |state: &ListState| {
+            let items = List::construct_list(
+                state,
+                &settings,
+                IconMode::Normal,
+                state.executing(),
+                state.area,
+                0,
+            );
+            format!("{:?}", items[1]).contains(&loading_spinner(0))
+        };
+        assert!(!spinner(&state));
+        state.set_executing(Some(1));
+        assert!(spinner(&state));
+        state.set_executing(None);
+        assert!(!spinner(&state));
+    }
+
+    fn marked_names(state: &ListState) -> Vec<&str> {
+        state.marked().iter().map(|r| r.result.as_str()).collect()
+    }
+
+    fn results(names: &[&str]) -> Vec<ListResult> {
+        names
+            .iter()
+            .map(|name| ListResult {
+                result: name.to_string(),
+                markable: *name != "1", // i.e. a command
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_marks() {
+        let mut state = list_state(0);
+        state.set_results(results(&["0", "1", "2", "3"]));
+        state.select(Some(2));
+        state.handle_action(&Action::ToggleMark);
+        assert_eq!(state.selected(), Some(3));
+        // the other way round is kept, Ctrl + click marks without launching
+        let click = MouseEvent {
+            modifiers: KeyModifiers::CONTROL,
+            ..mouse(MouseEventKind::Down(MouseButton::Left), 4, 5)
+        };
+        assert_eq!(state.handle_mouse_event(&click).unwrap(), None);
+        state.toggle_mark(1);
+        assert_eq!(marked_names(&state), ["2", "0"]);
+        state.toggle_mark(2);
+        assert_eq!(marked_names(&state), ["0"]);
+        state.toggle_mark(2);
+
+        // launching by number still launches one
+        assert!(matches!(
+            state.execute(ExecuteMode::Primary, Some(3), false),
+            Some(Action::ItemExecute(..))
+        ));
+        match state.handle_action(&Action::Execute(ExecuteMode::Primary, None, true)) {
+            Some(Action::ItemExecuteAll(results, keep_open)) => {
+                let names: Vec<&str> = results.iter().map(|r| r.result.as_str()).collect();
+                assert_eq!(names, ["0", "2"]);
+                assert!(keep_open);
+            }
+            action => panic!("expected a batch launch, got {:?}", action),
+        }
+        // launching them uses up the marks
+        assert!(state.marked().is_empty());
+    }
+
+    #[test]
+    fn test_marks_survive_new_results() {
+        let mut state = list_state(0);
+        state.set_results(results(&["0", "2"]));
+        state.toggle_mark(1);
+
+        // another query, the marked result isn't among its results
+        state.set_results(results(&["3", "4"]));
+        assert_eq!(marked_names(&state), ["2"]);
+        assert!(!state.is_marked(0));
+        state.toggle_mark(0);
+        assert_eq!(marked_names(&state), ["2", "3"]);
+
+        // found again, it's shown as marked and unmarks
+        state.set_results(results(&["2", "3"]));
+        assert!(state.is_marked(0) && state.is_marked(1));
+        state.toggle_mark(0);
+        assert_eq!(marked_names(&state), ["3"]);
+
+        // the same name from another module is another result
+        let mut other = results(&["3"]);
+        other[0].group = "Commands".into();
+        state.set_results(other);
+        assert!(!state.is_marked(0));
     }
 }
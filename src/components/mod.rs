@@ -8,10 +8,12 @@ use ratatui::{
 };
 use tokio::sync::{Mutex, mpsc::UnboundedSender};
 
-use crate::{action::Action, app::FocusArea, database::Database};
+use crate::{action::Action, app::FocusArea, database::Database, effects::EffectEvent};
 use crate::{settings::settings::Settings, tui::Event};
 
+pub mod help;
 pub mod list;
+pub mod notifications;
 pub mod results;
 pub mod search;
 pub mod util;
@@ -113,6 +115,20 @@ pub trait Component {
         let _ = mouse; // to appease clippy
         Ok(None)
     }
+    /// Whether the component takes a mouse event from the components below it, i.e. an open
+    /// overlay or a toast under the mouse. The topmost component that captures it gets it alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `mouse` - A mouse event about to be handled.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if no other component should handle the event.
+    fn captures_mouse(&self, mouse: &MouseEvent) -> bool {
+        let _ = mouse; // to appease clippy
+        false
+    }
     fn contains(&self, mouse: &MouseEvent) -> bool {
         let area = self.area();
         mouse.column >= area.x
@@ -144,4 +160,14 @@ pub trait Component {
     ///
     /// * `Result<()>` - An Ok result or an error.
     fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()>;
+    /// Effects to start for what changed in the last draw, see `ui.effects`.
+    ///
+    /// Taken by the app right after drawing, so the effects apply to the same frame.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<(EffectEvent, Rect)>` - Each event with the area its effect covers.
+    fn take_effects(&mut self) -> Vec<(EffectEvent, Rect)> {
+        vec![]
+    }
 }
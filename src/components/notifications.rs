@@ -0,0 +1,518 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use color_eyre::Result;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Position, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Padding, Paragraph},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    action::{Action, InputMode, NavigateDirection, Notice, Severity},
+    components::{
+        Component,
+        util::{Truncation, centered, fit_width, truncate, wrap},
+    },
+    settings::settings::{Settings, ThemeSettings, UINotificationSettings},
+};
+
+// lines of a message shown in a toast, the rest is in the messages view
+const TOAST_LINES: usize = 4;
+
+// a notice shown in the corner, the same notice again counts up instead of stacking
+#[derive(Debug, Clone)]
+struct Toast {
+    notice: Notice,
+    count: usize,
+    shown: Instant, // restarted when the notice repeats
+    area: Rect,     // set when drawn, clicking it dismisses the toast
+}
+
+// a notice in the messages view, repeats in a row are counted on one line
+#[derive(Debug, Clone)]
+struct Message {
+    notice: Notice,
+    count: usize,
+    time: DateTime<Local>, // of the last repeat
+}
+
+/// Toasts for errors and notices, stacked in the bottom right corner, and the messages view
+/// listing every notice of the session, toggled with `toggle_messages`.
+#[derive(Debug, Default)]
+pub struct Notifications {
+    settings: Option<Settings>,
+    toasts: Vec<Toast>,                              // oldest first
+    messages: Vec<Message>,                          // oldest first
+    open: bool,                                      // the messages view is shown
+    launches: Option<Vec<(String, Option<String>)>>, // shown in the view instead of the messages
+    scroll: usize,                                   // first message in the view
+    page_height: usize, // messages that fit in the view, set when drawn
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn notification_settings(&self) -> UINotificationSettings {
+        self.settings
+            .as_ref()
+            .map(|settings| settings.ui.notifications.clone())
+            .unwrap_or_default()
+    }
+
+    /// Keep a notice in the messages and, unless it's quieter than `min_severity`, toast it.
+    pub fn notify(&mut self, notice: Notice, now: Instant) {
+        let settings = self.notification_settings();
+        match self.messages.last_mut() {
+            Some(last) if last.notice == notice => {
+                last.count += 1;
+                last.time = Local::now();
+            }
+            _ => self.messages.push(Message {
+                notice: notice.clone(),
+                count: 1,
+                time: Local::now(),
+            }),
+        }
+        let overflow = self.messages.len().saturating_sub(settings.history_size);
+        self.messages.drain(..overflow);
+
+        if !settings.toasts || notice.severity < settings.min_severity || self.open {
+            return;
+        }
+        if let Some(toast) = self.toasts.iter_mut().find(|toast| toast.notice == notice) {
+            toast.count += 1;
+            toast.shown = now;
+            return;
+        }
+        self.toasts.push(Toast {
+            notice,
+            count: 1,
+            shown: now,
+            area: Rect::default(),
+        });
+        let overflow = self.toasts.len().saturating_sub(settings.max_toasts);
+        self.toasts.drain(..overflow);
+    }
+
+    // drop the toasts shown for longer than their timeout
+    fn expire(&mut self, now: Instant) {
+        let settings = self.notification_settings();
+        self.toasts.retain(|toast| {
+            let timeout = match toast.notice.severity {
+                Severity::Error => settings.error_timeout,
+                _ => settings.timeout,
+            };
+            timeout == 0 || now.duration_since(toast.shown) < Duration::from_millis(timeout)
+        });
+    }
+
+    // lines in the view, the launches when they're shown or the messages
+    fn line_count(&self) -> usize {
+        match &self.launches {
+            Some(launches) => launches.len(),
+            None => self.messages.len(),
+        }
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.line_count().saturating_sub(self.page_height.max(1))
+    }
+
+    fn scroll(&mut self, direction: NavigateDirection, amount: usize) {
+        let page = self.page_height.max(1);
+        self.scroll = match direction {
+            NavigateDirection::Up => self.scroll.saturating_sub(amount),
+            NavigateDirection::Down => self.scroll.saturating_add(amount),
+            NavigateDirection::PageUp => self.scroll.saturating_sub(page * amount),
+            NavigateDirection::PageDown => self.scroll.saturating_add(page * amount),
+            NavigateDirection::HalfPageUp => self.scroll.saturating_sub(page / 2 * amount),
+            NavigateDirection::HalfPageDown => self.scroll.saturating_add(page / 2 * amount),
+            NavigateDirection::Home => 0,
+            NavigateDirection::End => usize::MAX,
+            NavigateDirection::Left | NavigateDirection::Right => self.scroll,
+        }
+        .min(self.max_scroll());
+    }
+
+    // newest at the bottom, each toast above the one after it
+    fn draw_toasts(&mut self, frame: &mut Frame, area: Rect, theme: &ThemeSettings) {
+        let width = self
+            .notification_settings()
+            .width
+            .min(area.width.saturating_sub(2));
+        if width < 12 {
+            return;
+        }
+        let text_width = (width - 4) as usize; // borders and padding
+        let mut bottom = area.bottom().saturating_sub(1);
+        for toast in self.toasts.iter_mut().rev() {
+            let mut lines = wrap(&toast.notice.message, text_width);
+            if lines.len() > TOAST_LINES {
+                lines.truncate(TOAST_LINES);
+                let last = format!("{} …", lines[TOAST_LINES - 1]);
+                lines[TOAST_LINES - 1] = truncate(&last, text_width, Truncation::Start);
+            }
+            let height = lines.len() as u16 + 2;
+            if bottom < area.y + height {
+                toast.area = Rect::default();
+                continue;
+            }
+            bottom -= height;
+            toast.area = Rect::new(area.right() - width - 1, bottom, width, height);
+
+            let color = severity_color(theme, toast.notice.severity);
+            let mut block = Block::bordered()
+                .border_type(theme.border_type)
+                .border_style(Style::default().fg(color))
+                .title(Span::styled(
+                    format!(" {} ", severity_label(toast.notice.severity)),
+                    Style::default().fg(color),
+                ))
+                .padding(Padding::horizontal(1))
+                .style(Style::default().bg(theme.background).fg(theme.text));
+            if toast.count > 1 {
+                block = block.title(
+                    Line::from(format!(" ×{} ", toast.count))
+                        .alignment(Alignment::Right)
+                        .style(Style::default().fg(theme.text_muted)),
+                );
+            }
+            let text: Vec<Line> = lines.into_iter().map(Line::from).collect();
+            frame.render_widget(Clear, toast.area);
+            frame.render_widget(Paragraph::new(text).block(block), toast.area);
+        }
+    }
+
+    // the notices of the session, oldest first, scrolled to the newest when opened
+    // or the past launches, most recent first, see `show_launch_history`
+    fn draw_messages(&mut self, frame: &mut Frame, area: Rect, theme: &ThemeSettings) {
+        let width = area.width.saturating_sub(4).min(100);
+        let count = self.line_count();
+        let height = (count.max(1) as u16 + 2).min(area.height.saturating_sub(2));
+        if width < 20 || height < 3 {
+            return;
+        }
+        let popup = centered(area, width, height);
+        self.page_height = (height - 2) as usize;
+        self.scroll = self.scroll.min(self.max_scroll());
+
+        let mut block = Block::bordered()
+            .border_type(theme.border_type)
+            .border_style(Style::default().fg(theme.border))
+            .title(Span::styled(
+                match self.launches {
+                    Some(_) => " Launch history ",
+                    None => " Messages ",
+                },
+                Style::default().fg(theme.title),
+            ))
+            .padding(Padding::horizontal(1))
+            .style(Style::default().bg(theme.background).fg(theme.text));
+        if count > self.page_height {
+            block = block.title_bottom(
+                Line::from(format!(
+                    " {}-{} / {} ",
+                    self.scroll + 1,
+                    self.scroll + self.page_height,
+                    count
+                ))
+                .alignment(Alignment::Right)
+                .style(Style::default().fg(theme.text_muted)),
+            );
+        }
+        let text_width = (width - 4) as usize;
+        let lines: Vec<Line> = match &self.launches {
+            _ if count == 0 => vec![Line::styled(
+                match self.launches {
+                    Some(_) => "No launches",
+                    None => "No messages",
+                },
+                Style::default().fg(theme.text_muted),
+            )],
+            Some(launches) => launches
+                .iter()
+                .skip(self.scroll)
+                .take(self.page_height)
+                .map(|(name, error)| launch_line(name, error.as_deref(), theme, text_width))
+                .collect(),
+            None => self
+                .messages
+                .iter()
+                .skip(self.scroll)
+                .take(self.page_height)
+                .map(|message| message_line(message, theme, text_width))
+                .collect(),
+        };
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+}
+
+// "12:03:04 Error   Failed to launch Firefox: firefox not found ×2"
+fn message_line(message: &Message, theme: &ThemeSettings, width: usize) -> Line<'static> {
+    let time = message.time.format("%H:%M:%S ").to_string();
+    let label = fit_width(severity_label(message.notice.severity), 8, Truncation::End);
+    let mut text = message.notice.message.lines().collect::<Vec<_>>().join(" ");
+    if message.count > 1 {
+        text = format!("{} ×{}", text, message.count);
+    }
+    let text_width = width.saturating_sub(time.width() + label.width());
+    Line::from(vec![
+        Span::styled(time, Style::default().fg(theme.text_muted)),
+        Span::styled(
+            label,
+            Style::default().fg(severity_color(theme, message.notice.severity)),
+        ),
+        Span::raw(truncate(&text, text_width, Truncation::End)),
+    ])
+}
+
+// "Failed  Firefox: firefox not found" or "Launched Firefox"
+fn launch_line(
+    name: &str,
+    error: Option<&str>,
+    theme: &ThemeSettings,
+    width: usize,
+) -> Line<'static> {
+    let (label, color) = match error {
+        Some(_) => ("Failed", theme.error),
+        None => ("Launched", theme.accent),
+    };
+    let label = fit_width(label, 9, Truncation::End);
+    let text = match error {
+        Some(error) => format!("{}: {}", name, error.lines().collect::<Vec<_>>().join(" ")),
+        None => name.to_string(),
+    };
+    let text_width = width.saturating_sub(label.width());
+    Line::from(vec![
+        Span::styled(label, Style::default().fg(color)),
+        Span::raw(truncate(&text, text_width, Truncation::End)),
+    ])
+}
+
+fn severity_label(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "Info",
+        Severity::Warning => "Warning",
+        Severity::Error => "Error",
+    }
+}
+
+fn severity_color(theme: &ThemeSettings, severity: Severity) -> Color {
+    match severity {
+        Severity::Info => theme.accent,
+        Severity::Warning => theme.warning,
+        Severity::Error => theme.error,
+    }
+}
+
+impl Component for Notifications {
+    fn register_settings_handler(&mut self, settings: Settings) -> Result<()> {
+        self.settings = Some(settings);
+        Ok(())
+    }
+
+    // the open view covers the components, a toast only covers what's under it
+    fn captures_mouse(&self, mouse: &MouseEvent) -> bool {
+        let position = Position::new(mouse.column, mouse.row);
+        self.open
+            || self
+                .toasts
+                .iter()
+                .any(|toast| toast.area.contains(position))
+    }
+
+    // clicking a toast dismisses it, the wheel scrolls the messages view
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) if !self.open => {
+                let position = Position::new(mouse.column, mouse.row);
+                self.toasts.retain(|toast| !toast.area.contains(position));
+            }
+            MouseEventKind::ScrollUp if self.open => self.scroll(NavigateDirection::Up, 1),
+            MouseEventKind::ScrollDown if self.open => self.scroll(NavigateDirection::Down, 1),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Error(message) => self.notify(Notice::error(message), Instant::now()),
+            Action::Notify(notice) => self.notify(notice, Instant::now()),
+            Action::InputMode(mode) => {
+                let open = mode == InputMode::Messages;
+                if open && !self.open {
+                    // the toasts are all in the view
+                    self.toasts.clear();
+                    self.scroll = match self.launches {
+                        Some(_) => 0, // the most recent is first
+                        None => usize::MAX,
+                    };
+                }
+                if !open {
+                    self.launches = None;
+                }
+                self.open = open;
+            }
+            Action::LaunchHistory(launches) => {
+                self.launches = Some(launches);
+                self.scroll = 0;
+            }
+            Action::Navigate(direction, amount) if self.open => self.scroll(direction, amount),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let theme = self.settings.as_ref().unwrap().ui.theme.clone();
+        self.expire(Instant::now());
+        if self.open {
+            self.draw_messages(frame, area, &theme);
+        } else {
+            self.draw_toasts(frame, area, &theme);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notifications() -> Notifications {
+        let mut notifications = Notifications::new();
+        notifications
+            .register_settings_handler(Settings::default())
+            .unwrap();
+        notifications
+    }
+
+    #[test]
+    fn test_toasts_stack_and_expire() {
+        let mut notifications = notifications();
+        let now = Instant::now();
+        for message in ["one", "two", "three", "four"] {
+            notifications.notify(Notice::info(message), now);
+        }
+        // the oldest makes room, see `ui.notifications.max_toasts`
+        let toasts: Vec<&str> = notifications
+            .toasts
+            .iter()
+            .map(|toast| toast.notice.message.as_str())
+            .collect();
+        assert_eq!(toasts, vec!["two", "three", "four"]);
+
+        // a repeat counts up instead of stacking
+        notifications.notify(Notice::error("Failed to draw"), now);
+        notifications.notify(Notice::error("Failed to draw"), now);
+        assert_eq!(notifications.toasts.len(), 3);
+        assert_eq!(notifications.toasts.last().unwrap().count, 2);
+        assert_eq!(notifications.messages.len(), 5);
+
+        // errors stay longer than the rest
+        notifications.expire(now + Duration::from_millis(5000));
+        assert_eq!(notifications.toasts.len(), 1);
+        notifications.expire(now + Duration::from_millis(9000));
+        assert!(notifications.toasts.is_empty());
+        assert_eq!(notifications.messages.len(), 5);
+    }
+
+    #[test]
+    fn test_min_severity() {
+        let mut notifications = notifications();
+        notifications
+            .settings
+            .as_mut()
+            .unwrap()
+            .ui
+            .notifications
+            .min_severity = Severity::Warning;
+        notifications.notify(Notice::info("Cleared"), Instant::now());
+        notifications.notify(Notice::warning("Ignoring keybinding"), Instant::now());
+        assert_eq!(notifications.toasts.len(), 1);
+        assert_eq!(notifications.messages.len(), 2);
+    }
+
+    #[test]
+    fn test_messages_scroll() {
+        let mut notifications = notifications();
+        for i in 0..10 {
+            notifications.notify(Notice::info(format!("message {}", i)), Instant::now());
+        }
+        notifications
+            .update(Action::InputMode(InputMode::Messages))
+            .unwrap();
+        assert!(notifications.toasts.is_empty());
+        notifications.page_height = 4;
+        notifications.scroll(NavigateDirection::End, 1);
+        assert_eq!(notifications.scroll, 6);
+        notifications
+            .update(Action::Navigate(NavigateDirection::Up, 2))
+            .unwrap();
+        assert_eq!(notifications.scroll, 4);
+        notifications
+            .update(Action::Navigate(NavigateDirection::PageDown, 1))
+            .unwrap();
+        assert_eq!(notifications.scroll, 6);
+        notifications
+            .update(Action::Navigate(NavigateDirection::Home, 1))
+            .unwrap();
+        assert_eq!(notifications.scroll, 0);
+    }
+
+    #[test]
+    fn test_launch_history() {
+        let mut notifications = notifications();
+        notifications.notify(Notice::info("message"), Instant::now());
+        let launches = vec![
+            ("Code".to_string(), None),
+            ("Broken".to_string(), Some("broken not found".to_string())),
+        ];
+        notifications
+            .update(Action::LaunchHistory(launches))
+            .unwrap();
+        notifications
+            .update(Action::InputMode(InputMode::Messages))
+            .unwrap();
+        assert_eq!(notifications.line_count(), 2);
+        assert_eq!(notifications.scroll, 0);
+        // closing the view goes back to the messages
+        notifications
+            .update(Action::InputMode(InputMode::Insert))
+            .unwrap();
+        assert_eq!(notifications.line_count(), 1);
+    }
+
+    #[test]
+    fn test_captures_mouse() {
+        let mut notifications = notifications();
+        notifications.notify(Notice::error("Failed to draw"), Instant::now());
+        notifications.toasts[0].area = Rect::new(50, 20, 30, 3);
+        let click = |column, row| MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers: crossterm::event::KeyModifiers::NONE,
+        };
+        assert!(notifications.captures_mouse(&click(60, 21)));
+        assert!(!notifications.captures_mouse(&click(10, 5)));
+        notifications.handle_mouse_event(click(60, 21)).unwrap();
+        assert!(notifications.toasts.is_empty());
+        assert!(!notifications.captures_mouse(&click(60, 21)));
+
+        // the open view takes every event
+        notifications
+            .update(Action::InputMode(InputMode::Messages))
+            .unwrap();
+        assert!(notifications.captures_mouse(&click(10, 5)));
+    }
+}
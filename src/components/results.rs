@@ -3,21 +3,23 @@ use crossterm::event::{KeyCode, KeyEventKind, MouseButton, MouseEventKind};
 use std::cmp::min;
 use std::result;
 
-use crate::action::Action;
+use crate::action::{Action, ExecuteMode, InputMode, NavigateDirection};
 use crate::common::module_state::UISection;
 // use crate::common::module_state::{SearchResult, UISection};
 
 use crate::components::Component;
-use crate::components::list::{List, ListState};
-use crate::effects;
+use crate::components::list::{HintMatch, List, ListState, same_result};
+use crate::effects::{EffectEvent, Rainbow};
 use crate::search_modules::ListResult;
 
 use crate::components::util::{IconMode, calculate_color_fade, collapsed_border, number_to_icon};
-use crate::settings::settings::{Settings, UIResultsSettings};
+use crate::settings::settings::{
+    ResultsDisplay, ResultsThemeSettings, Settings, UIResultsSettings,
+};
 use crate::tui::Event;
-use ratatui::layout::{Constraint, Layout, Margin, Offset};
+use ratatui::layout::{Constraint, Layout, Margin, Offset, Position};
 use ratatui::symbols;
-use ratatui::widgets::{Borders, Paragraph};
+use ratatui::widgets::{Borders, Clear, Paragraph};
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -26,7 +28,7 @@ use ratatui::{
     widgets::{Block, ListItem, Padding, StatefulWidget, Widget},
 };
 use serde_json::Number;
-use tachyonfx::{Duration, EffectManager, EffectTimer, Interpolation, fx, pattern};
+use tachyonfx::{Duration, EffectManager, EffectTimer, Interpolation, fx};
 
 #[derive(Debug, Default, Clone)]
 pub struct ResultBoxState {
@@ -41,161 +43,134 @@ pub struct ResultBoxState {
     pub total_potential_results: usize, // not the number of results shown, but the total of potential i.e. 1500 applications, but only 10 relevant shown
 }
 
+// right click menu with the ways to launch a result
+#[derive(Debug, Clone)]
+struct ActionsMenu {
+    index: usize, // the result the menu is for
+    entries: Vec<(&'static str, ExecuteMode)>,
+    selected: usize,
+    position: Position, // where it was opened
+    area: Rect,         // set when drawn
+}
+
+impl ActionsMenu {
+    fn new(result: &ListResult, index: usize, position: Position) -> Self {
+        let mut entries = vec![("Launch", ExecuteMode::Primary)];
+        if result.secondary.is_some() {
+            entries.push(("Alternative action", ExecuteMode::Secondary));
+        }
+        Self {
+            index,
+            entries,
+            selected: 0,
+            position,
+            area: Rect::default(),
+        }
+    }
+
+    // the entry at a position, inside the border
+    fn entry_at(&self, position: Position) -> Option<usize> {
+        let inner = self.area.inner(Margin::new(1, 1));
+        inner
+            .contains(position)
+            .then(|| (position.y - inner.y) as usize)
+            .filter(|entry| *entry < self.entries.len())
+    }
+
+    fn draw(&mut self, frame: &mut ratatui::Frame, theme: &ResultsThemeSettings) {
+        let width = self
+            .entries
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0) as u16
+            + 4;
+        let height = self.entries.len() as u16 + 2;
+        let bounds = frame.area();
+        self.area = Rect {
+            x: self.position.x.min(bounds.right().saturating_sub(width)),
+            y: self.position.y.min(bounds.bottom().saturating_sub(height)),
+            width: width.min(bounds.width),
+            height: height.min(bounds.height),
+        };
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| {
+                let style = if i == self.selected {
+                    Style::default()
+                        .fg(theme.background.unwrap())
+                        .bg(theme.accent.unwrap())
+                } else {
+                    Style::default().fg(theme.text.unwrap())
+                };
+                ListItem::new(Line::from(format!(" {} ", name))).style(style)
+            })
+            .collect();
+        let block = Block::bordered()
+            .border_style(Style::default().fg(theme.border.unwrap()))
+            .style(Style::default().bg(theme.background.unwrap()));
+        frame.render_widget(Clear, self.area);
+        frame.render_widget(ratatui::widgets::List::new(items).block(block), self.area);
+    }
+}
+
 #[derive(Clone)]
 pub struct ResultsBox {
     settings: Option<Settings>,
-    render_tick: u64,
-    last_search_tick: u64,
-    delta_time: i32,
-    results: Vec<ListResult>,
+    rainbow: Rainbow,      // the rainbow border, see `ui.results.rainbow_border`
+    results_changed: bool, // new results since the last draw, for the results effect
+    drawn_selection: Option<usize>, // selection of the last draw, for the selection effect
+    pending_effect: Option<(EffectEvent, Rect)>, // taken by the app after the draw
+    status: Option<String>, // shown in place of the number of results, i.e. a failed launch
     previous_results: Vec<ListResult>,
-    total_potential_results: usize,
-    list_state: ListState,
+    total_potential_results: usize, // see [`Action::SearchTotal`]
+    list_state: ListState,          // owns the results
+    launch_queue: Vec<ListResult>,  // marked results still to be launched, the first is spinning
     // list: List,
     action_tx: Option<tokio::sync::mpsc::UnboundedSender<Action>>,
     area: Rect,
     focused: bool,
+    input_mode: InputMode,
+    actions_menu: Option<ActionsMenu>,
     root_layout: crate::common::layout::RootLayout,
 }
 
 impl ResultsBox {
+    // the spinner is on the next marked result to launch, when it's one of the results shown
+    fn spin_next_launch(&mut self) {
+        let index = self.launch_queue.first().and_then(|next| {
+            self.list_state
+                .results()?
+                .iter()
+                .position(|result| same_result(result, next))
+        });
+        self.list_state.set_executing(index);
+    }
+
     pub fn new() -> Self {
         Self {
             settings: None,
-            render_tick: 0,
-            last_search_tick: 0,
-            delta_time: 0,
-            results: Vec::new(),
+            rainbow: Rainbow::default(),
+            results_changed: false,
+            drawn_selection: None,
+            pending_effect: None,
+            status: None,
             previous_results: Vec::new(),
 
             total_potential_results: 0,
             list_state: ListState::default(),
+            launch_queue: Vec::new(),
             action_tx: None,
             area: Rect::default(),
             focused: true,
+            input_mode: InputMode::default(),
+            actions_menu: None,
             root_layout: crate::common::layout::RootLayout::default(),
             // list: List::new(),
         }
     }
-
-    fn get_loading_spinner(&self, tick: u64) -> String {
-        let remainder = tick % 4;
-        if remainder == 0 {
-            "◜".to_string()
-        } else if remainder == 1 {
-            "◝".to_string()
-        } else if remainder == 2 {
-            "◞".to_string()
-        } else {
-            "◟".to_string()
-        }
-    }
-
-    pub fn construct_list(
-        &self,
-        results: &Vec<ListResult>,
-        number_mode: IconMode,
-        executing_item: Option<usize>,
-        list_state: &ListState,
-        area: Rect,
-        tick: u64,
-    ) -> Vec<ListItem<'static>> {
-        let theme = self
-            .settings
-            .as_ref()
-            .unwrap()
-            .ui
-            .theme
-            .get_results_colors();
-        let available_height = area.height as usize;
-        let mut i = 1;
-        let items: Vec<ListItem<'static>> = results
-            .iter()
-            // .map(|(score, idx)| {
-            .map(|r| {
-                let result = &r.result;
-                let score = &r.score;
-
-                // space out sections to fit the width
-                // let app = &state.data.applications[*idx];
-
-                let score_text = score.to_string();
-
-                // get number icon
-                // mode configurable in settings
-                let mut prepend_icon = number_to_icon(i, number_mode);
-                // let executing_item = state.executing_item;
-                // if executing, use loading spinner
-                if executing_item.is_some() && i == executing_item.unwrap() + 1 {
-                    prepend_icon = self.get_loading_spinner(tick);
-                }
-
-                // pad score to end i.e. "App Name       123"
-                let line_width = area.width as usize;
-                let mut name_width = line_width.saturating_sub(score_text.len() - 1);
-                if prepend_icon.trim().is_empty() {
-                    name_width = name_width.saturating_sub(4); // extra space if no icon
-                } else {
-                    name_width = name_width.saturating_sub(prepend_icon.len() + 1); // +1 for space
-                }
-                let padded_name = format!("{:<width$}", result, width = name_width);
-
-                let mut text_color = theme.text.unwrap();
-                let mut muted_color = theme.text_muted.unwrap();
-
-                // calculate list color fade
-                if self
-                    .settings
-                    .as_ref()
-                    .unwrap()
-                    .ui
-                    .results
-                    .fade_color_at_bottom
-                    && available_height >= 10
-                {
-                    text_color = calculate_color_fade(
-                        theme.text.unwrap(),
-                        i.saturating_sub(list_state.offset()),
-                        available_height,
-                    );
-                    muted_color = calculate_color_fade(
-                        theme.text_muted.unwrap(),
-                        i.saturating_sub(list_state.offset()),
-                        available_height,
-                    );
-                }
-
-                // construct line
-                let line = Line::from(vec![
-                    // number index
-                    Span::styled(
-                        format!("{} ", prepend_icon),
-                        Style::default().fg(theme.accent.unwrap()),
-                    ),
-                    Span::styled(padded_name.clone(), Style::default().fg(text_color)), // name
-                    if self.settings.as_ref().unwrap().ui.results.show_scores {
-                        Span::styled(score_text.clone(), Style::default().fg(muted_color))
-                    } else {
-                        Span::raw("")
-                    },
-                ]);
-                i += 1;
-                ListItem::new(line)
-            })
-            .collect::<Vec<ListItem>>();
-
-        items
-    }
-
-    fn execute_selected(&mut self) {
-        if let Some(selected) = self.list_state.selected() {
-            if let Some(action_tx) = &self.action_tx {
-                action_tx
-                    .send(Action::ItemExecute(self.results[selected].clone()))
-                    .unwrap_or(());
-            }
-        }
-    }
 }
 
 impl Component for ResultsBox {
@@ -215,6 +190,8 @@ impl Component for ResultsBox {
 
     fn register_settings_handler(&mut self, settings: Settings) -> Result<()> {
         self.settings = Some(settings.clone());
+        self.list_state
+            .set_grouped(settings.ui.results.display == ResultsDisplay::Grouped);
 
         Ok(())
     }
@@ -224,26 +201,38 @@ impl Component for ResultsBox {
         }
 
         match event.unwrap() {
-            Event::Key(key) => {
-                return self.handle_key_event(key);
-            }
             Event::Mouse(mouse) => {
                 return self.handle_mouse_event(mouse);
             }
+            Event::Key(key) => {
+                return self.handle_key_event(key);
+            }
             _ => {}
         }
 
         Ok(None)
     }
 
+    // only keys that aren't bound to anything get here, in hint mode those type a hint label
     fn handle_key_event(&mut self, key: crossterm::event::KeyEvent) -> Result<Option<Action>> {
-        if (!self.focused) {
+        if key.kind != KeyEventKind::Press || self.input_mode != InputMode::Hint {
             return Ok(None);
         }
-        return self
-            .list_state
-            .handle_key_event(&key, self.settings.as_ref().unwrap());
+        let KeyCode::Char(c) = key.code else {
+            return Ok(Some(Action::HideHints));
+        };
+        match self.list_state.type_hint(c) {
+            HintMatch::Partial => Ok(None),
+            HintMatch::Launch(action) => {
+                if let Some(tx) = &self.action_tx {
+                    tx.send(Action::HideHints).unwrap();
+                }
+                Ok(Some(action))
+            }
+            HintMatch::None => Ok(Some(Action::HideHints)),
+        }
     }
+
     fn handle_mouse_event(
         &mut self,
         mouse: crossterm::event::MouseEvent,
@@ -252,36 +241,139 @@ impl Component for ResultsBox {
             return Ok(None);
         }
         log::info!("Mouse event received results: {:?}", mouse);
-        return self
-            .list_state
-            .handle_mouse_event(&mouse, self.settings.as_ref().unwrap());
+        let position = Position::new(mouse.column, mouse.row);
+        if let Some(menu) = self.actions_menu.as_mut() {
+            match mouse.kind {
+                MouseEventKind::Moved => {
+                    if let Some(entry) = menu.entry_at(position) {
+                        menu.selected = entry;
+                    }
+                }
+                // a click anywhere closes the menu, on an entry it also launches
+                MouseEventKind::Down(_) => {
+                    let menu = self.actions_menu.take().unwrap();
+                    if let Some(entry) = menu.entry_at(position) {
+                        return Ok(self.list_state.launch(
+                            menu.entries[entry].1,
+                            menu.index,
+                            false,
+                        ));
+                    }
+                }
+                _ => {}
+            }
+            return Ok(None);
+        }
+        if mouse.kind == MouseEventKind::Down(MouseButton::Right) {
+            if let Some(index) = self.list_state.row_at(position) {
+                self.list_state.select(Some(index));
+                let result = &self.list_state.results().unwrap()[index];
+                self.actions_menu = Some(ActionsMenu::new(result, index, position));
+            }
+            return Ok(None);
+        }
+        return self.list_state.handle_mouse_event(&mouse);
     }
     fn update(
         &mut self,
         action: crate::action::Action,
     ) -> color_eyre::eyre::Result<Option<crate::action::Action>> {
         match action {
-            Action::Render => {
-                self.render_tick = self.render_tick.saturating_add(1);
-                self.delta_time = 16; // assume ~60fps for now
-            }
             Action::SearchResults(results) => {
-                self.last_search_tick = self.render_tick;
-                self.results = results;
-                self.total_potential_results = self.results.len();
+                self.actions_menu = None;
+                self.results_changed = true;
+                self.status = None;
+                self.list_state.set_results(results);
                 self.list_state.select(Some(0));
             }
+            Action::SearchTotal(total) => self.total_potential_results = total,
+            Action::ItemExecute(result, _) => {
+                let index = self
+                    .list_state
+                    .results()
+                    .and_then(|results| results.iter().position(|r| *r == result));
+                self.list_state.set_executing(index);
+            }
+            Action::ItemExecuteAll(results, _) => {
+                self.launch_queue = results;
+                self.spin_next_launch();
+            }
+            Action::Launched(outcome) => {
+                let name = match self.launch_queue.first() {
+                    Some(result) => result.result.clone(),
+                    None => self
+                        .list_state
+                        .executing()
+                        .and_then(|index| self.list_state.results()?.get(index))
+                        .map(|result| result.result.clone())
+                        .unwrap_or_default(),
+                };
+                // the spinner moves on to the next marked result
+                if !self.launch_queue.is_empty() {
+                    self.launch_queue.remove(0);
+                    self.spin_next_launch();
+                } else {
+                    self.list_state.set_executing(None);
+                }
+                if let Err(err) = outcome {
+                    self.status = Some(format!("Failed to launch {}: {}", name, err));
+                }
+            }
             Action::Focus(focus) => {
                 if focus == self.focus_area() && !self.focused {
                     self.focused = true;
                 } else if focus != self.focus_area() && self.focused {
                     self.focused = false;
+                    self.actions_menu = None;
                     self.list_state.select(None);
                 }
             }
             Action::UpdateLayout(layout) => {
                 self.root_layout = layout;
             }
+            Action::InputMode(mode) => {
+                self.input_mode = mode;
+                if mode == InputMode::Hint {
+                    let hint_keys = &self.settings.as_ref().unwrap().ui.results.hint_keys;
+                    self.list_state.show_hints(hint_keys);
+                } else {
+                    self.list_state.hide_hints();
+                }
+            }
+            Action::ToggleScores => {
+                if let Some(settings) = self.settings.as_mut() {
+                    settings.ui.results.show_scores = !settings.ui.results.show_scores;
+                }
+            }
+            // the messages view or the help overlay is scrolled instead
+            Action::Navigate(..)
+                if matches!(self.input_mode, InputMode::Messages | InputMode::Help) => {}
+            // the actions menu takes the list keys while it's open
+            Action::Navigate(direction, amount) if self.actions_menu.is_some() => {
+                let menu = self.actions_menu.as_mut().unwrap();
+                let last = menu.entries.len() - 1;
+                menu.selected = match direction {
+                    NavigateDirection::Up => menu.selected.saturating_sub(amount),
+                    NavigateDirection::Down => menu.selected.saturating_add(amount).min(last),
+                    NavigateDirection::Home => 0,
+                    NavigateDirection::End => last,
+                    _ => menu.selected,
+                };
+            }
+            Action::Execute(_, None, keep_open) if self.actions_menu.is_some() => {
+                let menu = self.actions_menu.take().unwrap();
+                let (_, mode) = menu.entries[menu.selected];
+                return Ok(self.list_state.launch(mode, menu.index, keep_open));
+            }
+            Action::Navigate(..)
+            | Action::Execute(..)
+            | Action::NextGroup
+            | Action::PreviousGroup
+            | Action::ToggleMark
+                if self.focused =>
+            {
+                return Ok(self.list_state.handle_action(&action));
+            }
 
             _ => {}
         }
@@ -348,7 +440,10 @@ impl Component for ResultsBox {
 
         // show number of results
         // positions it inside the padding area
-        if results_settings.show_number_of_results {
+        if results_settings.show_number_of_results
+            || self.status.is_some()
+            || !self.list_state.marked().is_empty()
+        {
             // if padding is zero, make space for number of results
             if padding == 0 {
                 inner_area.height = inner_area.height.saturating_sub(1);
@@ -369,41 +464,37 @@ impl Component for ResultsBox {
                 chunk.height = 1;
             }
 
-            let num_results = Paragraph::new(format!(
-                "{} / {}",
-                self.results.len(),
-                self.total_potential_results
-            ))
-            .style(Style::default().fg(results_theme.text_muted.unwrap()))
-            .alignment(results_settings.number_of_results_alignment);
+            let (text, color) = match &self.status {
+                Some(status) => (status.clone(), results_theme.text_accent.unwrap()),
+                None => {
+                    let count = format!(
+                        "{} / {}",
+                        self.list_state.results().map_or(0, |results| results.len()),
+                        self.total_potential_results
+                    );
+                    match self.list_state.marked().len() {
+                        0 => (count, results_theme.text_muted.unwrap()),
+                        marked => (
+                            format!("{} marked · {}", marked, count),
+                            results_theme.text_accent.unwrap(),
+                        ),
+                    }
+                }
+            };
+            let num_results = Paragraph::new(text)
+                .style(Style::default().fg(color))
+                .alignment(results_settings.number_of_results_alignment);
             // num_results.render(chunk, buf);
             frame.render_widget(num_results, chunk);
         }
 
         // rainbow border effect
-        if self.settings.as_ref().unwrap().ui.results.rainbow_border {
-            let t = self.render_tick as u32;
-            let speed: f32 = self
-                .settings
-                .as_ref()
-                .unwrap()
-                .ui
-                .results
-                .rainbow_border_speed;
-            effects::rainbow(
-                results_theme.border.unwrap(),
-                2000,
-                speed,
-                area,
-                frame.buffer_mut(),
-                t,
-            );
+        let settings = self.settings.as_ref().unwrap();
+        if settings.ui.results.rainbow_border && !settings.ui.reduce_motion {
+            let speed = settings.ui.results.rainbow_border_speed;
+            self.rainbow.render(speed, area, frame.buffer_mut());
         }
 
-        let results: &Vec<ListResult> = &self.results;
-        // let list = self.list.clone();
-        self.list_state.set_results(results.clone());
-
         // render list with state
         frame.render_stateful_widget(
             List::new(self.settings.clone().unwrap()),
@@ -412,28 +503,26 @@ impl Component for ResultsBox {
         );
         // StatefulWidget::render(list, inner_area, frame.buffer_mut(), &mut self.list_state);
 
-        // fade in effect
-        if self.settings.as_ref().unwrap().ui.results.fade_in {
-            let mut direction: Option<pattern::AnyPattern> = None;
-            if results_settings.fade_top_to_bottom {
-                direction = Some(pattern::AnyPattern::Sweep(
-                    pattern::SweepPattern::down_to_up(
-                        min(area.height as usize, results.len()) as u16
-                    ),
-                ));
-            }
-            let tick = self.render_tick.saturating_sub(self.last_search_tick) as u32
-                * self.delta_time as u32;
-
-            effects::fade_in(
-                Color::Black,
-                self.settings.as_ref().unwrap().ui.results.fade_in_duration,
-                direction,
-                inner_area,
-                frame.buffer_mut(),
-                tick,
-            );
+        if let Some(menu) = self.actions_menu.as_mut() {
+            menu.draw(frame, &results_theme);
         }
+
+        // the app starts the effect over the list just drawn, see [`Component::take_effects`]
+        self.pending_effect = if std::mem::take(&mut self.results_changed) {
+            self.drawn_selection = self.list_state.selected();
+            Some((EffectEvent::Results, inner_area))
+        } else if self.drawn_selection != self.list_state.selected() {
+            self.drawn_selection = self.list_state.selected();
+            self.list_state
+                .selected_area()
+                .map(|area| (EffectEvent::Selection, area))
+        } else {
+            None
+        };
         Ok(())
     }
+
+    fn take_effects(&mut self) -> Vec<(EffectEvent, Rect)> {
+        self.pending_effect.take().into_iter().collect()
+    }
 }
@@ -1,43 +1,255 @@
 use crate::{
-    action::Action,
+    action::{Action, EditCommand, ExecuteMode, InputMode, NavigateDirection, Search},
     common::module_state::UISection,
     components::{Component, util::collapsed_border},
-    effects::{self, rainbow},
-    settings::settings::{Settings, UISearchSettings},
+    effects::Rainbow,
+    settings::settings::{EditingMode, Settings, UISearchSettings},
 };
 use color_eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
     buffer::Buffer,
-    layout::{Layout, Rect},
+    layout::{Layout, Position, Rect},
     style::{Color, Style},
     text::{Line, Span},
     widgets::{Block, Padding, Paragraph, StatefulWidget, Widget},
 };
 use ratatui::{layout::Constraint, widgets::Borders};
-use tui_textarea::TextArea;
+use std::time::Instant;
+use tui_textarea::{CursorMove, TextArea};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 #[derive(Clone)]
 pub struct SearchBox {
     settings: Option<Settings>,
-    render_tick: u64,
+    rainbow: Rainbow, // the rainbow border, see `ui.search.rainbow_border`
     text_area: TextArea<'static>,
     area: Rect,
     focused: bool,
     root_layout: crate::common::layout::RootLayout,
+    input_mode: InputMode,
+    pending_keys: String,         // unfinished key sequence, shown on the right
+    text_region: Rect,            // where the query is drawn, for clicking to move the caret
+    history: Vec<String>,         // past queries, most recent first
+    history_index: Option<usize>, // the recalled query, None while typing a new one
+    draft: String,                // the query being typed, restored after going through the history
+    has_results: bool,
+    caret_shown_at: Instant, // the caret blinks from here, reset when typing
+    completion: Option<String>, // the completed query, the rest is shown as ghost text
+    completion_available: bool, // last sent to the app, see [`Action::CompletionAvailable`]
+    action_tx: Option<tokio::sync::mpsc::UnboundedSender<Action>>,
+    prefixes: Vec<(String, String)>, // query prefix and the title of the module it scopes to
+    scope: Option<(String, String)>, // the typed prefix, shown as a chip and not in the text
 }
 
 impl SearchBox {
     pub fn new() -> Self {
         Self {
             settings: None,
-            render_tick: 0,
+            rainbow: Rainbow::default(),
             focused: true,
             text_area: TextArea::default(),
             area: Rect::default(),
             root_layout: crate::common::layout::RootLayout::default(),
+            input_mode: InputMode::default(),
+            pending_keys: String::new(),
+            text_region: Rect::default(),
+            history: vec![],
+            history_index: None,
+            draft: String::new(),
+            has_results: false,
+            caret_shown_at: Instant::now(),
+            completion: None,
+            completion_available: false,
+            action_tx: None,
+            prefixes: vec![],
+            scope: None,
         }
     }
+
+    // the rest of the completion, while the caret is at the end of the query
+    fn ghost_text(&self) -> Option<String> {
+        if !self.focused || self.input_mode != InputMode::Insert {
+            return None;
+        }
+        if self.text_area.cursor().1 < self.text().chars().count() {
+            return None;
+        }
+        let ghost = self
+            .completion
+            .as_ref()?
+            .strip_prefix(self.query().as_str())?;
+        (!ghost.is_empty()).then(|| ghost.to_string())
+    }
+
+    fn accept_completion(&mut self) -> Option<Action> {
+        self.ghost_text()?;
+        let completion = self.completion.take()?;
+        self.set_query(&completion);
+        self.edited()
+    }
+
+    fn set_query(&mut self, query: &str) {
+        self.scope = None;
+        self.text_area = TextArea::from([query]);
+        self.text_area.move_cursor(CursorMove::End);
+        self.caret_shown_at = Instant::now();
+        self.detect_scope();
+    }
+
+    // a query starting with a module prefix moves the prefix into the chip, longest wins
+    fn detect_scope(&mut self) {
+        if self.scope.is_some() {
+            return;
+        }
+        let text = self.text();
+        let Some((prefix, label)) = self
+            .prefixes
+            .iter()
+            .filter(|(prefix, _)| text.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .cloned()
+        else {
+            return;
+        };
+        let column = self.text_area.cursor().1;
+        self.text_area = TextArea::from([&text[prefix.len()..]]);
+        let column = column.saturating_sub(prefix.chars().count());
+        self.text_area
+            .move_cursor(CursorMove::Jump(0, column as u16));
+        self.scope = Some((prefix, label));
+    }
+
+    // the query was typed or edited, so it's no longer a recalled one
+    fn edited(&mut self) -> Option<Action> {
+        self.detect_scope();
+        self.history_index = None;
+        self.caret_shown_at = Instant::now();
+        self.query_changed()
+    }
+
+    // recall a query from the history, None goes back to the query being typed
+    fn recall(&mut self, index: Option<usize>) -> Option<Action> {
+        if self.history_index.is_none() {
+            self.draft = self.query();
+        }
+        self.history_index = index;
+        let query = match index {
+            Some(index) => self.history[index].clone(),
+            None => self.draft.clone(),
+        };
+        self.set_query(&query);
+        self.query_changed()
+    }
+
+    fn history_previous(&mut self) -> Option<Action> {
+        let index = self.history_index.map_or(0, |index| index + 1);
+        if index >= self.history.len() {
+            return None;
+        }
+        self.recall(Some(index))
+    }
+
+    fn history_next(&mut self) -> Option<Action> {
+        match self.history_index? {
+            0 => self.recall(None),
+            index => self.recall(Some(index - 1)),
+        }
+    }
+
+    fn edit(&mut self, command: EditCommand) -> Option<Action> {
+        let text_area = &mut self.text_area;
+        let modified = match command {
+            EditCommand::DeleteWordBack => text_area.delete_word(),
+            EditCommand::DeleteToStart => text_area.delete_line_by_head(),
+            EditCommand::DeleteToEnd => text_area.delete_line_by_end(),
+            EditCommand::WordBack => {
+                text_area.move_cursor(CursorMove::WordBack);
+                false
+            }
+            EditCommand::WordForward => {
+                text_area.move_cursor(CursorMove::WordForward);
+                false
+            }
+            EditCommand::LineStart => {
+                text_area.move_cursor(CursorMove::Head);
+                false
+            }
+            EditCommand::LineEnd => {
+                text_area.move_cursor(CursorMove::End);
+                false
+            }
+        };
+        self.caret_shown_at = Instant::now();
+        if modified { self.edited() } else { None }
+    }
+
+    // the caret, blinking unless the blink rate is 0. drawn over the character or ghost text
+    // after it
+    fn draw_caret(&self, frame: &mut ratatui::Frame, settings: &UISearchSettings, color: Color) {
+        let blink_rate = settings.caret_blink_rate as u128;
+        let elapsed = self.caret_shown_at.elapsed().as_millis();
+        if blink_rate > 0 && (elapsed / blink_rate) % 2 == 1 {
+            return;
+        }
+        let region = self.text_region;
+        if region.is_empty() {
+            return;
+        }
+        let (_, column) = self.text_area.cursor();
+        let text = self.text();
+        let x = text.chars().take(column).collect::<String>().width() as u16; // wide chars
+        let position = Position::new(region.x + x.min(region.width - 1), region.y);
+        let on_text = column < text.chars().count() || self.ghost_text().is_some();
+        if let Some(cell) = frame.buffer_mut().cell_mut(position) {
+            if on_text {
+                cell.set_bg(color);
+            } else {
+                cell.set_symbol(&settings.caret_text).set_fg(color);
+            }
+        }
+    }
+
+    // the text typed after the scope chip
+    fn text(&self) -> String {
+        self.text_area.lines().concat()
+    }
+
+    fn query(&self) -> String {
+        match &self.scope {
+            Some((prefix, _)) => format!("{}{}", prefix, self.text()),
+            None => self.text(),
+        }
+    }
+
+    fn query_changed(&self) -> Option<Action> {
+        if self.settings.as_ref().unwrap().search.always_search {
+            return Some(Action::Search(Search::Execute(self.query())));
+        }
+        None
+    }
+
+    // pending keys and the vim or hint mode, i.e. "g  NORMAL"
+    fn status_line(&self, settings: &Settings, color: Color, accent: Color) -> Line<'static> {
+        let mut spans = vec![];
+        if settings.input.show_pending_keys && !self.pending_keys.is_empty() {
+            spans.push(Span::styled(
+                format!("{} ", self.pending_keys),
+                Style::default().fg(accent),
+            ));
+        }
+        let mode = match self.input_mode {
+            InputMode::Hint => Some("HINT"),
+            InputMode::Messages | InputMode::Help => None,
+            _ if settings.input.editing_mode != EditingMode::Vim => None,
+            InputMode::Insert => Some("INSERT"),
+            InputMode::Normal => Some("NORMAL"),
+        };
+        if let Some(mode) = mode {
+            spans.push(Span::styled(mode, Style::default().fg(color)));
+        }
+        Line::from(spans)
+    }
 }
 
 impl Component for SearchBox {
@@ -51,7 +263,7 @@ impl Component for SearchBox {
         &mut self,
         tx: tokio::sync::mpsc::UnboundedSender<crate::action::Action>,
     ) -> color_eyre::eyre::Result<()> {
-        let _ = tx; // to appease clippy
+        self.action_tx = Some(tx);
         Ok(())
     }
 
@@ -71,36 +283,56 @@ impl Component for SearchBox {
         if !self.focused {
             return Ok(None);
         }
+        // in vim normal mode keys are for navigating, in hint mode they pick a result
+        if self.input_mode != InputMode::Insert {
+            return Ok(None);
+        }
+        // only keys that aren't bound to an action get here, see [keybinds] in settings
         match key.code {
-            KeyCode::Enter => {
-                return Ok(Some(Action::Search(crate::action::Search::Execute(
-                    self.text_area.lines().concat(),
-                ))));
-            }
-            KeyCode::Up => {
-                return Ok(None);
+            // the query is a single line, these never edit it
+            KeyCode::Enter | KeyCode::Up | KeyCode::Down | KeyCode::Tab | KeyCode::BackTab => {
+                Ok(None)
             }
-            KeyCode::Down => {
-                return Ok(None);
-            }
-            KeyCode::BackTab => {
-                return Ok(None);
-            }
-            KeyCode::Tab => {
-                return Ok(None);
+            // Backspace on an empty query leaves the module it's scoped to
+            KeyCode::Backspace if self.scope.is_some() && self.text().is_empty() => {
+                self.scope = None;
+                Ok(self.edited())
             }
             _ => {
-                self.text_area.input(key);
-                if self.settings.as_ref().unwrap().search.always_search {
-                    return Ok(Some(Action::Search(crate::action::Search::Execute(
-                        self.text_area.lines().concat(),
-                    ))));
+                if self.text_area.input(key) {
+                    return Ok(self.edited());
                 }
-                return Ok(None);
+                self.caret_shown_at = Instant::now();
+                Ok(None)
             }
         }
     }
 
+    // clicking the query moves the caret there
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
+        let position = Position::new(mouse.column, mouse.row);
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left)
+            || !self.text_region.contains(position)
+        {
+            return Ok(None);
+        }
+        // the character under the click, wide characters take two columns
+        let x = (position.x - self.text_region.x) as usize;
+        let mut width = 0;
+        let column = self
+            .text()
+            .chars()
+            .take_while(|c| {
+                width += c.width().unwrap_or(0);
+                width <= x
+            })
+            .count();
+        self.text_area
+            .move_cursor(CursorMove::Jump(0, column as u16));
+        self.caret_shown_at = Instant::now();
+        Ok(None)
+    }
+
     fn update(
         &mut self,
         action: crate::action::Action,
@@ -111,7 +343,6 @@ impl Component for SearchBox {
             }
             crate::action::Action::Render => {
                 // add any logic here that should run on every render
-                self.render_tick += 1;
             }
             Action::Focus(focus) => {
                 if (focus == self.focus_area()) {
@@ -123,6 +354,48 @@ impl Component for SearchBox {
             Action::UpdateLayout(layout) => {
                 self.root_layout = layout;
             }
+            Action::InputMode(mode) => {
+                self.input_mode = mode;
+            }
+            Action::PendingKeys(keys) => {
+                self.pending_keys = keys;
+            }
+            Action::Search(Search::Set(query)) => {
+                self.set_query(&query);
+                return Ok(self.edited());
+            }
+            // without search as you type, executing also runs the search
+            Action::Execute(ExecuteMode::Primary, None, _)
+                if self.focused && !self.settings.as_ref().unwrap().search.always_search =>
+            {
+                return Ok(Some(Action::Search(Search::Submit(self.query()))));
+            }
+            Action::SearchResults(results) => {
+                self.has_results = !results.is_empty();
+            }
+            Action::QueryHistory(history) => {
+                self.history = history;
+                self.history_index = None;
+            }
+            Action::ModulePrefixes(prefixes) => self.prefixes = prefixes,
+            Action::Completion(completion) => {
+                self.completion = completion;
+            }
+            Action::AcceptCompletion if self.focused => return Ok(self.accept_completion()),
+            Action::Edit(command) if self.focused => return Ok(self.edit(command)),
+            Action::HistoryPrevious if self.focused => return Ok(self.history_previous()),
+            Action::HistoryNext if self.focused => return Ok(self.history_next()),
+            // Up and Down go through the history while there are no results to move through
+            Action::Navigate(NavigateDirection::Up, _)
+                if self.focused && (self.history_index.is_some() || !self.has_results) =>
+            {
+                return Ok(self.history_previous());
+            }
+            Action::Navigate(NavigateDirection::Down, _)
+                if self.focused && self.history_index.is_some() =>
+            {
+                return Ok(self.history_next());
+            }
             _ => {}
         }
         Ok(None)
@@ -176,23 +449,10 @@ impl Component for SearchBox {
         frame.render_widget(block, area);
 
         // rainbow border effect
-        if self.settings.as_ref().unwrap().ui.search.rainbow_border {
-            let t = self.render_tick as u32;
-            let speed: f32 = self
-                .settings
-                .as_ref()
-                .unwrap()
-                .ui
-                .search
-                .rainbow_border_speed;
-            effects::rainbow(
-                search_theme.border.unwrap(),
-                2000,
-                speed,
-                area,
-                frame.buffer_mut(),
-                t,
-            );
+        let settings = self.settings.as_ref().unwrap();
+        if settings.ui.search.rainbow_border && !settings.ui.reduce_motion {
+            let speed = settings.ui.search.rainbow_border_speed;
+            self.rainbow.render(speed, area, frame.buffer_mut());
         }
 
         //
@@ -202,14 +462,187 @@ impl Component for SearchBox {
         let paragraph = Paragraph::new(search_settings.pre_query.clone())
             .alignment(self.settings.as_ref().unwrap().ui.search.text_alignment)
             .style(Style::default().bg(search_theme.background.unwrap()));
+        // drawn first, its background would cover the chip and caret
+        frame.render_widget(paragraph, inner_area);
+
+        let status = self.status_line(
+            self.settings.as_ref().unwrap(),
+            search_theme.text_muted.unwrap(),
+            search_theme.accent.unwrap(),
+        );
+        let status_width = status.width() as u16;
 
         let mut text_region = inner_area.clone();
         text_region.x = text_region.x.saturating_add(3);
-        text_region.width = text_region.width.saturating_sub(3);
+        text_region.width = text_region
+            .width
+            .saturating_sub(3)
+            .saturating_sub(status_width);
+
+        // the module the query is scoped to, before the text
+        if let Some((_, label)) = &self.scope {
+            let chip = Span::styled(
+                format!(" {} ", label),
+                Style::default()
+                    .fg(search_theme.background.unwrap())
+                    .bg(search_theme.accent.unwrap()),
+            );
+            let chip_width = (chip.width() as u16 + 1).min(text_region.width);
+            frame.render_widget(chip, text_region.intersection(inner_area));
+            text_region.x += chip_width;
+            text_region.width -= chip_width;
+        }
 
+        // the caret is drawn separately, to support caret_text and blinking
+        self.text_area.set_cursor_style(Style::default());
+        self.text_area
+            .set_placeholder_text(search_settings.placeholder.as_str());
+        self.text_area
+            .set_placeholder_style(Style::default().fg(search_theme.text_muted.unwrap()));
         frame.render_widget(&self.text_area, text_region);
+        self.text_region = text_region;
 
-        frame.render_widget(paragraph, inner_area);
+        let ghost_text = self.ghost_text();
+        if let Some(ghost) = &ghost_text {
+            let query_width = self.text().width() as u16;
+            if query_width < text_region.width {
+                frame.buffer_mut().set_stringn(
+                    text_region.x + query_width,
+                    text_region.y,
+                    ghost,
+                    (text_region.width - query_width) as usize,
+                    Style::default().fg(search_theme.text_muted.unwrap()),
+                );
+            }
+        }
+        // tell the app, so Tab and Right accept the completion only while it's shown
+        if ghost_text.is_some() != self.completion_available {
+            self.completion_available = ghost_text.is_some();
+            if let Some(tx) = &self.action_tx {
+                tx.send(Action::CompletionAvailable(self.completion_available))
+                    .unwrap();
+            }
+        }
+
+        if search_settings.caret_visible && self.focused && self.input_mode != InputMode::Hint {
+            self.draw_caret(frame, &search_settings, search_theme.caret.unwrap());
+        }
+
+        if status_width > 0 {
+            let mut status_region = inner_area;
+            status_region.height = status_region.height.min(1);
+            frame.render_widget(
+                Paragraph::new(status).alignment(ratatui::layout::Alignment::Right),
+                status_region,
+            );
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search_modules::ListResult;
+
+    fn search_box(query: &str) -> SearchBox {
+        let mut search_box = SearchBox::new();
+        search_box
+            .register_settings_handler(Settings::default())
+            .unwrap();
+        search_box.set_query(query);
+        search_box
+    }
+
+    fn execute(query: &str) -> Option<Action> {
+        Some(Action::Search(Search::Execute(query.into())))
+    }
+
+    #[test]
+    fn test_history_recall() {
+        let mut search_box = search_box("fir");
+        let history = vec!["firefox".to_string(), "2*3".to_string()];
+        search_box.update(Action::QueryHistory(history)).unwrap();
+        let up = Action::Navigate(NavigateDirection::Up, 1);
+        let down = Action::Navigate(NavigateDirection::Down, 1);
+
+        assert_eq!(search_box.update(up.clone()).unwrap(), execute("firefox"));
+        assert_eq!(search_box.update(up.clone()).unwrap(), execute("2*3"));
+        assert_eq!(search_box.update(up.clone()).unwrap(), None);
+        assert_eq!(search_box.update(down.clone()).unwrap(), execute("firefox"));
+        assert_eq!(search_box.update(down.clone()).unwrap(), execute("fir"));
+        assert_eq!(search_box.update(down).unwrap(), None);
+
+        // with results Up moves through them instead, Ctrl + r still recalls
+        search_box
+            .update(Action::SearchResults(vec![ListResult::default()]))
+            .unwrap();
+        assert_eq!(search_box.update(up).unwrap(), None);
+        assert_eq!(
+            search_box.update(Action::HistoryPrevious).unwrap(),
+            execute("firefox")
+        );
+    }
+
+    #[test]
+    fn test_accept_completion() {
+        let mut search_box = search_box("fir");
+        search_box
+            .update(Action::Completion(Some("firefox".into())))
+            .unwrap();
+        assert_eq!(search_box.ghost_text(), Some("efox".into()));
+
+        // only while the caret is at the end
+        search_box
+            .update(Action::Edit(EditCommand::WordBack))
+            .unwrap();
+        assert_eq!(search_box.ghost_text(), None);
+        assert_eq!(search_box.update(Action::AcceptCompletion).unwrap(), None);
+
+        search_box
+            .update(Action::Edit(EditCommand::LineEnd))
+            .unwrap();
+        assert_eq!(
+            search_box.update(Action::AcceptCompletion).unwrap(),
+            execute("firefox")
+        );
+        assert_eq!(search_box.ghost_text(), None);
+    }
+
+    #[test]
+    fn test_edit_commands() {
+        let mut search_box = search_box("open some file");
+        let mut edit = |command| search_box.update(Action::Edit(command)).unwrap();
+        assert_eq!(edit(EditCommand::DeleteWordBack), execute("open some "));
+        assert_eq!(edit(EditCommand::WordBack), None);
+        assert_eq!(edit(EditCommand::DeleteToStart), execute("some "));
+        assert_eq!(edit(EditCommand::LineEnd), None);
+        assert_eq!(edit(EditCommand::LineStart), None);
+        assert_eq!(edit(EditCommand::DeleteToEnd), execute(""));
+    }
+
+    #[test]
+    fn test_prefix_scope() {
+        let mut search_box = search_box("");
+        let prefixes = vec![("=".to_string(), "Calculator".to_string())];
+        search_box.update(Action::ModulePrefixes(prefixes)).unwrap();
+        search_box.set_query("=2*3");
+        assert_eq!(search_box.text(), "2*3");
+        assert_eq!(search_box.query(), "=2*3");
+        assert_eq!(search_box.scope, Some(("=".into(), "Calculator".into())));
+
+        // Backspace on the empty text leaves the scope
+        let backspace = KeyEvent::from(KeyCode::Backspace);
+        search_box.set_query("=");
+        assert_eq!(search_box.text(), "");
+        assert_eq!(search_box.handle_key_event(backspace).unwrap(), execute(""));
+        assert_eq!(search_box.scope, None);
+        assert_eq!(
+            search_box
+                .handle_key_event(KeyEvent::from(KeyCode::Char('=')))
+                .unwrap(),
+            execute("=")
+        );
+        assert!(search_box.scope.is_some());
+    }
+}
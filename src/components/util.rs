@@ -5,6 +5,7 @@ use ratatui::{
     widgets::Borders,
 };
 use serde::{Deserialize, Serialize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::{common::module_state::UISection, settings::settings::Settings};
 
@@ -16,63 +17,188 @@ pub enum IconMode {
     Normal,
     Subscript,
 }
-// small = ➀➁➂➃➄➅➆➇➈➉
-// circle = ❶❷❸❹❺❻❼❽❾❿
+// where a name too long for its line is cut, see `ui.results.truncation`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Truncation {
+    Start,
+    Middle,
+    #[default]
+    End,
+}
+
+// small = ➀➁➂➃➄➅➆➇➈➉ ⑪⑫..㊿
+// circle = ❶❷❸❹❺❻❼❽❾❿ ⓫⓬..⓴
 // normal = 1 2 3 4 5 6 7 8 9 10
+// subscript = ₁ ₂ ₃ .. ₁₀ ₁₁
 
 // im using font https://qwerasd205.github.io/PixelCode/index.html which have different looks
 // small is just a small version of the number, like a subscript "₁" but not quite
 // circle is a filled circle with the number inside
 // normal is just the number itself
+// numbers without a circled character fall back to normal
 pub fn number_to_icon(number: usize, mode: IconMode) -> String {
     assert!(number > 0, "Number must be greater than 0");
-    match (number, mode) {
-        (1, IconMode::Circle) => "❶",
-        (2, IconMode::Circle) => "❷",
-        (3, IconMode::Circle) => "❸",
-        (4, IconMode::Circle) => "❹",
-        (5, IconMode::Circle) => "❺",
-        (6, IconMode::Circle) => "❻",
-        (7, IconMode::Circle) => "❼",
-        (8, IconMode::Circle) => "❽",
-        (9, IconMode::Circle) => "❾",
-        (10, IconMode::Circle) => "❿",
-        //
-        (1, IconMode::Small) => "➀",
-        (2, IconMode::Small) => "➁",
-        (3, IconMode::Small) => "➂",
-        (4, IconMode::Small) => "➃",
-        (5, IconMode::Small) => "➄",
-        (6, IconMode::Small) => "➅",
-        (7, IconMode::Small) => "➆",
-        (8, IconMode::Small) => "➇",
-        (9, IconMode::Small) => "➈",
-        (10, IconMode::Small) => "➉",
-        //
-        (1, IconMode::Normal) => "1",
-        (2, IconMode::Normal) => "2",
-        (3, IconMode::Normal) => "3",
-        (4, IconMode::Normal) => "4",
-        (5, IconMode::Normal) => "5",
-        (6, IconMode::Normal) => "6",
-        (7, IconMode::Normal) => "7",
-        (8, IconMode::Normal) => "8",
-        (9, IconMode::Normal) => "9",
-        (10, IconMode::Normal) => "10",
-        //
-        (1, IconMode::Subscript) => "₁",
-        (2, IconMode::Subscript) => "₂",
-        (3, IconMode::Subscript) => "₃",
-        (4, IconMode::Subscript) => "₄",
-        (5, IconMode::Subscript) => "₅",
-        (6, IconMode::Subscript) => "₆",
-        (7, IconMode::Subscript) => "₇",
-        (8, IconMode::Subscript) => "₈",
-        (9, IconMode::Subscript) => "₉",
-        (10, IconMode::Subscript) => "₁₀",
-        _ => " ", // fallback for numbers > 10
+    let offset_char = |start: u32, first: usize| {
+        char::from_u32(start + (number - first) as u32).map(|c| c.to_string())
+    };
+    let icon = match (number, mode) {
+        (1..=10, IconMode::Circle) => offset_char(0x2776, 1), // ❶
+        (11..=20, IconMode::Circle) => offset_char(0x24EB, 11), // ⓫
+        (1..=10, IconMode::Small) => offset_char(0x2780, 1),  // ➀
+        (11..=20, IconMode::Small) => offset_char(0x246A, 11), // ⑪
+        (21..=35, IconMode::Small) => offset_char(0x3251, 21), // ㉑
+        (36..=50, IconMode::Small) => offset_char(0x32B1, 36), // ㊱
+        (_, IconMode::Subscript) => Some(
+            number
+                .to_string()
+                .chars()
+                .filter_map(|digit| digit.to_digit(10))
+                .filter_map(|digit| char::from_u32(0x2080 + digit)) // ₀
+                .collect(),
+        ),
+        _ => None,
+    };
+    icon.unwrap_or_else(|| number.to_string())
+}
+
+/// Shorten text to a display width, with an ellipsis where it was cut.
+///
+/// Widths are terminal columns, so wide characters such as "日" count twice.
+pub fn truncate(text: &str, width: usize, truncation: Truncation) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    // chars that fit in a width, from the start or the end of the text
+    let fit = |chars: &mut dyn Iterator<Item = char>, width: usize| {
+        let mut used = 0;
+        chars
+            .take_while(|c| {
+                used += c.width().unwrap_or(0);
+                used <= width
+            })
+            .collect::<String>()
+    };
+    let from_end = |width: usize| -> String {
+        let mut chars: Vec<char> = fit(&mut text.chars().rev(), width).chars().collect();
+        chars.reverse();
+        chars.into_iter().collect()
+    };
+    let available = width - 1; // the ellipsis
+    match truncation {
+        Truncation::Start => format!("…{}", from_end(available)),
+        Truncation::Middle => {
+            let head = available.div_ceil(2);
+            format!(
+                "{}…{}",
+                fit(&mut text.chars(), head),
+                from_end(available - head)
+            )
+        }
+        Truncation::End => format!("{}…", fit(&mut text.chars(), available)),
+    }
+}
+
+/// Pad or truncate text to exactly a display width.
+pub fn fit_width(text: &str, width: usize, truncation: Truncation) -> String {
+    let text = truncate(text, width, truncation);
+    let padding = width.saturating_sub(text.width());
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+/// Lay out a line as a name filling the width and right aligned columns after it,
+/// i.e. "App Name       123". Columns are kept whole, the name is truncated to fit.
+///
+/// # Returns
+///
+/// * `(String, String)` - The padded name and the columns, each column after a space
+pub fn layout_columns(
+    name: &str,
+    columns: &[&str],
+    width: usize,
+    truncation: Truncation,
+) -> (String, String) {
+    let columns: String = columns
+        .iter()
+        .filter(|column| !column.is_empty())
+        .map(|column| format!(" {}", column))
+        .collect();
+    let columns = truncate(&columns, width, Truncation::Start);
+    let name_width = width.saturating_sub(columns.width());
+    (fit_width(name, name_width, truncation), columns)
+}
+
+/// A rectangle of at most the given size in the middle of an area, for overlays.
+pub fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Break text into lines of at most a display width, between words where it can.
+///
+/// Words wider than a line are split, each line of the text starts a new line.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = vec![];
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let separator = if line.is_empty() { 0 } else { 1 };
+            if line.width() + separator + word.width() <= width {
+                if separator > 0 {
+                    line.push(' ');
+                }
+                line.push_str(word);
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for c in word.chars() {
+                if line.width() + c.width().unwrap_or(0) > width {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.push(c);
+            }
+        }
+        lines.push(line);
     }
-    .to_string()
+    lines
+}
+
+/// Prefix free labels made from `keys`, for launching results by typing a label.
+/// Uses single keys when there are enough, otherwise as few two key labels as possible.
+pub fn hint_labels(count: usize, keys: &str) -> Vec<String> {
+    let keys: Vec<char> = keys.chars().collect();
+    if keys.len() < 2 {
+        return vec![];
+    }
+    // start with single keys and split the first label into one label per key, until
+    // there are enough
+    let mut labels: std::collections::VecDeque<String> =
+        keys.iter().map(|key| key.to_string()).collect();
+    let mut offset = 0;
+    while labels.len() - offset < count {
+        let Some(label) = labels.get(offset).cloned() else {
+            break;
+        };
+        offset += 1;
+        for key in keys.iter() {
+            labels.push_back(format!("{}{}", label, key));
+        }
+    }
+    let mut labels: Vec<String> = labels.into_iter().skip(offset).take(count).collect();
+    // shorter labels first
+    labels.sort_by_key(|label| label.len());
+    labels
 }
 
 pub fn collapsed_border(
@@ -117,24 +243,6 @@ pub fn collapsed_border(
     }
 }
 
-pub fn calculate_minimum_size(settings: &Settings) -> Rect {
-    let mut min_width = 20;
-    let mut min_height = 5;
-
-    min_height += ((settings.ui.layout.gap.saturating_sub(1)) * 2) + 1;
-    min_height += (settings.ui.layout.padding * 2) + 1;
-
-    min_height += settings.ui.results.padding.saturating_sub(1) * 2;
-    min_height += settings.ui.search.padding * 2;
-
-    min_width += settings.ui.layout.padding * 2;
-    min_width += settings.ui.search.padding * 2;
-    min_width += settings.ui.results.padding.saturating_sub(1) * 2;
-    // hardcoded minimum size for now
-    // later can be calculated based on font size and ui settings
-    Rect::new(0, 0, min_width, min_height)
-}
-
 pub fn loading_spinner(tick: u64) -> String {
     let remainder = tick % 4;
     if remainder == 0 {
@@ -190,3 +298,74 @@ pub fn calculate_color_fade(start_color: Color, position: usize, height: usize)
         start_color
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_to_icon() {
+        assert_eq!(number_to_icon(1, IconMode::Circle), "❶");
+        assert_eq!(number_to_icon(10, IconMode::Small), "➉");
+        assert_eq!(number_to_icon(12, IconMode::Small), "⑫");
+        assert_eq!(number_to_icon(36, IconMode::Small), "㊱");
+        assert_eq!(number_to_icon(11, IconMode::Circle), "⓫");
+        assert_eq!(number_to_icon(21, IconMode::Circle), "21");
+        assert_eq!(number_to_icon(10, IconMode::Subscript), "₁₀");
+        assert_eq!(number_to_icon(123, IconMode::Normal), "123");
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("Firefox", 10, Truncation::End), "Firefox");
+        assert_eq!(truncate("Firefox", 5, Truncation::End), "Fire…");
+        assert_eq!(truncate("Firefox", 5, Truncation::Start), "…efox");
+        assert_eq!(truncate("Firefox", 5, Truncation::Middle), "Fi…ox");
+        assert_eq!(truncate("Firefox", 0, Truncation::End), "");
+        // wide characters take two columns and are never split
+        assert_eq!(truncate("日本語の名前", 6, Truncation::End), "日本…");
+        assert_eq!(fit_width("日本語", 7, Truncation::End), "日本語 ");
+        assert_eq!(fit_width("日本語", 5, Truncation::End), "日本…");
+    }
+
+    #[test]
+    fn test_layout_columns() {
+        let (name, columns) = layout_columns("Firefox", &["123"], 14, Truncation::End);
+        assert_eq!((name.as_str(), columns.as_str()), ("Firefox   ", " 123"));
+        let (name, columns) = layout_columns("日本語の名前", &["9"], 8, Truncation::End);
+        assert_eq!((name.as_str(), columns.as_str()), ("日本… ", " 9"));
+        // no score, no column and no underflow
+        let (name, columns) = layout_columns("Firefox", &[""], 8, Truncation::End);
+        assert_eq!((name.as_str(), columns.as_str()), ("Firefox ", ""));
+        assert_eq!(
+            layout_columns("Firefox", &["123"], 0, Truncation::End).0,
+            ""
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("kitty not found", 10), vec!["kitty not", "found"]);
+        assert_eq!(wrap("one\ntwo", 10), vec!["one", "two"]);
+        assert_eq!(wrap("abcdefgh ij", 3), vec!["abc", "def", "gh", "ij"]);
+        assert_eq!(wrap("日本語", 4), vec!["日本", "語"]);
+        assert_eq!(wrap("", 4), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_hint_labels() {
+        assert_eq!(hint_labels(1, "asdf"), vec!["a"]);
+        assert_eq!(hint_labels(3, "asdf"), vec!["a", "s", "d"]);
+        let labels = hint_labels(6, "asd");
+        assert_eq!(labels, vec!["d", "aa", "as", "ad", "sa", "ss"]);
+        // no label is the start of another
+        for label in labels.iter() {
+            assert!(
+                !labels
+                    .iter()
+                    .any(|other| other != label && other.starts_with(label.as_str()))
+            );
+        }
+        assert_eq!(hint_labels(20, "asdfghjkl").len(), 20);
+    }
+}
@@ -59,75 +59,6 @@ impl WizardBox {
             root_layout: crate::common::layout::RootLayout::default(),
         }
     }
-
-    pub fn construct_list(
-        &self,
-        results: &Vec<ListResult>,
-        number_mode: IconMode,
-        executing_item: Option<usize>,
-        list_state: &ListState,
-        area: Rect,
-        tick: u64,
-    ) -> Vec<ListItem<'static>> {
-        let theme = self
-            .settings
-            .as_ref()
-            .unwrap()
-            .ui
-            .theme
-            .get_results_colors();
-        let available_height = area.height as usize;
-        let mut i = 1;
-        let items: Vec<ListItem<'static>> = results
-            .iter()
-            // .map(|(score, idx)| {
-            .map(|r| {
-                let result = &r.result;
-                let score = &r.score;
-
-                // space out sections to fit the width
-                // let app = &state.data.applications[*idx];
-
-                let score_text = score.to_string();
-
-                // get number icon
-                // mode configurable in settings
-                let mut prepend_icon = number_to_icon(i, number_mode);
-
-                // pad score to end i.e. "App Name       123"
-                let line_width = area.width as usize;
-                let mut name_width = line_width.saturating_sub(score_text.len() - 1);
-                if prepend_icon.trim().is_empty() {
-                    name_width = name_width.saturating_sub(4); // extra space if no icon
-                } else {
-                    name_width = name_width.saturating_sub(prepend_icon.len() + 1); // +1 for space
-                }
-                let padded_name = format!("{:<width$}", result, width = name_width);
-
-                let mut text_color = theme.text.unwrap();
-                let mut muted_color = theme.text_muted.unwrap();
-
-                // construct line
-                let line = Line::from(vec![
-                    // number index
-                    Span::styled(
-                        format!("{} ", prepend_icon),
-                        Style::default().fg(theme.accent.unwrap()),
-                    ),
-                    Span::styled(padded_name.clone(), Style::default().fg(text_color)), // name
-                    if self.settings.as_ref().unwrap().ui.results.show_scores {
-                        Span::styled(score_text.clone(), Style::default().fg(muted_color))
-                    } else {
-                        Span::raw("")
-                    },
-                ]);
-                i += 1;
-                ListItem::new(line)
-            })
-            .collect::<Vec<ListItem>>();
-
-        items
-    }
 }
 
 impl Component for WizardBox {
@@ -155,9 +86,6 @@ impl Component for WizardBox {
         }
 
         match event.unwrap() {
-            Event::Key(key) => {
-                return self.handle_key_event(key);
-            }
             Event::Mouse(mouse) => {
                 return self.handle_mouse_event(mouse);
             }
@@ -167,17 +95,6 @@ impl Component for WizardBox {
         Ok(None)
     }
 
-    fn handle_key_event(
-        &mut self,
-        key: crossterm::event::KeyEvent,
-    ) -> color_eyre::eyre::Result<Option<crate::action::Action>> {
-        if !self.focused {
-            return Ok(None);
-        }
-        return self
-            .list_state
-            .handle_key_event(&key, self.settings.as_ref().unwrap());
-    }
     fn handle_mouse_event(
         &mut self,
         mouse: crossterm::event::MouseEvent,
@@ -186,9 +103,7 @@ impl Component for WizardBox {
             return Ok(None);
         }
 
-        return self
-            .list_state
-            .handle_mouse_event(&mouse, self.settings.as_ref().unwrap());
+        return self.list_state.handle_mouse_event(&mouse);
     }
 
     fn update(
@@ -211,6 +126,9 @@ impl Component for WizardBox {
             Action::UpdateLayout(layout) => {
                 self.root_layout = layout;
             }
+            Action::Navigate(..) | Action::Execute(..) if self.focused => {
+                return Ok(self.list_state.handle_action(&action));
+            }
             _ => {}
         }
         Ok(None)
@@ -266,26 +184,41 @@ impl Component for WizardBox {
             ListResult {
                 result: "Wizard Step 1: Choose Option A".to_string(),
                 score: 100,
+                group: String::new(),
                 launch: Rc::new(|| {
                     log::info!("Launching Wizard Step 1");
-                    true
+                    Ok(None)
                 }),
+                secondary: None,
+                action: None,
+                detail: None,
+                markable: false,
             },
             ListResult {
                 result: "Wizard Step 2: Configure Settings".to_string(),
                 score: 90,
+                group: String::new(),
                 launch: Rc::new(|| {
                     log::info!("Launching Wizard Step 2");
-                    true
+                    Ok(None)
                 }),
+                secondary: None,
+                action: None,
+                detail: None,
+                markable: false,
             },
             ListResult {
                 result: "Wizard Step 3: Review and Confirm".to_string(),
                 score: 80,
+                group: String::new(),
                 launch: Rc::new(|| {
                     log::info!("Launching Wizard Step 3");
-                    true
+                    Ok(None)
                 }),
+                secondary: None,
+                action: None,
+                detail: None,
+                markable: false,
             },
         ];
 
@@ -19,9 +19,15 @@ impl Database {
         })
     }
     pub fn initialise(&self) -> Result<()> {
-        use crate::db::one;
-        let migration_sql = one::MIGRATION;
-        self.connection.execute_batch(&migration_sql)?;
+        use crate::db::{four, one, three, two};
+        for migration_sql in [
+            one::MIGRATION,
+            two::MIGRATION,
+            three::MIGRATION,
+            four::MIGRATION,
+        ] {
+            self.connection.execute_batch(migration_sql)?;
+        }
         log::info!("Database initialised.");
         Ok(())
     }
@@ -50,4 +56,190 @@ impl Database {
         )?;
         Ok(())
     }
+
+    /// Remember a submitted or launched query, keeping the latest `limit` queries.
+    pub fn add_query_history(&self, query: &str, limit: usize) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO query_history (query) VALUES (?1)
+             ON CONFLICT(query) DO UPDATE SET id = (SELECT MAX(id) + 1 FROM query_history), used_at = CURRENT_TIMESTAMP",
+            params![query],
+        )?;
+        self.connection.execute(
+            "DELETE FROM query_history WHERE id NOT IN (SELECT id FROM query_history ORDER BY id DESC LIMIT ?1)",
+            params![limit as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Past queries, most recent first.
+    pub fn query_history(&self) -> Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT query FROM query_history ORDER BY id DESC")?;
+        statement
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()
+    }
+
+    /// Forget every past query.
+    pub fn clear_query_history(&self) -> Result<()> {
+        self.connection.execute("DELETE FROM query_history", [])?;
+        Ok(())
+    }
+
+    /// Remember a calculation, keeping the latest `limit` calculations.
+    pub fn add_calculation(&self, expression: &str, result: &str, limit: usize) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO calculator_history (expression, result) VALUES (?1, ?2)
+             ON CONFLICT(expression) DO UPDATE SET id = (SELECT MAX(id) + 1 FROM calculator_history), result = ?2, used_at = CURRENT_TIMESTAMP",
+            params![expression, result],
+        )?;
+        self.connection.execute(
+            "DELETE FROM calculator_history WHERE id NOT IN (SELECT id FROM calculator_history ORDER BY id DESC LIMIT ?1)",
+            params![limit as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Past calculations, as the expression and its result, most recent first.
+    pub fn calculations(&self) -> Result<Vec<(String, String)>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT expression, result FROM calculator_history ORDER BY id DESC")?;
+        statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect()
+    }
+
+    /// Forget every past calculation.
+    pub fn clear_calculations(&self) -> Result<()> {
+        self.connection
+            .execute("DELETE FROM calculator_history", [])?;
+        Ok(())
+    }
+
+    /// Save a calculator variable or function, replacing an earlier one of the same name.
+    pub fn set_definition(&self, name: &str, definition: &str) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO calculator_definitions (name, definition) VALUES (?1, ?2)
+             ON CONFLICT(name) DO UPDATE SET definition = ?2",
+            params![name, definition],
+        )?;
+        Ok(())
+    }
+
+    /// The saved calculator variables and functions, as i.e. "x=15" or "f(x)=x^2".
+    pub fn definitions(&self) -> Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT definition FROM calculator_definitions ORDER BY name")?;
+        statement
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()
+    }
+
+    /// Forget every calculator variable and function.
+    pub fn clear_definitions(&self) -> Result<()> {
+        self.connection
+            .execute("DELETE FROM calculator_definitions", [])?;
+        Ok(())
+    }
+
+    /// Record a launch and why it failed, if it did, keeping the latest `limit` launches.
+    pub fn add_launch(
+        &self,
+        name: &str,
+        query: &str,
+        error: Option<&str>,
+        limit: usize,
+    ) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO launch_history (name, query, error) VALUES (?1, ?2, ?3)",
+            params![name, query, error],
+        )?;
+        self.connection.execute(
+            "DELETE FROM launch_history WHERE id NOT IN (SELECT id FROM launch_history ORDER BY id DESC LIMIT ?1)",
+            params![limit as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Past launches, as the name and why it failed if it did, most recent first.
+    pub fn launch_history(&self) -> Result<Vec<(String, Option<String>)>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT name, error FROM launch_history ORDER BY id DESC")?;
+        statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_history() {
+        let database = Database::new(":memory:").unwrap();
+        database.initialise().unwrap();
+        for query in ["firefox", "2*3", "code", "firefox"] {
+            database.add_query_history(query, 3).unwrap();
+        }
+        assert_eq!(
+            database.query_history().unwrap(),
+            vec!["firefox", "code", "2*3"]
+        );
+        database.add_query_history("term", 3).unwrap();
+        assert_eq!(
+            database.query_history().unwrap(),
+            vec!["term", "firefox", "code"]
+        );
+        database.clear_query_history().unwrap();
+        assert!(database.query_history().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_calculator() {
+        let database = Database::new(":memory:").unwrap();
+        database.initialise().unwrap();
+        // exact expressions, "1+1" doesn't replace "1+12"
+        for (expression, result) in [("1+12", "13"), ("1+1", "2"), ("1+12", "13")] {
+            database.add_calculation(expression, result, 10).unwrap();
+        }
+        assert_eq!(
+            database.calculations().unwrap(),
+            vec![
+                ("1+12".to_string(), "13".to_string()),
+                ("1+1".to_string(), "2".to_string())
+            ]
+        );
+        database.clear_calculations().unwrap();
+        assert!(database.calculations().unwrap().is_empty());
+
+        database.set_definition("x", "x=15").unwrap();
+        database.set_definition("f", "f(x)=x^2").unwrap();
+        database.set_definition("x", "x=3").unwrap();
+        assert_eq!(database.definitions().unwrap(), vec!["f(x)=x^2", "x=3"]);
+        database.clear_definitions().unwrap();
+        assert!(database.definitions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_launch_history() {
+        let database = Database::new(":memory:").unwrap();
+        database.initialise().unwrap();
+        database.add_launch("Firefox", "fir", None, 2).unwrap();
+        database
+            .add_launch("Broken", "bro", Some("broken not found"), 2)
+            .unwrap();
+        database.add_launch("Code", "co", None, 2).unwrap();
+        assert_eq!(
+            database.launch_history().unwrap(),
+            vec![
+                ("Code".to_string(), None),
+                ("Broken".to_string(), Some("broken not found".to_string()))
+            ]
+        );
+    }
 }
@@ -0,0 +1,10 @@
+pub const MIGRATION: &str = "CREATE TABLE IF NOT EXISTS calculator_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    expression TEXT NOT NULL UNIQUE, -- a calculation done again moves to the top
+    result TEXT NOT NULL,
+    used_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE TABLE IF NOT EXISTS calculator_definitions (
+    name TEXT PRIMARY KEY,
+    definition TEXT NOT NULL -- i.e. \"x=15\" or \"f(x)=x^2\", defined again on startup
+);";
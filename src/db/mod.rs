@@ -1 +1,4 @@
+pub mod four;
 pub mod one;
+pub mod three;
+pub mod two;
@@ -10,7 +10,7 @@ CREATE VIRTUAL TABLE IF NOT EXISTS applications_fts USING fts5(
     name,
     content='applications',
     content_rowid='id',
-    // tokenize='ascii trigram'
+    -- tokenize='ascii trigram'
 );
 
 CREATE INDEX IF NOT EXISTS idx_applications_name ON applications (name);
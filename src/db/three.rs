@@ -0,0 +1,7 @@
+pub const MIGRATION: &str = "CREATE TABLE IF NOT EXISTS launch_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    query TEXT NOT NULL,
+    error TEXT, -- why it failed, NULL when it launched
+    launched_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);";
@@ -0,0 +1,5 @@
+pub const MIGRATION: &str = "CREATE TABLE IF NOT EXISTS query_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    query TEXT NOT NULL UNIQUE, -- a query used again moves to the top instead of repeating
+    used_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);";
@@ -1,53 +1,260 @@
+use std::time::Instant;
+
 use ratatui::{buffer::Buffer, layout::Rect, style::Color};
+use serde::{Deserialize, Serialize};
 use tachyonfx::{
-    Duration, EffectManager, fx,
-    pattern::{self, SweepPattern},
+    Duration, Effect, EffectManager, EffectTimer, Interpolation, Motion, fx,
+    pattern::{DiagonalPattern, DissolvePattern, RadialPattern, SweepPattern},
+};
+
+use crate::settings::settings::{
+    Easing, EffectKind, EffectPattern, EffectSettings, Settings, UIEffectsSettings,
 };
 
-pub fn rainbow(
-    start_color: Color,
-    timer: u32,
-    speed: f32,
-    area: Rect,
-    buf: &mut Buffer,
-    tick: u32,
-) {
-    let fg_shift = [1440.0, 0.0, 0.0];
-    let mut timer = timer;
-    let mut effects: EffectManager<()> = EffectManager::default();
-    let mut fx_rainbow = fx::hsl_shift_fg(fg_shift, timer)
-        .with_pattern(SweepPattern::left_to_right(area.width as u16 * 2))
-        .with_area(area);
-    // cut start end portion for infinite rainbow
-    fx_rainbow = fx::remap_alpha(0.333, 0.666, fx_rainbow);
-
-    let fx_in = fx::repeating(fx_rainbow.clone());
-
-    let t = (tick * (speed * 10.0) as u32) % timer;
-    // if fade in and out, adjust timer and create fade effects
-
-    let mut fx = fx_rainbow;
-
-    fx = fx::repeat(fx::sequence(&[fx_in]), fx::RepeatMode::Forever);
-
-    effects.add_effect(fx);
-    effects.process_effects(Duration::from_millis((t)), buf, area);
-}
-
-pub fn fade_in(
-    start_color: Color,
-    duration: u32,
-    fade_direction: Option<pattern::AnyPattern>,
-    area: Rect,
-    buf: &mut Buffer,
-    tick: u32,
-) {
-    let mut effects: EffectManager<()> = EffectManager::default();
-    let mut fx = fx::fade_from_fg(start_color, duration);
-    if let Some(fade_direction) = fade_direction {
-        fx = fx.with_pattern(fade_direction)
-    }
-    fx = fx::remap_alpha(0.1, 1.0, fx);
-    effects.add_effect(fx);
-    effects.process_effects(Duration::from_millis(tick), buf, area);
+/// Something that happened which can have an effect, see `ui.effects` in settings.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum EffectEvent {
+    #[default]
+    Open,
+    Close,
+    Results,
+    Selection,
+    Launch,
+}
+
+impl EffectEvent {
+    fn settings(self, effects: &UIEffectsSettings) -> &EffectSettings {
+        match self {
+            EffectEvent::Open => &effects.open,
+            EffectEvent::Close => &effects.close,
+            EffectEvent::Results => &effects.results,
+            EffectEvent::Selection => &effects.selection,
+            EffectEvent::Launch => &effects.launch,
+        }
+    }
+}
+
+/// Time between frames, measured rather than assumed from the frame rate.
+#[derive(Debug, Clone, Default)]
+pub struct FrameClock {
+    last_frame: Option<Instant>,
+}
+
+impl FrameClock {
+    /// The time since the last call, zero on the first.
+    pub fn delta(&mut self) -> Duration {
+        let now = Instant::now();
+        let delta = self
+            .last_frame
+            .map_or(std::time::Duration::ZERO, |last| now - last);
+        self.last_frame = Some(now);
+        delta.into()
+    }
+}
+
+/// The running effects of the app, kept between frames.
+///
+/// Each event has at most one effect running, triggering it again restarts it.
+#[derive(Default)]
+pub struct Effects {
+    manager: EffectManager<EffectEvent>,
+    clock: FrameClock,
+}
+
+impl Effects {
+    /// Start the effect declared for the event on an area, if there is one.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if an effect was started
+    pub fn trigger(&mut self, settings: &Settings, event: EffectEvent, area: Rect) -> bool {
+        if settings.ui.reduce_motion {
+            return false;
+        }
+        let declared = event.settings(&settings.ui.effects);
+        match build_effect(declared, settings.ui.theme.background) {
+            Some(effect) => {
+                self.manager
+                    .add_unique_effect(event, effect.with_area(area));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Start the close effect, cancelling the effects that only matter while the app is open.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if there is a close effect to wait for before quitting
+    pub fn close(&mut self, settings: &Settings, area: Rect) -> bool {
+        for event in [
+            EffectEvent::Open,
+            EffectEvent::Results,
+            EffectEvent::Selection,
+        ] {
+            self.manager.cancel_unique_effect(event);
+        }
+        self.trigger(settings, EffectEvent::Close, area)
+    }
+
+    /// Draw the running effects over the buffer, advancing them by the time since the last frame.
+    pub fn process(&mut self, buf: &mut Buffer, area: Rect) {
+        let delta = self.clock.delta();
+        self.manager.process_effects(delta, buf, area);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.manager.is_running()
+    }
+}
+
+/// The effect for the settings, None when the kind is "none" or it takes no time.
+pub fn build_effect(settings: &EffectSettings, background: Color) -> Option<Effect> {
+    if settings.duration == 0 {
+        return None;
+    }
+    let timer = EffectTimer::from_ms(settings.duration, settings.easing.into());
+    let effect = match settings.kind {
+        EffectKind::None => return None,
+        EffectKind::FadeIn => fx::fade_from_fg(background, timer),
+        EffectKind::FadeOut => fx::fade_to_fg(background, timer),
+        EffectKind::Dissolve => fx::dissolve(timer),
+        EffectKind::Coalesce => fx::coalesce(timer),
+        // sweeps have their own direction, so the pattern only picks it
+        EffectKind::SweepIn => {
+            return Some(fx::sweep_in(
+                motion(settings.pattern),
+                10,
+                0,
+                background,
+                timer,
+            ));
+        }
+        EffectKind::SweepOut => {
+            return Some(fx::sweep_out(
+                motion(settings.pattern),
+                10,
+                0,
+                background,
+                timer,
+            ));
+        }
+    };
+    Some(match settings.pattern {
+        EffectPattern::None => effect,
+        EffectPattern::LeftToRight => effect.with_pattern(SweepPattern::left_to_right(10)),
+        EffectPattern::RightToLeft => effect.with_pattern(SweepPattern::right_to_left(10)),
+        EffectPattern::UpToDown => effect.with_pattern(SweepPattern::up_to_down(5)),
+        EffectPattern::DownToUp => effect.with_pattern(SweepPattern::down_to_up(5)),
+        EffectPattern::Radial => effect.with_pattern(RadialPattern::center()),
+        EffectPattern::Diagonal => effect.with_pattern(DiagonalPattern::top_left_to_bottom_right()),
+        EffectPattern::Random => effect.with_pattern(DissolvePattern::new()),
+    })
+}
+
+// the direction of a sweep, left to right unless the pattern is another sweep
+fn motion(pattern: EffectPattern) -> Motion {
+    match pattern {
+        EffectPattern::RightToLeft => Motion::RightToLeft,
+        EffectPattern::UpToDown => Motion::UpToDown,
+        EffectPattern::DownToUp => Motion::DownToUp,
+        _ => Motion::LeftToRight,
+    }
+}
+
+impl From<Easing> for Interpolation {
+    fn from(easing: Easing) -> Self {
+        match easing {
+            Easing::Linear => Interpolation::Linear,
+            Easing::QuadIn => Interpolation::QuadIn,
+            Easing::QuadOut => Interpolation::QuadOut,
+            Easing::QuadInOut => Interpolation::QuadInOut,
+            Easing::CubicIn => Interpolation::CubicIn,
+            Easing::CubicOut => Interpolation::CubicOut,
+            Easing::CubicInOut => Interpolation::CubicInOut,
+            Easing::SineIn => Interpolation::SineIn,
+            Easing::SineOut => Interpolation::SineOut,
+            Easing::SineInOut => Interpolation::SineInOut,
+            Easing::ExpoIn => Interpolation::ExpoIn,
+            Easing::ExpoOut => Interpolation::ExpoOut,
+            Easing::ExpoInOut => Interpolation::ExpoInOut,
+            Easing::BackOut => Interpolation::BackOut,
+            Easing::BounceOut => Interpolation::BounceOut,
+            Easing::ElasticOut => Interpolation::ElasticOut,
+        }
+    }
+}
+
+/// A rainbow cycling through a border forever, kept between frames.
+#[derive(Clone, Default)]
+pub struct Rainbow {
+    effect: Option<Effect>,
+    area: Rect, // the effect is made again when this changes
+    clock: FrameClock,
+}
+
+impl Rainbow {
+    /// Shift the colours of the area, speed is a multiple of one cycle every two seconds.
+    pub fn render(&mut self, speed: f32, area: Rect, buf: &mut Buffer) {
+        if self.effect.is_none() || self.area != area {
+            let rainbow = fx::hsl_shift_fg([1440.0, 0.0, 0.0], 2000)
+                .with_pattern(SweepPattern::left_to_right(area.width * 2))
+                .with_area(area);
+            // cut the start and end so the colours wrap around without a jump
+            let rainbow = fx::remap_alpha(0.333, 0.666, rainbow);
+            self.effect = Some(fx::repeating(rainbow));
+            self.area = area;
+        }
+        let delta = self.clock.delta().as_secs_f32() * speed;
+        if let Some(effect) = self.effect.as_mut() {
+            effect.process(Duration::from_secs_f32(delta), buf, area);
+        }
+    }
+}
+
+impl std::fmt::Debug for Rainbow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Rainbow").field("area", &self.area).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_effect() {
+        let mut settings = EffectSettings {
+            kind: EffectKind::Dissolve,
+            duration: 200,
+            easing: Easing::QuadOut,
+            pattern: EffectPattern::Radial,
+        };
+        assert!(build_effect(&settings, Color::Black).is_some());
+        settings.duration = 0;
+        assert!(build_effect(&settings, Color::Black).is_none());
+        settings.duration = 200;
+        settings.kind = EffectKind::None;
+        assert!(build_effect(&settings, Color::Black).is_none());
+    }
+
+    #[test]
+    fn test_reduce_motion() {
+        let mut settings = Settings::default();
+        settings.ui.effects.selection.kind = EffectKind::FadeIn;
+        settings.ui.effects.selection.duration = 100;
+        let area = Rect::new(0, 0, 10, 1);
+
+        let mut effects = Effects::default();
+        assert!(effects.trigger(&settings, EffectEvent::Selection, area));
+        assert!(effects.is_running());
+
+        settings.ui.reduce_motion = true;
+        let mut effects = Effects::default();
+        assert!(!effects.trigger(&settings, EffectEvent::Selection, area));
+        assert!(!effects.is_running());
+    }
 }
@@ -0,0 +1,114 @@
+use std::io::stdout;
+use std::rc::Rc;
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{TerminalOptions, Viewport, backend::CrosstermBackend, layout::Rect};
+
+use crate::{
+    action::Action,
+    common::{color_depth::quantize_buffer, layout::RootLayout},
+    components::{Component, results::ResultsBox, search::SearchBox, wizard::WizardBox},
+    search_modules::ListResult,
+    settings::{
+        settings::Settings,
+        themes::{ThemeSource, list_themes, load_theme},
+    },
+};
+
+// `rook theme list` and `rook theme preview <name>`
+// the preview draws the real components with sample results inline in the terminal, below the prompt
+
+const PREVIEW_HEIGHT: u16 = 16;
+const PREVIEW_MAX_WIDTH: u16 = 90;
+const PREVIEW_QUERY: &str = "term";
+
+pub fn print_theme_list(settings: &Settings) {
+    let current = settings.ui.theme.name.as_deref();
+    for theme in list_themes() {
+        let marker = if Some(theme.name.as_str()) == current {
+            "*"
+        } else {
+            " "
+        };
+        let source = match theme.source {
+            ThemeSource::Builtin => "built in".to_string(),
+            ThemeSource::File(path) => path.to_string_lossy().to_string(),
+        };
+        println!("{} {:<20} {}", marker, theme.name, source);
+    }
+}
+
+fn sample_results() -> Vec<ListResult> {
+    [
+        ("Terminal", 120),
+        ("Terminator", 104),
+        ("GNOME Terminal", 96),
+        ("Kitty terminal emulator", 88),
+        ("Alacritty", 40),
+    ]
+    .into_iter()
+    .map(|(name, score)| ListResult {
+        result: name.to_string(),
+        score,
+        group: String::new(),
+        launch: Rc::new(|| Ok(None)),
+        secondary: None,
+        action: None,
+        detail: None,
+        markable: false,
+    })
+    .collect()
+}
+
+pub fn preview_theme(settings: &Settings, name: &str) -> Result<()> {
+    let mut settings = settings.clone();
+    settings.ui.theme = load_theme(name)?;
+    // effects are time based, so they'd be frozen on their first frame
+    settings.ui.reduce_motion = true;
+    let color_depth = settings.ui.color_depth.resolve();
+
+    let mut terminal = ratatui::Terminal::with_options(
+        CrosstermBackend::new(stdout()),
+        TerminalOptions {
+            viewport: Viewport::Inline(PREVIEW_HEIGHT),
+        },
+    )?;
+    let viewport = terminal.get_frame().area();
+    let area = Rect {
+        width: viewport.width.min(PREVIEW_MAX_WIDTH),
+        ..viewport
+    };
+
+    let mut components: Vec<Box<dyn Component>> = vec![
+        Box::new(SearchBox::new()),
+        Box::new(ResultsBox::new()),
+        Box::new(WizardBox::new()),
+    ];
+    let mut layout = RootLayout::default();
+    layout.calculate_split(area, &settings);
+    for component in components.iter_mut() {
+        component.register_settings_handler(settings.clone())?;
+        component.update(Action::UpdateLayout(layout.clone()))?;
+        component.update(Action::SearchResults(sample_results()))?;
+        // only the focused search box takes the typed query
+        for c in PREVIEW_QUERY.chars() {
+            component.handle_key_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))?;
+        }
+    }
+
+    terminal.draw(|frame| {
+        for component in components.iter_mut() {
+            if let Err(err) = component.draw(frame, area) {
+                log::error!("Failed to draw theme preview: {:?}", err);
+            }
+        }
+        quantize_buffer(
+            frame.buffer_mut(),
+            color_depth,
+            settings.ui.theme.background,
+        );
+    })?;
+    println!();
+    Ok(())
+}
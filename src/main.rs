@@ -1,11 +1,16 @@
 use std::path::PathBuf;
 
 use clap::Parser;
-use cli::Cli;
+use cli::{Cli, Command, ConfigCommand, ThemeCommand};
 use color_eyre::Result;
 use ftail::Ftail;
 
 use crate::app::App;
+use crate::common::clipboard;
+use crate::settings::{
+    layers::{SettingsOverrides, collect_layers, describe_resolved, resolve_layers},
+    settings::Settings,
+};
 
 // mod action;
 mod app;
@@ -18,6 +23,7 @@ mod action;
 mod database;
 mod effects;
 mod errors;
+mod gallery;
 mod logging;
 mod search_modules;
 
@@ -42,11 +48,58 @@ async fn main() -> Result<()> {
         .unwrap();
 
     let args = Cli::parse();
-    let mut app = App::new(args.tick_rate, args.frame_rate).await?;
+    let overrides = SettingsOverrides {
+        profile: args.profile.clone(),
+        set: args.set.clone(),
+    };
+    if let Some(command) = args.command {
+        return run_command(command, &overrides);
+    }
+
+    let settings = Settings::load(&overrides)?;
+    let mut app = App::new(
+        settings,
+        overrides,
+        args.tick_rate,
+        args.frame_rate,
+        args.height,
+    )
+    .await?;
     app.run().await?;
     Ok(())
 }
 
+fn run_command(command: Command, overrides: &SettingsOverrides) -> Result<()> {
+    match command {
+        Command::Config {
+            command: ConfigCommand::Show { resolved },
+        } => {
+            if resolved {
+                let layers = collect_layers(overrides)?;
+                println!("{}", describe_resolved(&resolve_layers(&layers)?));
+            } else {
+                let settings = Settings::load(overrides)?;
+                println!("{}", toml::to_string_pretty(&settings)?);
+            }
+        }
+        Command::Theme { command } => {
+            let settings = Settings::load(overrides)?;
+            match command {
+                ThemeCommand::List => gallery::print_theme_list(&settings),
+                ThemeCommand::Preview { name } => gallery::preview_theme(&settings, &name)?,
+            }
+        }
+        Command::Copy { text } => {
+            // only the last line of stderr is shown when a launch fails
+            if let Err(err) = clipboard::serve(&text) {
+                eprintln!("Could not copy: {}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    Ok(())
+}
+
 mod test {
     #[test]
     fn it_works() {
@@ -1,10 +1,11 @@
 use std::{rc::Rc, sync::Arc, time::Instant};
 
 use crate::{
+    action::{Action, Notice},
     app::App,
     common::application::Application,
     database::Database,
-    search_modules::{ListResult, ScoredResult, SearchModule},
+    search_modules::{ListResult, ScoredResult, SearchModule, Trigger},
     settings::settings::Settings,
 };
 use color_eyre::Result;
@@ -22,6 +23,7 @@ pub struct DesktopFilesModule {
     results: Vec<ScoredResult>,
     data: Option<Box<DesktopData>>,
     database: Option<Arc<Mutex<Database>>>,
+    action_tx: Option<tokio::sync::mpsc::UnboundedSender<Action>>,
 }
 
 impl DesktopFilesModule {
@@ -32,6 +34,7 @@ impl DesktopFilesModule {
             results: Vec::new(),
             data: None,
             database: None,
+            action_tx: None,
         }
     }
     fn get_database(&self) -> Option<tokio::sync::MutexGuard<'_, Database>> {
@@ -46,6 +49,15 @@ impl SearchModule for DesktopFilesModule {
     fn name(&self) -> &str {
         "desktop_files_module"
     }
+    fn title(&self) -> &str {
+        "Applications"
+    }
+    fn triggers(&self) -> Vec<Trigger> {
+        vec![Trigger::new(
+            "firefox",
+            "Search installed applications by name",
+        )]
+    }
     fn init(&mut self) -> Result<()> {
         if self.database.is_none() {
             return Err(color_eyre::eyre::eyre!(
@@ -158,6 +170,45 @@ impl SearchModule for DesktopFilesModule {
         self.database = Some(database);
         Ok(())
     }
+    fn register_action_handler(
+        &mut self,
+        handler: tokio::sync::mpsc::UnboundedSender<Action>,
+    ) -> Result<()> {
+        self.action_tx = Some(handler);
+        Ok(())
+    }
+
+    fn command(&mut self, command: &str) -> Result<bool> {
+        match command {
+            // forget the indexed desktop files, so init finds them again
+            "reindex" => {
+                {
+                    let db = self.get_database().ok_or_else(|| {
+                        color_eyre::eyre::eyre!(
+                            "Database handler not registered for DesktopFilesModule"
+                        )
+                    })?;
+                    db.get_connection().execute(
+                        "DELETE FROM applications WHERE file_type = 'desktop_file'",
+                        [],
+                    )?;
+                }
+                self.results.clear();
+                self.init()?;
+                if let Some(action_tx) = &self.action_tx {
+                    let notice = Notice::info(format!("Indexed {} applications", self.total()));
+                    action_tx.send(Action::Notify(notice)).unwrap();
+                }
+                // run from the palette, whose results stay
+                Ok(false)
+            }
+            _ => Err(color_eyre::eyre::eyre!(
+                "{} has no command {:?}",
+                self.name(),
+                command
+            )),
+        }
+    }
 
     fn search(&mut self, query: &str) -> Result<bool> {
         // ignore empty queries
@@ -185,6 +236,9 @@ impl SearchModule for DesktopFilesModule {
 
         Ok(true)
     }
+    fn total(&self) -> usize {
+        self.data.as_ref().map_or(0, |data| data.applications.len())
+    }
     fn get_ui_results(&self) -> Vec<ListResult> {
         self.results
             .iter()
@@ -198,8 +252,12 @@ impl SearchModule for DesktopFilesModule {
                 ListResult {
                     result: app.name(),
                     score: s,
-                    // source_module: self.name().to_string(),
+                    group: String::new(), // set by the app
                     launch: Rc::new(move || app_clone.launch()),
+                    secondary: None,
+                    action: None,
+                    detail: None,
+                    markable: false,
                 }
             })
             .collect()
@@ -0,0 +1,200 @@
+use nucleo::{Config, Matcher};
+
+use crate::{
+    action::Action,
+    search_modules::{ListResult, ScoredResult, SearchModule, Trigger},
+    settings::{settings::Settings, themes::list_themes},
+};
+use color_eyre::Result;
+
+// a command of the palette, with the keys it's bound to
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Command {
+    name: String,
+    action: Action,
+    keys: Option<String>,
+}
+
+/// Rook's own actions as results, i.e. switching the theme, scoped to with ":".
+pub struct CommandsModule {
+    commands: Vec<Command>, // made again when the settings change, for the keys and themes
+    results: Vec<ScoredResult>,
+}
+
+impl CommandsModule {
+    pub fn new() -> Self {
+        Self {
+            commands: vec![],
+            results: vec![],
+        }
+    }
+}
+
+fn commands(settings: &Settings) -> Vec<Command> {
+    let module_command =
+        |module: &str, command: &str| Action::ModuleCommand(module.into(), command.into());
+    let commands = vec![
+        ("Toggle side panel", Action::ToggleWizard),
+        ("Toggle scores", Action::ToggleScores),
+        ("Show messages", Action::ToggleMessages),
+        ("Show launch history", Action::ShowLaunchHistory),
+        ("Show help", Action::ToggleHelp),
+        ("Reload settings", Action::ReloadSettings),
+        ("Edit settings in $EDITOR", Action::EditSettings),
+        (
+            "Reindex applications",
+            module_command("desktop_files_module", "reindex"),
+        ),
+        ("Clear query history", Action::ClearHistory),
+        (
+            "Clear calculator history",
+            module_command("maths_module", "clear_history"),
+        ),
+        (
+            "Clear calculator variables",
+            module_command("maths_module", "clear_variables"),
+        ),
+        ("Suspend", Action::Suspend),
+        ("Quit", Action::Quit),
+    ];
+    let themes = list_themes().into_iter().map(|theme| {
+        (
+            format!("Theme: {}", theme.name),
+            Action::SetTheme(theme.name),
+        )
+    });
+    commands
+        .into_iter()
+        .map(|(name, action)| (name.to_string(), action))
+        .chain(themes)
+        .map(|(name, action)| {
+            let keys: Vec<String> = settings
+                .keybinds
+                .keys_for(&action)
+                .iter()
+                .map(|keys| keys.to_string())
+                .collect();
+            Command {
+                name,
+                keys: (!keys.is_empty()).then(|| keys.join(", ")),
+                action,
+            }
+        })
+        .collect()
+}
+
+impl SearchModule for CommandsModule {
+    fn name(&self) -> &str {
+        "commands_module"
+    }
+    fn title(&self) -> &str {
+        "Commands"
+    }
+
+    fn register_settings_handler(&mut self, settings: Settings) -> Result<()> {
+        self.commands = commands(&settings);
+        Ok(())
+    }
+
+    // every command for an empty query, in the order above
+    fn search(&mut self, query: &str) -> Result<bool> {
+        let count = self.commands.len();
+        if query.trim().is_empty() {
+            self.results = (0..count)
+                .map(|index| ScoredResult {
+                    index,
+                    score: (count - index) as u16,
+                })
+                .collect();
+            return Ok(count > 0);
+        }
+        let mut matcher = Matcher::new(Config::DEFAULT);
+        let query = query.trim().to_lowercase();
+        self.results = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(index, command)| {
+                let score = matcher.fuzzy_match(
+                    nucleo::Utf32Str::new(&command.name.to_lowercase(), &mut vec![]),
+                    nucleo::Utf32Str::new(&query, &mut vec![]),
+                )?;
+                Some(ScoredResult { index, score })
+            })
+            .collect();
+        self.results
+            .sort_by_key(|result| std::cmp::Reverse(result.score));
+        Ok(!self.results.is_empty())
+    }
+
+    fn prefix(&self) -> Option<&str> {
+        Some(":")
+    }
+
+    fn scoped_only(&self) -> bool {
+        true
+    }
+
+    fn batch_execute(&self) -> bool {
+        false
+    }
+
+    fn triggers(&self) -> Vec<Trigger> {
+        vec![
+            Trigger::new(":theme", "Switch the theme until rook is closed"),
+            Trigger::new(":reload", "Reload the settings"),
+        ]
+    }
+
+    fn total(&self) -> usize {
+        self.commands.len()
+    }
+
+    fn get_ui_results(&self) -> Vec<ListResult> {
+        self.results
+            .iter()
+            .map(|scored| {
+                let command = &self.commands[scored.index];
+                ListResult {
+                    result: command.name.clone(),
+                    score: scored.score,
+                    action: Some(Box::new(command.action.clone())),
+                    detail: command.keys.clone(),
+                    ..ListResult::default()
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module() -> CommandsModule {
+        let mut module = CommandsModule::new();
+        module
+            .register_settings_handler(Settings::default())
+            .unwrap();
+        module
+    }
+
+    #[test]
+    fn test_search_commands() {
+        let mut module = module();
+        assert!(module.search("").unwrap());
+        assert_eq!(module.get_ui_results().len(), module.total());
+
+        assert!(module.search("side").unwrap());
+        let top = module.get_ui_results().remove(0);
+        assert_eq!(top.result, "Toggle side panel");
+        assert_eq!(top.action, Some(Box::new(Action::ToggleWizard)));
+        assert_eq!(top.detail.as_deref(), Some("Ctrl + b"));
+
+        assert!(module.search("theme nord").unwrap());
+        let top = module.get_ui_results().remove(0);
+        assert_eq!(top.action, Some(Box::new(Action::SetTheme("nord".into()))));
+
+        assert!(!module.search("zzzz").unwrap());
+    }
+}
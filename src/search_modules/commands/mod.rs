@@ -0,0 +1 @@
+pub mod commands_module;
@@ -0,0 +1,345 @@
+use std::collections::BTreeMap;
+
+use lexers::MathToken;
+use shunting::{MathContext, RPNExpr, ShuntingParser};
+
+/// Named constants, a variable of the same name can't be set.
+pub const CONSTANTS: &[(&str, f64)] = &[
+    ("pi", std::f64::consts::PI),
+    ("e", std::f64::consts::E),
+    ("tau", std::f64::consts::TAU),
+    ("phi", 1.618_033_988_749_895),
+];
+
+/// Functions of one argument on top of the ones the parser knows.
+pub const FUNCTIONS: &[&str] = &["sqrt", "tan", "ln", "exp", "floor", "ceil", "round"];
+
+// the parser's own functions and distributions, names that can't be defined
+const PARSER_FUNCTIONS: &[&str] = &[
+    "abs",
+    "atan2",
+    "cos",
+    "log",
+    "max",
+    "min",
+    "nCr",
+    "nMCr",
+    "nMPr",
+    "nPr",
+    "rand",
+    "sin",
+    "normal",
+    "uniform",
+    "lognormal",
+];
+
+// how deep functions may call each other, so a function calling itself fails
+const MAX_DEPTH: usize = 32;
+
+/// What a line of calculator input does, applied with [`Calculator::apply`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Expression(f64),
+    Assign(String, f64),                 // a variable, i.e. "x=5*3"
+    Define(String, Vec<String>, String), // a function, its parameters and body, i.e. "f(x)=x^2"
+}
+
+impl Statement {
+    /// The value calculated, None for a function definition.
+    pub fn value(&self) -> Option<f64> {
+        match self {
+            Statement::Expression(value) | Statement::Assign(_, value) => Some(*value),
+            Statement::Define(..) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Function {
+    params: Vec<String>,
+    body: RPNExpr,
+}
+
+/// Evaluates expressions with variables, `ans` and user defined functions.
+///
+/// The parser's [`MathContext`] evaluates the operators and its own functions, arguments and
+/// variables are resolved here first.
+pub struct Calculator {
+    variables: BTreeMap<String, f64>,
+    functions: BTreeMap<String, Function>,
+    ans: Option<f64>, // the last result used
+    context: MathContext,
+}
+
+impl Calculator {
+    pub fn new() -> Self {
+        Self {
+            variables: BTreeMap::new(),
+            functions: BTreeMap::new(),
+            ans: None,
+            context: MathContext::new(),
+        }
+    }
+
+    /// Work out what the input does without changing anything, spaces are ignored.
+    pub fn parse(&self, input: &str) -> Result<Statement, String> {
+        let input: String = input.split_whitespace().collect();
+        let Some((target, expression)) = input.split_once('=') else {
+            return Ok(Statement::Expression(self.evaluate(&input)?));
+        };
+        if expression.is_empty() {
+            return Err(format!("Expected a value after {}=", target));
+        }
+        let Some((name, params)) = target.split_once('(') else {
+            check_name(target)?;
+            return Ok(Statement::Assign(
+                target.to_string(),
+                self.evaluate(expression)?,
+            ));
+        };
+        check_name(name)?;
+        let params = params
+            .strip_suffix(')')
+            .ok_or_else(|| format!("Expected {}(x)=...", name))?;
+        let params: Vec<String> = params
+            .split(',')
+            .filter(|param| !param.is_empty())
+            .map(str::to_string)
+            .collect();
+        for (i, param) in params.iter().enumerate() {
+            check_name(param)?;
+            if params[..i].contains(param) {
+                return Err(format!("{} is a parameter twice", param));
+            }
+        }
+        // the body may only use the parameters and what is defined already
+        let body = ShuntingParser::parse_str(expression)?;
+        let scope = params.iter().map(|param| (param.clone(), 1.0)).collect();
+        self.eval_rpn(&body, &scope, 0)?;
+        Ok(Statement::Define(
+            name.to_string(),
+            params,
+            expression.to_string(),
+        ))
+    }
+
+    pub fn evaluate(&self, expression: &str) -> Result<f64, String> {
+        let rpn = ShuntingParser::parse_str(expression)?;
+        self.eval_rpn(&rpn, &BTreeMap::new(), 0)
+    }
+
+    /// Set the variable or define the function of a statement.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(String, String)>` - The name and the definition to save, i.e. "x=15"
+    pub fn apply(&mut self, statement: &Statement) -> Option<(String, String)> {
+        match statement {
+            Statement::Expression(_) => None,
+            Statement::Assign(name, value) => {
+                self.variables.insert(name.clone(), *value);
+                Some((name.clone(), format!("{}={}", name, value)))
+            }
+            Statement::Define(name, params, body) => {
+                let function = Function {
+                    params: params.clone(),
+                    body: ShuntingParser::parse_str(body).ok()?,
+                };
+                self.functions.insert(name.clone(), function);
+                Some((
+                    name.clone(),
+                    format!("{}({})={}", name, params.join(","), body),
+                ))
+            }
+        }
+    }
+
+    pub fn set_ans(&mut self, value: f64) {
+        self.ans = Some(value);
+    }
+
+    /// Forget the variables, functions and `ans`.
+    pub fn clear(&mut self) {
+        self.variables.clear();
+        self.functions.clear();
+        self.ans = None;
+    }
+
+    /// The defined names for completion, functions with their opening parenthesis.
+    pub fn names(&self) -> Vec<String> {
+        let variables = self.variables.keys().cloned();
+        let functions = self.functions.keys().map(|name| format!("{}(", name));
+        variables.chain(functions).collect()
+    }
+
+    fn eval_rpn(
+        &self,
+        rpn: &RPNExpr,
+        scope: &BTreeMap<String, f64>,
+        depth: usize,
+    ) -> Result<f64, String> {
+        if depth > MAX_DEPTH {
+            return Err("Functions nested too deeply, does one call itself?".to_string());
+        }
+        let mut operands = Vec::new();
+        for token in &rpn.0 {
+            let arity = match token {
+                MathToken::Number(number) => {
+                    operands.push(*number);
+                    continue;
+                }
+                MathToken::Variable(name) => {
+                    operands.push(self.variable(name, scope)?);
+                    continue;
+                }
+                MathToken::Function(_, arity) => *arity,
+                MathToken::BOp(_) => 2,
+                MathToken::UOp(_) => 1,
+                other => return Err(format!("Unexpected {:?}", other)),
+            };
+            if arity > operands.len() {
+                return Err(format!("Missing operands for {:?}", token));
+            }
+            let args = operands.split_off(operands.len() - arity);
+            let value = match token {
+                MathToken::Function(name, _) => self.call(name, &args, depth)?,
+                _ => self.delegate(&args, token)?,
+            };
+            operands.push(value);
+        }
+        match operands.as_slice() {
+            [value] => Ok(*value),
+            [] => Err("Nothing to calculate".to_string()),
+            _ => Err("Missing an operator".to_string()),
+        }
+    }
+
+    fn variable(&self, name: &str, scope: &BTreeMap<String, f64>) -> Result<f64, String> {
+        if let Some(value) = scope.get(name).or_else(|| self.variables.get(name)) {
+            return Ok(*value);
+        }
+        if name == "ans" {
+            return self
+                .ans
+                .ok_or_else(|| "No earlier result for ans".to_string());
+        }
+        CONSTANTS
+            .iter()
+            .find(|(constant, _)| *constant == name)
+            .map(|(_, value)| *value)
+            .ok_or_else(|| format!("Unknown variable {}", name))
+    }
+
+    fn call(&self, name: &str, args: &[f64], depth: usize) -> Result<f64, String> {
+        if let Some(function) = self.functions.get(name) {
+            if args.len() != function.params.len() {
+                return Err(format!(
+                    "{} takes {} arguments, got {}",
+                    name,
+                    function.params.len(),
+                    args.len()
+                ));
+            }
+            let scope = function.params.iter().cloned().zip(args.iter().copied());
+            return self.eval_rpn(&function.body, &scope.collect(), depth + 1);
+        }
+        let function: fn(f64) -> f64 = match name {
+            "sqrt" => f64::sqrt,
+            "tan" => f64::tan,
+            "ln" => f64::ln,
+            "exp" => f64::exp,
+            "floor" => f64::floor,
+            "ceil" => f64::ceil,
+            "round" => f64::round,
+            _ => return self.delegate(args, &MathToken::Function(name.to_string(), args.len())),
+        };
+        match args {
+            [arg] => Ok(function(*arg)),
+            _ => Err(format!("{} takes 1 argument, got {}", name, args.len())),
+        }
+    }
+
+    // an operator or function of the parser, on arguments already worked out
+    fn delegate(&self, args: &[f64], token: &MathToken) -> Result<f64, String> {
+        let mut tokens: Vec<MathToken> = args.iter().map(|arg| MathToken::Number(*arg)).collect();
+        tokens.push(token.clone());
+        self.context.eval(&RPNExpr(tokens))
+    }
+}
+
+// a name that can be defined, not a constant or a built in function
+fn check_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("{:?} is not a valid name", name));
+    }
+    let reserved = name == "ans"
+        || CONSTANTS.iter().any(|(constant, _)| *constant == name)
+        || FUNCTIONS.contains(&name)
+        || PARSER_FUNCTIONS.contains(&name);
+    if reserved {
+        return Err(format!("{} is built in", name));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(calculator: &mut Calculator, input: &str) -> Result<Option<f64>, String> {
+        let statement = calculator.parse(input)?;
+        calculator.apply(&statement);
+        Ok(statement.value())
+    }
+
+    #[test]
+    fn test_variables_and_functions() {
+        let mut calculator = Calculator::new();
+        assert_eq!(run(&mut calculator, "2 * (3 + 4)"), Ok(Some(14.0)));
+        assert_eq!(run(&mut calculator, "x = 5*3"), Ok(Some(15.0)));
+        assert_eq!(run(&mut calculator, "x + 1"), Ok(Some(16.0)));
+        assert_eq!(run(&mut calculator, "f(a, b) = a*b + x"), Ok(None));
+        assert_eq!(run(&mut calculator, "f(2, 3) + sqrt(16)"), Ok(Some(25.0)));
+        assert_eq!(run(&mut calculator, "max(f(1,1), 2)"), Ok(Some(16.0)));
+        assert_eq!(run(&mut calculator, "floor(tau)"), Ok(Some(6.0)));
+
+        assert!(run(&mut calculator, "ans").is_err());
+        calculator.set_ans(7.0);
+        assert_eq!(run(&mut calculator, "ans * 2"), Ok(Some(14.0)));
+
+        // parsing alone doesn't change anything
+        assert_eq!(
+            calculator.parse("y = 2"),
+            Ok(Statement::Assign("y".into(), 2.0))
+        );
+        assert!(calculator.evaluate("y").is_err());
+        assert_eq!(calculator.names(), vec!["x", "f("]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut calculator = Calculator::new();
+        assert!(calculator.parse("pi = 3").is_err());
+        assert!(calculator.parse("sqrt(x) = x").is_err());
+        assert!(calculator.parse("2x = 3").is_err());
+        assert!(calculator.parse("x =").is_err());
+        assert!(calculator.parse("f(x, x) = x").is_err());
+        // unknown in the body
+        assert!(calculator.parse("f(x) = x + z").is_err());
+        assert!(calculator.parse("f(1, 2)").is_err());
+
+        run(&mut calculator, "g(x) = x").unwrap();
+        assert!(calculator.parse("g(1, 2)").is_err());
+        // g calling itself once it's redefined
+        run(&mut calculator, "g(x) = g(x) + 1").unwrap();
+        assert!(calculator.evaluate("g(1)").is_err());
+
+        calculator.clear();
+        assert!(calculator.evaluate("g(1)").is_err());
+    }
+}
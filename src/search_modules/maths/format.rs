@@ -0,0 +1,427 @@
+use crate::settings::settings::{CalculatorSettings, NumberFormat, Rounding};
+
+// significant digits an f64 holds reliably, the rest is noise like 0.1+0.2 = 0.30000000000000004
+const SIGNIFICANT_DIGITS: usize = 15;
+
+// the largest denominator tried for a fraction, and how close it has to be
+const MAX_DENOMINATOR: i64 = 10_000;
+const FRACTION_TOLERANCE: f64 = 1e-9;
+
+// whole numbers shown in other bases, all of them exact in an f64
+const MAX_WHOLE: f64 = 9_007_199_254_740_992.0; // 2^53
+
+/// The decimal and digit group separators of a locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Separators {
+    pub decimal: char,
+    pub group: char,
+}
+
+impl Default for Separators {
+    fn default() -> Self {
+        Self {
+            decimal: '.',
+            group: ',',
+        }
+    }
+}
+
+impl Separators {
+    /// The separators of a locale name like "de_DE.UTF-8", the English ones if it isn't known.
+    pub fn for_locale(locale: &str) -> Self {
+        let locale = locale.split(['.', '@']).next().unwrap_or_default();
+        let (language, region) = locale.split_once(['_', '-']).unwrap_or((locale, ""));
+        let (decimal, group) = match (language, region) {
+            ("de" | "it" | "fr", "CH") => ('.', '\''),
+            ("pt", "BR") => (',', '.'),
+            (
+                "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" | "ro" | "hr" | "sl"
+                | "sr",
+                _,
+            ) => (',', '.'),
+            (
+                "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "no" | "uk" | "hu"
+                | "bg" | "et" | "lv" | "lt",
+                _,
+            ) => (',', '\u{a0}'),
+            _ => return Self::default(),
+        };
+        Self { decimal, group }
+    }
+
+    /// The separators of LC_ALL, LC_NUMERIC or LANG, the first one set.
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|locale| !locale.is_empty())
+            .map(|locale| Self::for_locale(&locale))
+            .unwrap_or_default()
+    }
+}
+
+/// Formats calculator results with the precision, rounding and locale of the settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberFormatter {
+    precision: usize,
+    rounding: Rounding,
+    grouping: bool,
+    separators: Separators,
+    formats: Vec<NumberFormat>,
+}
+
+impl NumberFormatter {
+    pub fn new(settings: &CalculatorSettings) -> Self {
+        let separators = match settings.locale.as_str() {
+            "" => Separators::from_env(),
+            locale => Separators::for_locale(locale),
+        };
+        Self {
+            precision: settings.precision,
+            rounding: settings.rounding,
+            grouping: settings.thousands_separator,
+            separators,
+            formats: settings.formats.clone(),
+        }
+    }
+
+    /// The result as it's shown, i.e. "1,234.5", in scientific notation when rounding would
+    /// lose it.
+    pub fn format(&self, value: f64) -> String {
+        self.decimal(value, self.grouping)
+    }
+
+    /// The result without digit groups, for the clipboard, i.e. "1234.5".
+    pub fn plain(&self, value: f64) -> String {
+        self.decimal(value, false)
+    }
+
+    /// The other formats of the settings that apply to a value, i.e. hex for a whole number.
+    pub fn alternates(&self, value: f64) -> Vec<String> {
+        if !value.is_finite() {
+            return vec![];
+        }
+        let whole = whole(value);
+        self.formats
+            .iter()
+            .filter_map(|format| match format {
+                NumberFormat::Hex => {
+                    whole.map(|n| base(n, "0x", format!("{:x}", n.unsigned_abs())))
+                }
+                NumberFormat::Octal => {
+                    whole.map(|n| base(n, "0o", format!("{:o}", n.unsigned_abs())))
+                }
+                NumberFormat::Binary => {
+                    whole.map(|n| base(n, "0b", format!("{:b}", n.unsigned_abs())))
+                }
+                // only where the decimal result doesn't show it well already
+                NumberFormat::Scientific => {
+                    let magnitude = value.abs();
+                    let shown = (1e-3..1e6).contains(&magnitude) || magnitude == 0.0;
+                    (!shown && !self.needs_scientific(value)).then(|| self.scientific(value))
+                }
+                NumberFormat::Fraction => match whole {
+                    Some(_) => None,
+                    None => fraction(value)
+                        .map(|(numerator, denominator)| format!("{}/{}", numerator, denominator)),
+                },
+            })
+            .collect()
+    }
+
+    fn decimal(&self, value: f64, grouping: bool) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        if self.needs_scientific(value) {
+            return self.scientific(value);
+        }
+        let (digits, point) = significant(value);
+        let (digits, point) = round(digits, point, self.precision, self.rounding);
+        if digits.is_empty() {
+            return "0".to_string();
+        }
+        // zeros between the point and the first digit, i.e. 0.05
+        let digits: Vec<u8> = std::iter::repeat_n(0, (-point).max(0) as usize)
+            .chain(digits)
+            .collect();
+        let point = point.max(0) as usize;
+        let (integer, fraction) = digits.split_at(point.min(digits.len()));
+        let mut integer: String = integer
+            .iter()
+            .map(|digit| char::from(b'0' + digit))
+            .collect();
+        integer.extend(std::iter::repeat_n('0', point.saturating_sub(digits.len())));
+        if integer.is_empty() {
+            integer.push('0');
+        }
+        let mut shown = String::new();
+        if value < 0.0 {
+            shown.push('-');
+        }
+        shown.push_str(&match grouping {
+            true => group(&integer, self.separators.group),
+            false => integer,
+        });
+        if !fraction.is_empty() {
+            shown.push(self.separators.decimal);
+            shown.extend(fraction.iter().map(|digit| char::from(b'0' + digit)));
+        }
+        shown
+    }
+
+    // too large to show every digit, or too small to show at this precision
+    fn needs_scientific(&self, value: f64) -> bool {
+        let (digits, point) = significant(value);
+        if digits.is_empty() {
+            return false;
+        }
+        let (rounded, _) = round(digits, point, self.precision, self.rounding);
+        point > SIGNIFICANT_DIGITS as i32 || rounded.is_empty()
+    }
+
+    // i.e. 1.5e-7, with the mantissa rounded to the precision
+    fn scientific(&self, value: f64) -> String {
+        let (digits, point) = significant(value);
+        if digits.is_empty() {
+            return "0".to_string();
+        }
+        let (mut digits, mantissa_point) = round(digits, 1, self.precision, self.rounding);
+        // rounding up a mantissa of 9.99 makes it 10
+        let exponent = point - 1 + (mantissa_point - 1);
+        let mut shown = String::new();
+        if value < 0.0 {
+            shown.push('-');
+        }
+        shown.push(char::from(b'0' + digits.remove(0)));
+        if !digits.is_empty() {
+            shown.push(self.separators.decimal);
+            shown.extend(digits.iter().map(|digit| char::from(b'0' + digit)));
+        }
+        format!("{}e{}", shown, exponent)
+    }
+}
+
+// the significant digits of a value and where the decimal point goes, without trailing zeros
+// i.e. 0.30000000000000004 is ([3], 0) and 1250 is ([1, 2, 5], 4)
+fn significant(value: f64) -> (Vec<u8>, i32) {
+    if value == 0.0 {
+        return (vec![], 0);
+    }
+    let shown = format!("{:.*e}", SIGNIFICANT_DIGITS - 1, value.abs());
+    let (mantissa, exponent) = shown.split_once('e').unwrap_or((&shown, "0"));
+    let mut digits: Vec<u8> = mantissa
+        .bytes()
+        .filter(u8::is_ascii_digit)
+        .map(|digit| digit - b'0')
+        .collect();
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    (digits, exponent.parse::<i32>().unwrap_or(0) + 1)
+}
+
+// round digits to a number of places after the point, trailing zeros are dropped
+// the sign is shown separately, so down is towards zero and up away from it either way
+fn round(mut digits: Vec<u8>, mut point: i32, places: usize, rounding: Rounding) -> (Vec<u8>, i32) {
+    let places = places.min(i32::MAX as usize / 2) as i32;
+    // zeros in front so the digits kept start at the point at the latest
+    while point + places < 0 {
+        digits.insert(0, 0);
+        point += 1;
+    }
+    let keep = (point + places) as usize;
+    if keep < digits.len() {
+        let dropped = digits.split_off(keep);
+        let (first, rest) = dropped.split_first().unwrap_or((&0, &[]));
+        let rest_zero = rest.iter().all(|digit| *digit == 0);
+        let round_up = match rounding {
+            Rounding::HalfUp => *first >= 5,
+            Rounding::HalfEven => {
+                let odd = digits.last().is_some_and(|digit| digit % 2 == 1);
+                *first > 5 || (*first == 5 && (!rest_zero || odd))
+            }
+            Rounding::Down => false,
+            Rounding::Up => *first != 0 || !rest_zero,
+        };
+        if round_up {
+            let mut carry = true;
+            for digit in digits.iter_mut().rev() {
+                *digit += 1;
+                carry = *digit == 10;
+                if !carry {
+                    break;
+                }
+                *digit = 0;
+            }
+            if carry {
+                digits.insert(0, 1);
+                point += 1;
+            }
+        }
+    }
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    while digits.first() == Some(&0) {
+        digits.remove(0);
+        point -= 1;
+    }
+    if digits.iter().all(|digit| *digit == 0) {
+        return (vec![], 0);
+    }
+    (digits, point)
+}
+
+// a whole number, ignoring noise past the significant digits, i.e. 0.1*30
+fn whole(value: f64) -> Option<i64> {
+    let (digits, point) = significant(value);
+    let whole = digits.len() as i32 <= point && value.abs() < MAX_WHOLE;
+    whole.then(|| value.round() as i64)
+}
+
+fn base(value: i64, prefix: &str, digits: String) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    format!("{}{}{}", sign, prefix, digits)
+}
+
+// the closest fraction with a small denominator, from the continued fraction of the value
+fn fraction(value: f64) -> Option<(i64, i64)> {
+    let (mut numerator, mut denominator) = (1i64, 0i64);
+    let (mut previous_numerator, mut previous_denominator) = (0i64, 1i64);
+    let mut remainder = value.abs();
+    loop {
+        let term = remainder.floor();
+        if term > i64::MAX as f64 / 2.0 {
+            return None;
+        }
+        let term = term as i64;
+        let next_numerator = term
+            .checked_mul(numerator)?
+            .checked_add(previous_numerator)?;
+        let next_denominator = term
+            .checked_mul(denominator)?
+            .checked_add(previous_denominator)?;
+        if next_denominator > MAX_DENOMINATOR {
+            return None;
+        }
+        (previous_numerator, previous_denominator) = (numerator, denominator);
+        (numerator, denominator) = (next_numerator, next_denominator);
+        let error = (value.abs() - numerator as f64 / denominator as f64).abs();
+        if error <= FRACTION_TOLERANCE {
+            // a whole number, or as close to one as the value can tell
+            let sign = if value < 0.0 { -1 } else { 1 };
+            return (denominator > 1).then_some((sign * numerator, denominator));
+        }
+        remainder = 1.0 / (remainder - term as f64);
+        if !remainder.is_finite() {
+            return None;
+        }
+    }
+}
+
+// separators between groups of three digits, from the right
+fn group(integer: &str, separator: char) -> String {
+    let mut grouped = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatter(settings: CalculatorSettings) -> NumberFormatter {
+        NumberFormatter::new(&CalculatorSettings {
+            locale: "en_GB".into(),
+            ..settings
+        })
+    }
+
+    #[test]
+    fn test_format() {
+        let default = formatter(CalculatorSettings::default());
+        assert_eq!(default.format(0.1 + 0.2), "0.3");
+        assert_eq!(default.format(2.0 / 3.0), "0.6666666667");
+        assert_eq!(default.format(-1234567.5), "-1,234,567.5");
+        assert_eq!(default.plain(-1234567.5), "-1234567.5");
+        assert_eq!(default.format(100.0), "100");
+        assert_eq!(default.format(0.0), "0");
+        assert_eq!(default.format(1e20), "1e20");
+        assert_eq!(default.format(-1.5e-12), "-1.5e-12");
+        assert_eq!(default.format(f64::INFINITY), "inf");
+
+        let german = NumberFormatter::new(&CalculatorSettings {
+            locale: "de_DE.UTF-8".into(),
+            ..CalculatorSettings::default()
+        });
+        assert_eq!(german.format(1234.25), "1.234,25");
+
+        let two_places = |rounding| {
+            formatter(CalculatorSettings {
+                precision: 2,
+                rounding,
+                thousands_separator: false,
+                ..CalculatorSettings::default()
+            })
+        };
+        let rounded = |rounding, value| two_places(rounding).format(value);
+        assert_eq!(rounded(Rounding::HalfUp, 2.345), "2.35");
+        assert_eq!(rounded(Rounding::HalfUp, -2.345), "-2.35");
+        assert_eq!(rounded(Rounding::HalfEven, 2.345), "2.34");
+        assert_eq!(rounded(Rounding::HalfEven, 2.355), "2.36");
+        assert_eq!(rounded(Rounding::Down, 2.349), "2.34");
+        assert_eq!(rounded(Rounding::Up, 2.341), "2.35");
+        assert_eq!(rounded(Rounding::Up, 9.999), "10");
+        // noise doesn't round up
+        assert_eq!(rounded(Rounding::Up, 0.1 + 0.2), "0.3");
+        assert_eq!(rounded(Rounding::HalfUp, 0.004), "4e-3");
+    }
+
+    #[test]
+    fn test_alternates() {
+        let every = formatter(CalculatorSettings {
+            formats: vec![
+                NumberFormat::Hex,
+                NumberFormat::Octal,
+                NumberFormat::Binary,
+                NumberFormat::Scientific,
+                NumberFormat::Fraction,
+            ],
+            ..CalculatorSettings::default()
+        });
+        assert_eq!(every.alternates(255.0), ["0xff", "0o377", "0b11111111"]);
+        assert_eq!(every.alternates(-10.0), ["-0xa", "-0o12", "-0b1010"]);
+        assert_eq!(every.alternates(0.75), ["3/4"]);
+        assert_eq!(every.alternates(-1.0 / 3.0), ["-1/3"]);
+        assert_eq!(every.alternates(std::f64::consts::PI), Vec::<String>::new());
+        assert_eq!(
+            every.alternates(1234567.0),
+            [
+                "0x12d687",
+                "0o4553207",
+                "0b100101101011010000111",
+                "1.234567e6"
+            ]
+        );
+        assert_eq!(every.alternates(0.0001), ["1e-4", "1/10000"]);
+        assert_eq!(
+            every.alternates(2f64.powi(40) + 0.5),
+            ["1.0995116278e12", "2199023255553/2"]
+        );
+    }
+
+    #[test]
+    fn test_separators() {
+        assert_eq!(Separators::for_locale("en_US.UTF-8"), Separators::default());
+        assert_eq!(Separators::for_locale("C"), Separators::default());
+        assert_eq!(Separators::for_locale("fr_FR").decimal, ',');
+        assert_eq!(Separators::for_locale("de_CH").group, '\'');
+        assert_eq!(Separators::for_locale("pt-BR").group, '.');
+    }
+}
@@ -1,79 +1,108 @@
-use std::{collections::VecDeque, rc::Rc, vec};
+use std::{collections::VecDeque, rc::Rc, sync::Arc, vec};
 
-use nucleo::{Config, Matcher};
-use shunting::ShuntingParser;
+use futures::executor;
+use tokio::sync::Mutex;
 
 use crate::{
-    search_modules::{ListResult, SearchModule},
-    settings::settings::Settings,
+    action::{Action, Notice},
+    common::clipboard::copy,
+    database::Database,
+    search_modules::{
+        ListResult, SearchModule, Trigger, complete_from,
+        maths::{
+            calculator::{Calculator, Statement},
+            format::NumberFormatter,
+        },
+    },
+    settings::settings::{CalculatorSettings, Settings},
 };
 use color_eyre::Result;
 
+// functions and constants known to the calculator, for completion
+const COMPLETIONS: &[&str] = &[
+    "abs(", "atan2(", "ans", "ceil(", "cos(", "exp(", "floor(", "ln(", "log(", "max(", "min(",
+    "nCr(", "nPr(", "rand(", "round(", "sin(", "sqrt(", "tan(", "pi", "phi", "tau",
+];
+
+// calculations kept, in memory and in the database
+const HISTORY_SIZE: usize = 100;
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Equation {
     pub expression: String,
-    pub result: String,
+    pub result: String, // the value unrounded, empty for a function definition
 }
 impl Equation {
-    pub fn launch(&self) -> bool {
-        log::info!("Launching equation result: {}", self.result);
-        // no launch action for equations
-        true
+    // how it's shown in the results with what Enter copies, the decimal result first then the
+    // other formats, i.e. "255 = 0xff"
+    fn rows(&self, formatter: &NumberFormatter) -> Vec<(String, String)> {
+        let Ok(value) = self.result.parse::<f64>() else {
+            return vec![(self.expression.clone(), self.expression.clone())];
+        };
+        let alternates = formatter.alternates(value).into_iter();
+        std::iter::once((formatter.format(value), formatter.plain(value)))
+            .chain(alternates.map(|shown| (shown.clone(), shown)))
+            .map(|(shown, copied)| (format!("{} = {}", self.expression, shown), copied))
+            .collect()
     }
 }
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 
 pub struct MathsData {
-    pub equations: VecDeque<Equation>,
+    pub equations: VecDeque<Equation>, // used calculations, most recent first
 }
 
 pub struct MathsModule {
     data: Box<MathsData>,
-    context: shunting::MathContext,
+    calculator: Calculator,
+    formatter: NumberFormatter,
+    current: Option<(Equation, Statement)>, // the query being typed, remembered once launched
     time_since_last_eval: std::time::Instant,
+    action_tx: Option<tokio::sync::mpsc::UnboundedSender<Action>>,
+    database: Option<Arc<Mutex<Database>>>,
 }
 
 impl MathsModule {
     pub fn new() -> Self {
         Self {
             data: Box::new(MathsData::default()),
-            context: shunting::MathContext::new(),
+            calculator: Calculator::new(),
+            formatter: NumberFormatter::new(&CalculatorSettings::default()),
+            current: None,
             time_since_last_eval: std::time::Instant::now(),
+            action_tx: None,
+            database: None,
         }
     }
 
-    pub fn test_for_duplicate(&mut self, equation: &mut Equation) -> Result<(bool, usize)> {
-        let mut matcher = Matcher::new(Config::DEFAULT);
-        if self.data.equations.is_empty() {
-            return Ok((false, 0));
-        }
-
-        let default = Equation::default();
-
-        let expression = equation.expression.to_string();
-
-        for i in 0..self.data.equations.len() {
-            let compare_equation = self.data.equations.get(i).unwrap_or(&default);
-
-            let compare_expression = compare_equation.expression.to_string();
+    fn get_database(&self) -> Option<tokio::sync::MutexGuard<'_, Database>> {
+        let database = self.database.as_ref()?;
+        Some(executor::block_on(database.lock()))
+    }
 
-            // exact match then return duplicate found
-            if expression == compare_expression {
-                return Ok((true, i));
-            }
-            let substring_match = matcher
-                .substring_match(
-                    nucleo::Utf32Str::new(&expression, &mut vec![]),
-                    nucleo::Utf32Str::new(&compare_expression, &mut vec![]),
-                )
-                .unwrap_or_default();
-
-            if substring_match > 0 {
-                return Ok((true, i));
-            }
+    // move a calculation to the top of the history, its result becomes ans
+    fn remember(&mut self, equation: Equation) -> Result<()> {
+        if let Ok(value) = equation.result.parse::<f64>() {
+            self.calculator.set_ans(value);
         }
+        // only the exact expression is replaced, "1+1" and "1+12" are both kept
+        self.data
+            .equations
+            .retain(|remembered| remembered.expression != equation.expression);
+        self.data.equations.push_front(equation.clone());
+        self.data.equations.truncate(HISTORY_SIZE);
+        if let Some(database) = self.get_database() {
+            database.add_calculation(&equation.expression, &equation.result, HISTORY_SIZE)?;
+        }
+        Ok(())
+    }
 
-        Ok((false, 0))
+    fn notify(&self, message: &str) {
+        if let Some(action_tx) = &self.action_tx {
+            action_tx
+                .send(Action::Notify(Notice::info(message)))
+                .unwrap();
+        }
     }
 }
 
@@ -81,79 +110,316 @@ impl SearchModule for MathsModule {
     fn name(&self) -> &str {
         "maths_module"
     }
+    fn title(&self) -> &str {
+        "Calculator"
+    }
+
+    // the saved variables and functions are defined again, the latest result is ans
+    fn init(&mut self) -> Result<()> {
+        let Some(database) = self.get_database() else {
+            return Ok(());
+        };
+        let definitions = database.definitions()?;
+        let calculations = database.calculations()?;
+        drop(database);
+        for definition in definitions {
+            match self.calculator.parse(&definition) {
+                Ok(statement) => {
+                    self.calculator.apply(&statement);
+                }
+                Err(err) => log::warn!("Could not define {}: {}", definition, err),
+            }
+        }
+        self.data.equations = calculations
+            .into_iter()
+            .map(|(expression, result)| Equation { expression, result })
+            .collect();
+        if let Some(value) = self
+            .data
+            .equations
+            .iter()
+            .find_map(|equation| equation.result.parse().ok())
+        {
+            self.calculator.set_ans(value);
+        }
+        Ok(())
+    }
+
+    fn register_settings_handler(&mut self, settings: Settings) -> Result<()> {
+        self.formatter = NumberFormatter::new(&settings.calculator);
+        Ok(())
+    }
+
+    fn register_database_handler(&mut self, database: Arc<Mutex<Database>>) -> Result<()> {
+        self.database = Some(database);
+        Ok(())
+    }
 
     fn search(&mut self, query: &str) -> Result<bool> {
-        if query.is_empty() {
+        let expression: String = query.split_whitespace().collect();
+        if expression.is_empty() {
             return Err(color_eyre::eyre::eyre!("Empty query"));
         }
-
-        let formatted_query = query.trim().replace(" ", "");
-        let equation = &mut Equation {
-            expression: formatted_query.clone(),
-            result: "✕".to_string(),
+        // block to prevent expressions that are just numbers
+        if expression.parse::<f64>().is_ok() {
+            return Err(color_eyre::eyre::eyre!("Expression is just a number"));
+        }
+        let statement = match self.calculator.parse(&expression) {
+            Ok(statement) => statement,
+            Err(err) => {
+                self.current = None;
+                return Err(color_eyre::eyre::eyre!(err));
+            }
+        };
+        log::info!("Evaluated expression: {} = {:?}", query, statement.value());
+        let equation = Equation {
+            expression,
+            result: statement
+                .value()
+                .map(|value| value.to_string())
+                .unwrap_or_default(),
         };
+        self.current = Some((equation, statement));
 
-        let expr = ShuntingParser::parse_str(formatted_query.as_str());
-
-        let result = if expr.is_ok() {
-            match self.context.eval(&expr.unwrap()) {
-                Ok(value) => {
-                    // block to prevent expressions that are just numbers
-                    log::info!("Evaluated expression: {} = {}", query, value);
-                    if query.parse::<f64>().is_ok() {
-                        return Err(color_eyre::eyre::eyre!("Expression is just a number"));
-                    }
-                    equation.result = value.to_string();
-                    equation
-                }
-                Err(_) => {
-                    return Err(color_eyre::eyre::eyre!(
-                        "Expression cannot be just a number"
-                    ));
+        log::info!("MathsModule is candidate for query {}", query);
+
+        self.time_since_last_eval = std::time::Instant::now();
+
+        Ok(true)
+    }
+
+    // a launched calculation is remembered, and its variable or function defined
+    fn execute(&mut self, result: &ListResult) {
+        let formatter = &self.formatter;
+        let current = self.current.take_if(|(equation, _)| {
+            let rows = equation.rows(formatter);
+            rows.iter().any(|(shown, _)| *shown == result.result)
+        });
+        let equation = match current {
+            Some((equation, statement)) => {
+                if let Some((name, definition)) = self.calculator.apply(&statement)
+                    && let Some(database) = self.get_database()
+                    && let Err(err) = database.set_definition(&name, &definition)
+                {
+                    log::error!("Could not save {}: {:?}", definition, err);
                 }
+                equation
             }
-        } else {
-            return Err(color_eyre::eyre::eyre!(
-                "Expression cannot be just a number"
-            ));
+            None => match self
+                .data
+                .equations
+                .iter()
+                .find(|equation| equation.rows(formatter)[0].0 == result.result)
+            {
+                Some(equation) => equation.clone(),
+                None => return,
+            },
         };
-
-        let is_duplicate = self.test_for_duplicate(result)?;
-        if is_duplicate.0 {
-            log::trace!(
-                "Duplicate equation found: {} = {}",
-                result.expression,
-                result.result
-            );
-            return Ok(false);
-        } else {
-            self.data.equations.push_front(result.clone());
+        if let Err(err) = self.remember(equation) {
+            log::error!("Could not save the calculation: {:?}", err);
         }
+    }
 
-        if self.data.equations.len() > 100 {
-            let _ = self.data.equations.split_off(99);
-        }
+    fn register_action_handler(
+        &mut self,
+        handler: tokio::sync::mpsc::UnboundedSender<Action>,
+    ) -> Result<()> {
+        self.action_tx = Some(handler);
+        Ok(())
+    }
 
-        log::info!("MathsModule is candidate for query {}", query);
+    fn prefix(&self) -> Option<&str> {
+        Some("=")
+    }
 
-        self.time_since_last_eval = std::time::Instant::now();
+    // launching copies the result, the clipboard only holds one
+    fn batch_execute(&self) -> bool {
+        false
+    }
 
-        Ok(true)
+    fn secondary_action(&self) -> Option<&str> {
+        Some("Copy the expression")
     }
 
+    fn triggers(&self) -> Vec<Trigger> {
+        vec![
+            Trigger::new(
+                "2*(3+4)",
+                "Calculate, launching copies the result and keeps it below",
+            ),
+            Trigger::new(
+                "2^40",
+                "Also in other formats, see calculator.formats in the settings",
+            ),
+            Trigger::new("sqrt(ans)", "Functions, constants and the last result"),
+            Trigger::new(
+                "x=5*3",
+                "Set a variable once launched, kept between sessions",
+            ),
+            Trigger::new("f(x)=x^2+1", "Define a function once launched"),
+        ]
+    }
+
+    // completes the function or constant being typed, i.e. "2*co" to "2*cos("
+    fn complete(&self, query: &str) -> Option<String> {
+        let start = query
+            .rfind(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .map_or(0, |i| i + 1);
+        let (expression, name) = query.split_at(start);
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        // the user's own names first
+        let names = self.calculator.names();
+        let candidates = names
+            .iter()
+            .map(String::as_str)
+            .chain(COMPLETIONS.iter().copied());
+        let completion = complete_from(name, candidates)?;
+        Some(format!("{}{}", expression, completion))
+    }
+
+    fn command(&mut self, command: &str) -> Result<bool> {
+        match command {
+            "clear_history" => {
+                self.data.equations.clear();
+                if let Some(database) = self.get_database() {
+                    database.clear_calculations()?;
+                }
+                self.notify("Cleared the calculator history");
+                Ok(true)
+            }
+            "clear_variables" => {
+                self.calculator.clear();
+                if let Some(database) = self.get_database() {
+                    database.clear_definitions()?;
+                }
+                self.notify("Forgot the calculator variables and functions");
+                Ok(false)
+            }
+            _ => Err(color_eyre::eyre::eyre!(
+                "{} has no command {:?}",
+                self.name(),
+                command
+            )),
+        }
+    }
+
+    // the query being typed in each format, then the history without it
     fn get_ui_results(&self) -> Vec<ListResult> {
-        self.data
+        let current = self.current.as_ref().map(|(equation, _)| equation);
+        let current_rows = current.into_iter().flat_map(|equation| {
+            let rows = equation.rows(&self.formatter).into_iter();
+            rows.map(move |row| (equation, row))
+        });
+        let history_rows = self
+            .data
             .equations
             .iter()
+            .filter(|equation| Some(&equation.expression) != current.map(|c| &c.expression))
+            .map(|equation| (equation, equation.rows(&self.formatter).remove(0)));
+        current_rows
+            .chain(history_rows)
             .enumerate()
-            .map(|(idx, eq)| {
+            .map(|(idx, (equation, (shown, copied)))| {
+                let expression = equation.expression.clone();
                 ListResult {
-                    result: format!("{} = {}", eq.expression, eq.result),
+                    result: shown,
                     score: idx as u16,
-                    // source_module: self.name().to_string(),
-                    launch: Rc::new(|| false),
+                    group: String::new(), // set by the app
+                    launch: Rc::new(move || copy(&copied)),
+                    secondary: Some(Rc::new(move || copy(&expression))),
+                    action: None,
+                    detail: None,
+                    markable: false,
                 }
             })
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::settings::NumberFormat;
+
+    #[test]
+    fn test_complete() {
+        let module = MathsModule::new();
+        assert_eq!(module.complete("2*co"), Some("2*cos(".into()));
+        assert_eq!(module.complete("p"), Some("pi".into()));
+        assert_eq!(module.complete("max(1,m"), Some("max(1,max(".into()));
+        assert_eq!(module.complete("2*3"), None);
+        assert_eq!(module.complete("pi"), None);
+        assert_eq!(module.complete(""), None);
+    }
+
+    #[test]
+    fn test_formats() {
+        let mut module = MathsModule::new();
+        let mut settings = Settings::default();
+        settings.calculator.locale = "en_US.UTF-8".into();
+        settings.calculator.formats = vec![NumberFormat::Hex, NumberFormat::Fraction];
+        module.register_settings_handler(settings).unwrap();
+
+        module.search("1000*1000").unwrap();
+        let results: Vec<String> = module
+            .get_ui_results()
+            .into_iter()
+            .map(|r| r.result)
+            .collect();
+        assert_eq!(results, ["1000*1000 = 1,000,000", "1000*1000 = 0xf4240"]);
+
+        module.search("0.1+0.2").unwrap();
+        let results = module.get_ui_results();
+        assert_eq!(results[0].result, "0.1+0.2 = 0.3");
+        assert!(results[0].secondary.is_some());
+        // launching another format remembers the calculation too
+        assert_eq!(results[1].result, "0.1+0.2 = 3/10");
+        module.execute(&results[1]);
+        assert_eq!(module.data.equations[0].expression, "0.1+0.2");
+    }
+
+    fn launch_top(module: &mut MathsModule) {
+        let top = module.get_ui_results().remove(0);
+        module.execute(&top);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut module = MathsModule::new();
+        let database = Arc::new(Mutex::new(Database::new(":memory:").unwrap()));
+        executor::block_on(database.lock()).initialise().unwrap();
+        module.register_database_handler(database.clone()).unwrap();
+
+        // typing doesn't add to the history, launching does
+        for query in ["1+1", "1+12", "x = 5*3"] {
+            module.search(query).unwrap();
+            launch_top(&mut module);
+        }
+        module.search("1+1").unwrap();
+        let results: Vec<String> = module
+            .get_ui_results()
+            .into_iter()
+            .map(|r| r.result)
+            .collect();
+        assert_eq!(results, ["1+1 = 2", "x=5*3 = 15", "1+12 = 13"]);
+
+        module.search("x + ans").unwrap();
+        assert_eq!(module.get_ui_results()[0].result, "x+ans = 30");
+        module.search("f(a) = a*2").unwrap();
+        launch_top(&mut module);
+        assert_eq!(module.complete("2*f"), Some("2*f(".into()));
+
+        // another session with the same database
+        let mut module = MathsModule::new();
+        module.register_database_handler(database).unwrap();
+        module.init().unwrap();
+        assert_eq!(module.data.equations.len(), 4);
+        module.search("f(x) + ans").unwrap();
+        assert_eq!(module.get_ui_results()[0].result, "f(x)+ans = 45");
+
+        module.command("clear_variables").unwrap();
+        assert!(module.search("x").is_err());
+    }
+}
@@ -1 +1,3 @@
+pub mod calculator;
+pub mod format;
 pub mod maths_module;
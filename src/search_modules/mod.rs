@@ -1,9 +1,12 @@
 pub mod applications;
+pub mod commands;
 pub mod maths;
 
 use std::{rc::Rc, sync::Arc};
 
-use crate::{database::Database, settings::settings::Settings};
+use crate::{
+    action::Action, common::launch::LaunchResult, database::Database, settings::settings::Settings,
+};
 use color_eyre::Result;
 use serde::{Deserialize, Serialize, ser::SerializeStruct};
 use tokio::sync::Mutex;
@@ -12,6 +15,10 @@ pub trait SearchModule {
     fn name(&self) -> &str {
         "Unnamed Module"
     }
+    // shown above the module's results when they are grouped, and in the search box chip
+    fn title(&self) -> &str {
+        self.name()
+    }
     // Initialize the module if necessary.
     // used once all action and settings handlers have been registered.
     fn init(&mut self) -> Result<()> {
@@ -57,18 +64,123 @@ pub trait SearchModule {
     /// * `bool` - True if this module has results for the query, false otherwise
     fn search(&mut self, query: &str) -> Result<bool>;
 
+    /// The default prefix that scopes the query to this module, i.e. "=" for maths.
+    ///
+    /// Can be changed with `search.prefixes` in settings. The prefix is removed before the
+    /// query is passed to [`SearchModule::search`].
+    fn prefix(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether the module only searches when the query starts with its prefix, i.e. commands.
+    fn scoped_only(&self) -> bool {
+        false
+    }
+
+    /// Complete the query, shown as ghost text after the caret in the search box.
+    ///
+    /// By default the name of the top result, when it starts with the query.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - The whole completed query, or None when there's no completion
+    fn complete(&self, query: &str) -> Option<String> {
+        let name = self.get_ui_results().into_iter().next()?.result;
+        complete_from(query, [name.as_str()])
+    }
+
+    /// Example queries the module responds to, listed in the help overlay.
+    fn triggers(&self) -> Vec<Trigger> {
+        vec![]
+    }
+    /// What the alternative action of the module's results does, if they have one.
+    ///
+    /// Listed in the help overlay, see [`ListResult::secondary`].
+    fn secondary_action(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether the module's results can be marked and launched together, see `toggle_mark`.
+    ///
+    /// Off for results that don't make sense more than once, i.e. commands.
+    fn batch_execute(&self) -> bool {
+        true
+    }
+
     fn execute(&mut self, result: &ListResult) -> () {
         let _ = result;
     }
+    /// Run a module specific command, bound with `module:<name>:<command>` in keybindings.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the command changed the results, so they are shown again
+    fn command(&mut self, command: &str) -> Result<bool> {
+        Err(color_eyre::eyre::eyre!(
+            "{} has no command {:?}",
+            self.name(),
+            command
+        ))
+    }
     // fn get_results(&self)
     fn get_ui_results(&self) -> Vec<ListResult> {
         vec![]
     }
+    /// The number of items the module searches through, i.e. all applications.
+    ///
+    /// Shown as the total in the results count, by default the number of results.
+    fn total(&self) -> usize {
+        self.get_ui_results().len()
+    }
     fn get_applications(&self) -> Vec<Rc<crate::common::application::Application>> {
         vec![]
     }
 }
 
+/// The first candidate that starts with the query, ignoring case, completing it.
+///
+/// Keeps what was typed, so "fir" and "Firefox" complete to "firefox".
+pub fn complete_from<'a>(
+    query: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<String> {
+    if query.is_empty() {
+        return None;
+    }
+    candidates.into_iter().find_map(|candidate| {
+        let rest = candidate
+            .get(query.len()..)
+            .filter(|rest| !rest.is_empty())?;
+        candidate[..query.len()]
+            .eq_ignore_ascii_case(query)
+            .then(|| format!("{}{}", query, rest))
+    })
+}
+
+/// An example query and what the module does with it, i.e. "2*(3+4)" to calculate.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Trigger {
+    pub example: String,
+    pub description: String,
+}
+impl Trigger {
+    pub fn new(example: &str, description: &str) -> Self {
+        Self {
+            example: example.to_string(),
+            description: description.to_string(),
+        }
+    }
+}
+
+/// What a module responds to, gathered by the app for the help overlay.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct ModuleHelp {
+    pub title: String,
+    pub prefix: Option<String>, // scopes the query to the module, see `search.prefixes`
+    pub triggers: Vec<Trigger>,
+    pub secondary_action: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ScoredResult {
     pub index: usize,
@@ -82,8 +194,12 @@ fn clone_box<F: Fn() + Send + Sync + 'static>(f: F) -> Box<dyn Fn() + Send + Syn
 pub struct ListResult {
     pub result: String,
     pub score: u16,
-    pub launch: Rc<dyn Fn() -> bool + Send + Sync>,
-    // pub launch: Rc<dyn Fn() -> bool + Send + Sync>,
+    pub group: String, // title of the module it came from, set by the app
+    pub launch: Rc<dyn Fn() -> LaunchResult + Send + Sync>,
+    pub secondary: Option<Rc<dyn Fn() -> LaunchResult + Send + Sync>>, // alternative action, bound to execute:secondary
+    pub action: Option<Box<Action>>, // dispatched inside rook instead of launching, i.e. a command
+    pub detail: Option<String>,      // shown right aligned, i.e. the keybinding of a command
+    pub markable: bool, // can be marked for launching with others, set by the app, see `batch_execute`
 }
 
 impl Default for ListResult {
@@ -91,8 +207,12 @@ impl Default for ListResult {
         Self {
             result: String::new(),
             score: 0,
-            launch: Rc::new(|| false),
-            // launch: Rc::new(|| false),
+            group: String::new(),
+            launch: Rc::new(|| Ok(None)),
+            secondary: None,
+            action: None,
+            detail: None,
+            markable: false,
         }
     }
 }
@@ -110,7 +230,12 @@ impl Clone for ListResult {
         Self {
             result: self.result.clone(),
             score: self.score.clone(),
+            group: self.group.clone(),
             launch: self.launch.clone(),
+            secondary: self.secondary.clone(),
+            action: self.action.clone(),
+            detail: self.detail.clone(),
+            markable: self.markable,
         }
     }
 }
@@ -155,7 +280,12 @@ impl<'de> Deserialize<'de> for ListResult {
         Ok(ListResult {
             result: helper.result,
             score: helper.score,
-            launch: Rc::new(|| false),
+            group: String::new(),
+            launch: Rc::new(|| Ok(None)),
+            secondary: None,
+            action: None,
+            detail: None,
+            markable: false,
         })
     }
 }
@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use config::{Config, Environment, File, FileFormat, Map, Source, Value, ValueKind};
+use xdg::BaseDirectories;
+
+use crate::settings::settings::{Settings, get_settings_path};
+
+// settings are merged from several layers, lowest precedence first:
+//
+// 1. built in defaults
+// 2. $XDG_CONFIG_DIRS/rook/settings.toml (i.e. /etc/xdg/rook/settings.toml)
+// 3. ~/.config/rook/settings.toml
+// 4. ~/.config/rook/profiles/<NAME>.toml, when `--profile NAME` is given
+// 5. environment variables, i.e. ROOK__UI__LAYOUT__GAP=2
+// 6. `--set ui.results.max_results=10` flags
+//
+// each layer only needs to contain the keys it changes
+
+const ENV_PREFIX: &str = "ROOK";
+const ENV_SEPARATOR: &str = "__";
+
+/// Overrides supplied on the command line, applied on top of the settings files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SettingsOverrides {
+    pub profile: Option<String>,
+    pub set: Vec<String>, // "key=value" pairs
+}
+
+/// A single source of settings, with a human readable name used when showing where a value came from.
+pub struct SettingsLayer {
+    pub name: String,
+    pub config: Config,
+}
+
+/// A single resolved setting, and the name of the layer that set it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedValue {
+    pub key: String,
+    pub value: String,
+    pub source: String,
+}
+
+pub fn system_settings_files() -> Vec<PathBuf> {
+    // XDG_CONFIG_DIRS is in order of preference, so reverse it to merge the most important last
+    BaseDirectories::with_prefix("rook")
+        .get_config_dirs()
+        .into_iter()
+        .rev()
+        .map(|dir| dir.join("settings.toml"))
+        .collect()
+}
+
+pub fn user_settings_file() -> PathBuf {
+    get_settings_path().join("settings.toml")
+}
+
+pub fn profile_settings_file(profile: &str) -> PathBuf {
+    get_settings_path()
+        .join("profiles")
+        .join(format!("{}.toml", profile))
+}
+
+fn file_layer(path: &PathBuf, required: bool) -> Result<Option<SettingsLayer>> {
+    if !required && !path.exists() {
+        return Ok(None);
+    }
+    let config = Config::builder()
+        .add_source(File::from(path.as_path()).format(FileFormat::Toml))
+        .build()
+        .map_err(|err| eyre!("Could not read settings file {:?}: {}", path, err))?;
+    Ok(Some(SettingsLayer {
+        name: path.to_string_lossy().to_string(),
+        config,
+    }))
+}
+
+fn environment_layer() -> Result<SettingsLayer> {
+    let config = Config::builder()
+        .add_source(
+            Environment::with_prefix(ENV_PREFIX)
+                .prefix_separator(ENV_SEPARATOR)
+                .separator(ENV_SEPARATOR)
+                .try_parsing(true),
+        )
+        .build()?;
+    Ok(SettingsLayer {
+        name: "environment".into(),
+        config,
+    })
+}
+
+fn override_layer(set: &[String]) -> Result<SettingsLayer> {
+    let mut builder = Config::builder();
+    for pair in set {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| eyre!("Invalid --set value {:?}, expected key=value", pair))?;
+        builder = builder.set_override(key.trim(), parse_override_value(value.trim()))?;
+    }
+    Ok(SettingsLayer {
+        name: "--set".into(),
+        config: builder.build()?,
+    })
+}
+
+// keep numbers and booleans typed, so they display the same as values read from a file
+fn parse_override_value(value: &str) -> ValueKind {
+    if let Ok(i) = value.parse::<i64>() {
+        ValueKind::I64(i)
+    } else if let Ok(f) = value.parse::<f64>() {
+        ValueKind::Float(f)
+    } else if let Ok(b) = value.parse::<bool>() {
+        ValueKind::Boolean(b)
+    } else {
+        ValueKind::String(value.to_string())
+    }
+}
+
+/// Collect every settings layer in merge order, lowest precedence first.
+pub fn collect_layers(overrides: &SettingsOverrides) -> Result<Vec<SettingsLayer>> {
+    let mut layers = vec![SettingsLayer {
+        name: "default".into(),
+        config: Config::try_from(&Settings::default())?,
+    }];
+
+    for path in system_settings_files() {
+        layers.extend(file_layer(&path, false)?);
+    }
+    layers.extend(file_layer(&user_settings_file(), false)?);
+
+    if let Some(profile) = &overrides.profile {
+        let path = profile_settings_file(profile);
+        if !path.exists() {
+            return Err(eyre!(
+                "Settings profile {:?} not found at {:?}",
+                profile,
+                path
+            ));
+        }
+        layers.extend(file_layer(&path, true)?);
+    }
+
+    layers.push(environment_layer()?);
+    layers.push(override_layer(&overrides.set)?);
+    Ok(layers)
+}
+
+// the results fade in was replaced by `ui.effects.results`, the old keys are mapped onto it
+// within the same layer, unless that layer sets the new keys itself
+fn migrate_legacy_keys(layer: &SettingsLayer) -> Result<Config> {
+    let config = &layer.config;
+    let mut migrated: Vec<(&str, ValueKind)> = vec![];
+    if let Ok(fade_in) = config.get_bool("ui.results.fade_in") {
+        let kind = if fade_in { "fade_in" } else { "none" };
+        migrated.push(("ui.effects.results.kind", ValueKind::String(kind.into())));
+    }
+    if let Ok(duration) = config.get_int("ui.results.fade_in_duration") {
+        migrated.push(("ui.effects.results.duration", ValueKind::I64(duration)));
+    }
+    if let Ok(top_to_bottom) = config.get_bool("ui.results.fade_top_to_bottom") {
+        let pattern = if top_to_bottom { "down_to_up" } else { "none" };
+        migrated.push((
+            "ui.effects.results.pattern",
+            ValueKind::String(pattern.into()),
+        ));
+    }
+    if migrated.is_empty() {
+        return Ok(config.clone());
+    }
+    log::warn!(
+        "ui.results.fade_in, fade_in_duration and fade_top_to_bottom in {} are deprecated, \
+         use [ui.effects.results] instead",
+        layer.name
+    );
+    let mut builder = Config::builder().add_source(config.clone());
+    for (key, value) in migrated {
+        if config.get::<Value>(key).is_err() {
+            builder = builder.set_override(key, value)?;
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// Merge the layers in order into a single [`Settings`].
+pub fn merge_layers(layers: &[SettingsLayer]) -> Result<Settings> {
+    let mut builder = Config::builder();
+    for layer in layers {
+        builder = builder.add_source(migrate_legacy_keys(layer)?);
+    }
+    let settings: Settings = builder.build()?.try_deserialize()?;
+    Ok(settings)
+}
+
+/// Flatten the layers into dotted keys, recording the last layer to set each key.
+pub fn resolve_layers(layers: &[SettingsLayer]) -> Result<Vec<ResolvedValue>> {
+    let mut resolved: BTreeMap<String, ResolvedValue> = BTreeMap::new();
+    for layer in layers {
+        let mut values = BTreeMap::new();
+        flatten("", &migrate_legacy_keys(layer)?.collect()?, &mut values);
+        for (key, value) in values {
+            // a value replaces anything above or below it, i.e. `ui.theme = "nord"` replaces `ui.theme.accent`
+            let child_prefix = format!("{}.", key);
+            resolved.retain(|existing, _| {
+                !existing.starts_with(&child_prefix) && !key.starts_with(&format!("{}.", existing))
+            });
+            resolved.insert(
+                key.clone(),
+                ResolvedValue {
+                    key,
+                    value,
+                    source: layer.name.clone(),
+                },
+            );
+        }
+    }
+    Ok(resolved.into_values().collect())
+}
+
+fn flatten(prefix: &str, table: &Map<String, Value>, out: &mut BTreeMap<String, String>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match &value.kind {
+            ValueKind::Table(inner) => flatten(&key, inner, out),
+            _ => {
+                out.insert(key, format_value(value));
+            }
+        }
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match &value.kind {
+        ValueKind::String(s) => format!("{:?}", s),
+        ValueKind::Array(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(format_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => value.to_string(),
+    }
+}
+
+/// Render the resolved settings as `key = value  # source` lines, for `rook config show --resolved`.
+pub fn describe_resolved(values: &[ResolvedValue]) -> String {
+    let width = values
+        .iter()
+        .map(|v| v.key.len() + v.value.len() + 3)
+        .max()
+        .unwrap_or(0);
+    values
+        .iter()
+        .map(|v| {
+            let assignment = format!("{} = {}", v.key, v.value);
+            format!("{:<width$}  # {}", assignment, v.source, width = width)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::settings::{EffectKind, EffectPattern};
+
+    fn toml_layer(name: &str, contents: &str) -> SettingsLayer {
+        SettingsLayer {
+            name: name.into(),
+            config: Config::builder()
+                .add_source(File::from_str(contents, FileFormat::Toml))
+                .build()
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_later_layers_override_earlier() {
+        let layers = vec![
+            SettingsLayer {
+                name: "default".into(),
+                config: Config::try_from(&Settings::default()).unwrap(),
+            },
+            toml_layer("system", "[ui.layout]\ngap = 3\npadding = 4\n"),
+            toml_layer("user", "[ui.layout]\ngap = 0\n"),
+            override_layer(&["ui.results.max_results=10".to_string()]).unwrap(),
+        ];
+
+        let settings = merge_layers(&layers).unwrap();
+        assert_eq!(settings.ui.layout.gap, 0);
+        assert_eq!(settings.ui.layout.padding, 4);
+        assert_eq!(settings.ui.results.max_results, 10);
+        // untouched values keep their defaults
+        assert_eq!(
+            settings.ui.layout.title,
+            Settings::default().ui.layout.title
+        );
+
+        let resolved = resolve_layers(&layers).unwrap();
+        let source_of = |key: &str| {
+            resolved
+                .iter()
+                .find(|v| v.key == key)
+                .map(|v| v.source.clone())
+                .unwrap()
+        };
+        assert_eq!(source_of("ui.layout.gap"), "user");
+        assert_eq!(source_of("ui.layout.padding"), "system");
+        assert_eq!(source_of("ui.results.max_results"), "--set");
+        assert_eq!(source_of("ui.layout.title"), "default");
+    }
+
+    #[test]
+    fn test_legacy_fade_in_keys() {
+        let default = || SettingsLayer {
+            name: "default".into(),
+            config: Config::try_from(&Settings::default()).unwrap(),
+        };
+        let layers = vec![
+            default(),
+            toml_layer(
+                "user",
+                "[ui.results]\nfade_in = false\nfade_in_duration = 200\n",
+            ),
+        ];
+        let results = merge_layers(&layers).unwrap().ui.effects.results;
+        assert_eq!(results.kind, EffectKind::None);
+        assert_eq!(results.duration, 200);
+
+        // the new keys win over the old ones in the same layer, and in later layers
+        let layers = vec![
+            default(),
+            toml_layer(
+                "user",
+                "[ui.results]\nfade_in = false\nfade_top_to_bottom = false\n\
+                 [ui.effects.results]\nkind = \"dissolve\"\n",
+            ),
+            override_layer(&["ui.effects.results.pattern=radial".to_string()]).unwrap(),
+        ];
+        let results = merge_layers(&layers).unwrap().ui.effects.results;
+        assert_eq!(results.kind, EffectKind::Dissolve);
+        assert_eq!(results.pattern, EffectPattern::Radial);
+    }
+
+    #[test]
+    fn test_invalid_override() {
+        assert!(override_layer(&["ui.layout.gap".to_string()]).is_err());
+    }
+}
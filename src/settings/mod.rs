@@ -1,2 +1,5 @@
+pub mod layers;
 pub mod serialise;
 pub mod settings;
+pub mod theme_import;
+pub mod themes;
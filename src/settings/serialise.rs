@@ -244,3 +244,47 @@ where
 {
     Ok(Some(Option::deserialize(deserializer)?))
 }
+
+// a size in cells or a percentage, i.e. 25 or "30%"
+pub fn serialize_size<S>(
+    size: &ratatui::layout::Constraint,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match size {
+        ratatui::layout::Constraint::Percentage(p) => serializer.serialize_str(&format!("{}%", p)),
+        ratatui::layout::Constraint::Length(n) => serializer.serialize_str(&n.to_string()),
+        _ => serializer.serialize_str("0"),
+    }
+}
+
+pub fn deserialize_size<'de, D>(deserializer: D) -> Result<ratatui::layout::Constraint, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Cells(u16),
+        Text(String),
+    }
+    let s = match Size::deserialize(deserializer)? {
+        Size::Cells(n) => return Ok(ratatui::layout::Constraint::Length(n)),
+        Size::Text(s) => s,
+    };
+    parse_size(&s).map_err(serde::de::Error::custom)
+}
+
+pub fn parse_size(s: &str) -> Result<ratatui::layout::Constraint, String> {
+    let s = s.trim();
+    let parsed = match s.strip_suffix('%') {
+        Some(p) => p
+            .trim()
+            .parse()
+            .map(|p: u16| ratatui::layout::Constraint::Percentage(p.min(100))),
+        None => s.parse().map(ratatui::layout::Constraint::Length),
+    };
+    parsed.map_err(|_| format!("invalid size {:?}, expected i.e. 25 or \"30%\"", s))
+}
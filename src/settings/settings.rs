@@ -1,30 +1,45 @@
-use config::Config;
+use color_eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use dirs::config_dir;
-use ratatui::layout::Alignment;
+use ratatui::layout::{Alignment, Constraint};
 use ratatui::style::Style;
 use ratatui::{style::Color, widgets::BorderType};
 use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 
-use crate::action::{Action, Search};
+use crate::action::{Action, InputMode, Search, Severity};
+use crate::common::color_depth::ColorDepth;
 use crate::common::module_state::UISection;
-use crate::components::util::IconMode;
+use crate::components::util::{IconMode, Truncation};
+use crate::settings::layers::{
+    SettingsOverrides, collect_layers, merge_layers, user_settings_file,
+};
 use crate::settings::serialise::{
     deserialize_alignment, deserialize_border_type, deserialize_color,
-    deserialize_optional_border_type, deserialize_optional_color, serialize_alignment,
-    serialize_border_type, serialize_color, serialize_optional_border_type,
-    serialize_optional_color,
+    deserialize_optional_border_type, deserialize_optional_color, deserialize_size,
+    serialize_alignment, serialize_border_type, serialize_color, serialize_optional_border_type,
+    serialize_optional_color, serialize_size,
 };
+use crate::settings::themes::deserialize_theme;
 
 pub fn get_settings_path() -> PathBuf {
     let path = config_dir().expect("Could not find config directory");
     path.join("rook")
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PanelPosition {
+    #[default]
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum VerticalAlignment {
     Top,
@@ -45,11 +60,8 @@ impl Default for SerializableKeyEvent {
         }
     }
 }
-impl Serialize for SerializableKeyEvent {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
+impl std::fmt::Display for SerializableKeyEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut parts = Vec::new();
 
         if self.modifiers.contains(KeyModifiers::CONTROL) {
@@ -65,11 +77,8 @@ impl Serialize for SerializableKeyEvent {
             parts.push("Super");
         }
 
-        if (self.code == KeyCode::Null) {
-            return serializer.serialize_none();
-        }
-
         let key_str = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
             KeyCode::Char(c) => c.to_string(),
             KeyCode::Enter => "Enter".to_string(),
             KeyCode::Tab => "Tab".to_string(),
@@ -90,30 +99,37 @@ impl Serialize for SerializableKeyEvent {
             _ => format!("{:?}", self.code),
         };
 
-        if !parts.is_empty() {
-            parts.push(&key_str);
-            serializer.serialize_str(&parts.join(" + "))
-        } else {
-            serializer.serialize_str(&key_str)
-        }
+        parts.push(&key_str);
+        write!(f, "{}", parts.join(" + "))
     }
 }
 
-impl<'de> Deserialize<'de> for SerializableKeyEvent {
-    // Deserialize a KeyEvent from a string like "Ctrl + A" or "Enter"
-    // can be in capitals or small letters
-    // splits by "+", trims spaces
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+impl Serialize for SerializableKeyEvent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
-        D: serde::Deserializer<'de>,
+        S: serde::Serializer,
     {
-        let s: String = Deserialize::deserialize(deserializer)?;
+        if (self.code == KeyCode::Null) {
+            return serializer.serialize_none();
+        }
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl std::str::FromStr for SerializableKeyEvent {
+    type Err = String;
+
+    // parse a key from a string like "Ctrl + A" or "Enter"
+    // modifiers and named keys can be in capitals or small letters
+    // a single character keeps its case, so "G" is Shift + g, unless combined with Ctrl or Alt
+    // splits by "+", trims spaces
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         let parts: Vec<&str> = s.split("+").map(|part| part.trim()).collect();
 
         let mut modifiers = KeyModifiers::empty();
-        let key_part = parts.last().ok_or_else(|| {
-            serde::de::Error::custom("Invalid key event format: missing key part")
-        })?;
+        let key_part = parts
+            .last()
+            .ok_or_else(|| "Invalid key event format: missing key part".to_string())?;
 
         for &part in &parts[..parts.len() - 1] {
             match part.to_lowercase().as_str() {
@@ -140,17 +156,113 @@ impl<'de> Deserialize<'de> for SerializableKeyEvent {
             "pagedown" => KeyCode::PageDown,
             "delete" => KeyCode::Delete,
             "insert" => KeyCode::Insert,
-            k if k.starts_with('f') => {
+            "space" => KeyCode::Char(' '),
+            k if k.starts_with('f') && k.len() > 1 => {
                 let n: u8 = k[1..]
                     .parse()
-                    .map_err(|_| serde::de::Error::custom("Invalid function key number"))?;
+                    .map_err(|_| "Invalid function key number".to_string())?;
                 KeyCode::F(n)
             }
-            k if k.len() == 1 => KeyCode::Char(k.chars().next().unwrap()),
-            _ => return Err(serde::de::Error::custom("Unknown key code")),
+            k if k.chars().count() == 1 => {
+                let c = key_part.chars().next().unwrap();
+                if modifiers.contains(KeyModifiers::SHIFT) {
+                    KeyCode::Char(c.to_ascii_uppercase())
+                } else if modifiers.is_empty() {
+                    KeyCode::Char(c)
+                } else {
+                    KeyCode::Char(c.to_ascii_lowercase())
+                }
+            }
+            _ => return Err(format!("Unknown key code {:?}", key_part)),
         };
 
-        Ok(SerializableKeyEvent { code, modifiers })
+        Ok(SerializableKeyEvent { code, modifiers }.normalized())
+    }
+}
+
+impl<'de> Deserialize<'de> for SerializableKeyEvent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl SerializableKeyEvent {
+    // terminals report Shift + a as 'A' with the shift modifier, and Shift + Tab as BackTab
+    // normalise both so they compare equal to the parsed bindings
+    pub fn normalized(mut self) -> Self {
+        match self.code {
+            KeyCode::Char(_) => self.modifiers.remove(KeyModifiers::SHIFT),
+            KeyCode::BackTab => {
+                self.code = KeyCode::Tab;
+                self.modifiers.insert(KeyModifiers::SHIFT);
+            }
+            _ => {}
+        }
+        self
+    }
+}
+
+/// A sequence of keys pressed one after another, i.e. "g g" or "Ctrl + x Ctrl + c".
+/// A single key is a sequence of length one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct KeySequence(pub Vec<SerializableKeyEvent>);
+
+impl KeySequence {
+    pub fn keys(&self) -> &[SerializableKeyEvent] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys: Vec<String> = self.0.iter().map(|key| key.to_string()).collect();
+        write!(f, "{}", keys.join(" "))
+    }
+}
+
+impl std::str::FromStr for KeySequence {
+    type Err = String;
+
+    // keys are separated by spaces, spaces around "+" belong to a single key
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut groups: Vec<String> = vec![];
+        for token in s.split_whitespace() {
+            match groups.last_mut() {
+                Some(last) if last.ends_with('+') || token.starts_with('+') => last.push_str(token),
+                _ => groups.push(token.to_string()),
+            }
+        }
+        if groups.is_empty() {
+            return Err("Empty key sequence".to_string());
+        }
+        let keys = groups
+            .iter()
+            .map(|group| group.parse())
+            .collect::<Result<Vec<SerializableKeyEvent>, String>>()?;
+        Ok(KeySequence(keys))
+    }
+}
+
+impl Serialize for KeySequence {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for KeySequence {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
     }
 }
 
@@ -160,6 +272,7 @@ impl From<KeyEvent> for SerializableKeyEvent {
             code: event.code,
             modifiers: event.modifiers,
         }
+        .normalized()
     }
 }
 
@@ -174,13 +287,14 @@ pub struct UISearchSettings {
     pub pre_query: String,     // text before the query input
     pub caret_text: String,    // caret character
     pub caret_blink_rate: u32, // in ms
-    pub caret_visible: bool, // if disabled, remove blinking, caret, and care movement    // if true, search as you type
+    pub caret_visible: bool,   // if disabled, remove blinking, caret, and care movement
+    pub placeholder: String,   // shown while the query is empty
     #[serde(
         deserialize_with = "deserialize_alignment",
         serialize_with = "serialize_alignment"
     )]
     pub text_alignment: Alignment, // alignment of the text: left, center, right
-    pub padding: u16,        // padding inside the search box
+    pub padding: u16,          // padding inside the search box
     pub rainbow_border: bool,
     pub rainbow_border_speed: f32, // speed of the rainbow border effect in scalar multiples 1.0, 1.5, 2.0 etc
 }
@@ -191,6 +305,7 @@ impl Default for UISearchSettings {
             caret_text: "▋".into(),
             caret_blink_rate: 500,
             caret_visible: true,
+            placeholder: "Type to search".into(),
             text_alignment: Alignment::Left,
             padding: 0,
             rainbow_border: false,
@@ -198,21 +313,33 @@ impl Default for UISearchSettings {
         }
     }
 }
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultsDisplay {
+    #[default]
+    Flat, // results of all modules in one list
+    Grouped, // results under a header per module, see `ui.results.group_limit`
+}
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UIResultsSettings {
-    pub max_results: usize,           // maximum number of results to display
-    pub show_scores: bool,            // whether to show scores next to results
-    pub open_through_number: bool,    // whether to open results through number keybinds
-    pub numbered: bool,               // whether to show numbers next to results
-    pub number_mode: IconMode,        // icon mode for numbers
-    pub loopback: bool,               // whether to loop back when navigating results
-    pub fade_color_at_bottom: bool,   // whether to fade text color towards the bottom
-    pub padding: u16,                 // padding inside the results box
-    pub fade_in: bool,                // whether to fade in results on search
-    pub fade_in_duration: u32,        // duration of fade in effect in ms
-    pub fade_top_to_bottom: bool,     // pattern used for fade in effect
-    pub rainbow_border: bool,         // whether to use rainbow border effect
+    pub max_results: usize,        // maximum number of results to display
+    pub show_scores: bool,         // whether to show scores next to results
+    pub open_through_number: bool, // whether to open results through number keybinds
+    pub numbered: bool,            // whether to show numbers next to results
+    pub number_mode: IconMode,     // icon mode for numbers
+    pub hint_keys: String,         // letters used for hint labels, home row first
+    pub display: ResultsDisplay,   // flat or grouped by module
+    pub truncation: Truncation,    // where long names are cut, Start, Middle or End
+    pub group_limit: usize,        // results shown per group when grouped, 0 for all
+    // group_limit for one module, by module name, i.e. maths_module = 3
+    #[serde(default)]
+    pub group_limits: BTreeMap<String, usize>,
+    pub loopback: bool,             // whether to loop back when navigating results
+    pub fade_color_at_bottom: bool, // whether to fade text color towards the bottom
+    pub padding: u16,               // padding inside the results box
+    pub rainbow_border: bool,       // whether to use rainbow border effect
     pub rainbow_border_speed: f32, // speed of the rainbow border effect in scalar multiples 1.0, 1.5, 2.0 etc
+    pub scrollbar: bool,           // whether to show a scrollbar when the results don't fit
     pub show_number_of_results: bool, // whether to show number of results at the top
     pub number_of_results_position: VerticalAlignment, // position of number of results text
     #[serde(
@@ -226,23 +353,142 @@ impl Default for UIResultsSettings {
         Self {
             max_results: 20,
             show_scores: true,            // show fuzzy scores next to results
-            numbered: true,               // show numbers next to results (upto 10)
-            open_through_number: true,    // CTRL + number to open
+            numbered: true,               // show numbers next to the visible results
+            open_through_number: true,    // CTRL + number to open, counted from the top of the page
             number_mode: IconMode::Small, // icon mode for numbers
-            loopback: true,               // loop back when navigating results
-            fade_color_at_bottom: true,   // fade text color towards the bottom. REQUIRES RGB COLORS
+            hint_keys: "asdfghjkl".to_string(),
+            display: ResultsDisplay::Flat,
+            truncation: Truncation::End,
+            group_limit: 5,
+            group_limits: BTreeMap::new(),
+            loopback: true,             // loop back when navigating results
+            fade_color_at_bottom: true, // fade text color towards the bottom. NEEDS 256+ COLORS
             padding: 1,
-            fade_in: true,          // fade in results on search
-            fade_in_duration: 1000, // duration of fade in effect in ms
-            fade_top_to_bottom: true,
             rainbow_border: false,
             rainbow_border_speed: 1.0,
+            scrollbar: true,
             show_number_of_results: true,
             number_of_results_position: VerticalAlignment::Bottom,
             number_of_results_alignment: Alignment::Right,
         }
     }
 }
+// an effect from tachyonfx, see `ui.effects`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectKind {
+    #[default]
+    None,
+    FadeIn, // from the background colour
+    FadeOut,
+    Dissolve,
+    Coalesce,
+    SweepIn, // fades in while sweeping across the area, needs a sweep pattern for the direction
+    SweepOut,
+}
+impl EffectKind {
+    // fades blend between rgb colours, which low colour depths can't show
+    pub fn uses_color(self) -> bool {
+        matches!(
+            self,
+            EffectKind::FadeIn | EffectKind::FadeOut | EffectKind::SweepIn | EffectKind::SweepOut
+        )
+    }
+}
+
+// how the effect progresses over its duration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    BackOut,
+    BounceOut,
+    ElasticOut,
+}
+
+// the order cells of the area are affected in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectPattern {
+    #[default]
+    None, // all at once
+    LeftToRight,
+    RightToLeft,
+    UpToDown,
+    DownToUp,
+    Radial,   // outwards from the centre
+    Diagonal, // top left to bottom right
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectSettings {
+    pub kind: EffectKind,       // "none" turns the effect off
+    pub duration: u32,          // in ms
+    pub easing: Easing,         // i.e. "linear", "quad_out" or "bounce_out"
+    pub pattern: EffectPattern, // i.e. "none", "left_to_right" or "radial"
+}
+
+// in toml this is
+// [ui.effects.open]
+// kind = "fade_in"
+// duration = 300
+// easing = "quad_out"
+// pattern = "radial"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UIEffectsSettings {
+    pub open: EffectSettings,      // the whole app, when it starts
+    pub close: EffectSettings,     // the whole app, quitting waits for it to finish
+    pub results: EffectSettings,   // the results list, when new results arrive
+    pub selection: EffectSettings, // the selected line, when the selection moves
+    pub launch: EffectSettings,    // the whole app, when a result is launched
+}
+impl UIEffectsSettings {
+    pub fn all_mut(&mut self) -> [&mut EffectSettings; 5] {
+        [
+            &mut self.open,
+            &mut self.close,
+            &mut self.results,
+            &mut self.selection,
+            &mut self.launch,
+        ]
+    }
+}
+impl Default for UIEffectsSettings {
+    fn default() -> Self {
+        Self {
+            open: EffectSettings {
+                kind: EffectKind::FadeIn,
+                duration: 300,
+                easing: Easing::QuadOut,
+                pattern: EffectPattern::None,
+            },
+            close: EffectSettings::default(),
+            results: EffectSettings {
+                kind: EffectKind::FadeIn,
+                duration: 1000,
+                easing: Easing::Linear,
+                pattern: EffectPattern::DownToUp,
+            },
+            selection: EffectSettings::default(),
+            launch: EffectSettings::default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UITooltipSettings {
     pub enabled: bool,     // whether tooltips are enabled
@@ -261,11 +507,44 @@ impl Default for UITooltipSettings {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UINotificationSettings {
+    pub toasts: bool, // show toasts, notices are kept in the messages view either way
+    pub min_severity: Severity, // quieter notices only go to the messages view
+    pub timeout: u64, // ms an info or warning toast is shown, 0 to keep it until clicked
+    pub error_timeout: u64, // ms an error toast is shown, 0 to keep it until clicked
+    pub max_toasts: usize, // toasts stacked at once, the oldest goes first
+    pub width: u16,   // columns of a toast, narrower when the view is
+    pub history_size: usize, // notices kept in the messages view
+}
+impl Default for UINotificationSettings {
+    fn default() -> Self {
+        Self {
+            toasts: true,
+            min_severity: Severity::Info,
+            timeout: 4000,
+            error_timeout: 8000,
+            max_toasts: 3,
+            width: 40,
+            history_size: 200,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UILayoutSettings {
-    pub sections: Vec<UISection>, // order of layout sections
+    pub sections: Vec<UISection>, // order of layout sections, Wizard here stacks it with them
     pub gap: u16,                 // gap between sections
     pub padding: u16,             // padding around the entire UI
+    pub side_panel: PanelPosition, // where the wizard is, unless it's in `sections`
+    #[serde(
+        deserialize_with = "deserialize_size",
+        serialize_with = "serialize_size"
+    )]
+    pub side_panel_size: Constraint, // columns, or rows above and below, i.e. 25 or "30%"
+    pub stack_below_width: u16,   // a left or right panel moves above or below, 0 for never
+    pub hide_below_width: u16,    // the panel is hidden below this width, 0 for never
+    pub short_below_height: u16,  // shorter has no padding above and below or panel rows
     pub title: String,            // title of the application
     #[serde(
         deserialize_with = "deserialize_alignment",
@@ -279,6 +558,11 @@ impl Default for UILayoutSettings {
             sections: vec![UISection::Search, UISection::Results],
             gap: 1,
             padding: 1,
+            side_panel: PanelPosition::Left,
+            side_panel_size: Constraint::Length(25),
+            stack_below_width: 0,
+            hide_below_width: 60,
+            short_below_height: 16,
             title: "Rook".into(),
             title_alignment: Alignment::Center,
         }
@@ -286,6 +570,9 @@ impl Default for UILayoutSettings {
 }
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct ThemeSettings {
+    // name of the theme file this was loaded from, if any
+    #[serde(default, skip_serializing)]
+    pub name: Option<String>,
     #[serde(
         deserialize_with = "deserialize_color",
         serialize_with = "serialize_color"
@@ -347,6 +634,18 @@ pub struct ThemeSettings {
     )]
     #[serde(default)]
     pub title: Color,
+    #[serde(
+        deserialize_with = "deserialize_color",
+        serialize_with = "serialize_color"
+    )]
+    #[serde(default)]
+    pub warning: Color,
+    #[serde(
+        deserialize_with = "deserialize_color",
+        serialize_with = "serialize_color"
+    )]
+    #[serde(default)]
+    pub error: Color,
 
     #[serde(
         deserialize_with = "deserialize_border_type",
@@ -488,6 +787,7 @@ pub struct ResultsThemeSettings {
 impl Default for ThemeSettings {
     fn default() -> Self {
         Self {
+            name: None,
             background: Color::Reset,
             highlight: Color::Blue,
             muted: Color::DarkGray,
@@ -500,6 +800,8 @@ impl Default for ThemeSettings {
             text_muted: Color::Rgb(150, 150, 150),
             text_accent: Color::Cyan,
             title: Color::White,
+            warning: Color::Yellow,
+            error: Color::Red,
             border_type: BorderType::Rounded,
 
             search: Some(SearchThemeSettings::default()),
@@ -529,12 +831,12 @@ impl ThemeSettings {
             muted_dark: Some(search.muted_dark.unwrap_or(self.muted_dark)),
             accent: Some(search.accent.unwrap_or(self.accent)),
             caret: Some(search.caret.unwrap_or(Color::Yellow)),
-            border: Some(search.border.unwrap_or(Color::Blue)),
-            pre_query_text: Some(search.pre_query_text.unwrap_or(Color::Rgb(200, 200, 200))),
-            text: Some(search.text.unwrap_or(Color::Rgb(200, 200, 200))),
-            text_muted: Some(search.text_muted.unwrap_or(Color::Rgb(150, 150, 150))),
-            text_accent: Some(search.text_accent.unwrap_or(Color::Cyan)),
-            border_type: Some(BorderType::Rounded),
+            border: Some(search.border.unwrap_or(self.border)),
+            pre_query_text: Some(search.pre_query_text.unwrap_or(self.text)),
+            text: Some(search.text.unwrap_or(self.text)),
+            text_muted: Some(search.text_muted.unwrap_or(self.text_muted)),
+            text_accent: Some(search.text_accent.unwrap_or(self.text_accent)),
+            border_type: Some(search.border_type.unwrap_or(self.border_type)),
         }
     }
     pub fn get_results_colors(&self) -> ResultsThemeSettings {
@@ -545,11 +847,11 @@ impl ThemeSettings {
             muted: Some(results.muted.unwrap_or(self.muted)),
             muted_dark: Some(results.muted_dark.unwrap_or(self.muted_dark)),
             accent: Some(results.accent.unwrap_or(self.accent)),
-            border: Some(results.border.unwrap_or(Color::Blue)),
-            text: Some(results.text.unwrap_or(Color::Rgb(200, 200, 200))),
-            text_muted: Some(results.text_muted.unwrap_or(Color::Rgb(150, 150, 150))),
-            text_accent: Some(results.text_accent.unwrap_or(Color::Cyan)),
-            border_type: Some(BorderType::Rounded),
+            border: Some(results.border.unwrap_or(self.border)),
+            text: Some(results.text.unwrap_or(self.text)),
+            text_muted: Some(results.text_muted.unwrap_or(self.text_muted)),
+            text_accent: Some(results.text_accent.unwrap_or(self.text_accent)),
+            border_type: Some(results.border_type.unwrap_or(self.border_type)),
         }
     }
 
@@ -595,157 +897,363 @@ pub struct UISettings {
     pub results: UIResultsSettings,
     #[serde(default)]
     pub tooltip: UITooltipSettings,
+    #[serde(default, deserialize_with = "deserialize_theme")]
+    pub theme: ThemeSettings, // theme name i.e. "nord", or a table of colours
     #[serde(default)]
-    pub theme: ThemeSettings,
+    pub color_depth: ColorDepth, // "auto", "truecolor", "ansi256", "ansi16" or "monochrome"
+    #[serde(default)]
+    pub effects: UIEffectsSettings,
+    #[serde(default)]
+    pub reduce_motion: bool, // turns off every effect, including rainbow borders
+    #[serde(default)]
+    pub notifications: UINotificationSettings,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EditingMode {
+    #[default]
+    Standard,
+    Vim, // normal mode for navigating, insert mode for typing the query
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputSettings {
+    pub chord_timeout: u64, // ms to wait for the next key of a sequence like "g g"
+    pub editing_mode: EditingMode, // "standard" or "vim"
+    pub show_pending_keys: bool, // show the keys of an unfinished sequence in the search box
+    pub mouse: bool, // hover to select, click to launch. off leaves text selection to the terminal
+}
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            chord_timeout: 1000,
+            editing_mode: EditingMode::Standard,
+            show_pending_keys: true,
+            mouse: true,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SearchSettings {
     pub always_search: bool, // if true, search as you type
+    pub history_size: usize, // number of past queries to remember, 0 to keep none
+    // prefix that scopes a query to one module, by module name, i.e. maths_module = "="
+    // modules without one here use their own, an empty prefix turns it off
+    #[serde(default)]
+    pub prefixes: BTreeMap<String, String>,
 }
 impl Default for SearchSettings {
     fn default() -> Self {
         Self {
             always_search: true,
+            history_size: 100,
+            prefixes: BTreeMap::new(),
+        }
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchSettings {
+    pub keep_open: bool,     // stay open after launching, with the query cleared
+    pub failure_window: u64, // ms a program is watched for exiting with an error, 0 to not wait
+    pub history_size: usize, // number of launches to remember, 0 to keep none
+}
+impl Default for LaunchSettings {
+    fn default() -> Self {
+        Self {
+            keep_open: false,
+            failure_window: 300,
+            history_size: 200,
+        }
+    }
+}
+
+/// How a result is rounded to `calculator.precision` decimal places.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    #[default]
+    HalfUp, // 2.5 to 3 and -2.5 to -3
+    HalfEven, // 2.5 to 2 and 3.5 to 4, bankers' rounding
+    Down,     // towards zero
+    Up,       // away from zero
+}
+
+/// Another way of showing a result, below the decimal one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberFormat {
+    Hex,        // 0xff, for whole numbers
+    Octal,      // 0o377, for whole numbers
+    Binary,     // 0b11111111, for whole numbers
+    Scientific, // 1.5e-7, for very large or small numbers
+    Fraction,   // 3/4, for numbers close to a fraction
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalculatorSettings {
+    pub precision: usize, // decimal places results are rounded to, trailing zeros are dropped
+    pub rounding: Rounding, // "half_up", "half_even", "down" or "up"
+    pub thousands_separator: bool, // group digits, i.e. 1,234,567.5
+    // separators of a locale, i.e. "de_DE" for 1.234,5, empty for LC_NUMERIC or LANG
+    pub locale: String,
+    // shown below a result when they apply: "hex", "octal", "binary", "scientific", "fraction"
+    pub formats: Vec<NumberFormat>,
+}
+impl Default for CalculatorSettings {
+    fn default() -> Self {
+        Self {
+            precision: 10,
+            rounding: Rounding::HalfUp,
+            thousands_separator: true,
+            locale: String::new(),
+            formats: vec![NumberFormat::Scientific, NumberFormat::Fraction],
         }
     }
 }
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+
+/// Keys bound to each action, by action string, see [`Action::parse`].
+pub type ActionBindings = BTreeMap<String, Vec<KeySequence>>;
+
+// in toml this is
+// [keybinds]
+// quit = ["Ctrl + q", "Esc"]
+// "navigate_down:10" = ["PageDown"]
+// "search:set:=" = ["Ctrl + m"]
+// toggle_scores = "Ctrl + s"  (a single key doesn't need a list)
+//
+// [keybinds.normal]     vim normal mode, on top of the bindings above
+// "navigate_end" = ["G"]
+//
+// [keybinds.hint]       while hint labels are shown, other keys type the label
+// hide_hints = ["Esc"]
+//
+// [keybinds.completion] while ghost text is shown after the query
+// accept_completion = ["Right", "Tab"]
+//
+// [keybinds.messages]   while the messages view is open, replaces all other bindings
+// toggle_messages = ["Esc", "q", "F2"]
+//
+// [keybinds.help]       while the help overlay is open, other keys type its filter
+// toggle_help = ["Esc", "F1"]
+//
+// actions are merged with the defaults, so only changed actions need to be listed
+// an empty list unbinds an action, i.e. quit = []
+// a key bound by the user wins over the same key in the defaults, i.e. the PageDown above
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct KeyBindings {
-    #[serde(default)]
-    pub quit: Vec<SerializableKeyEvent>,
-    #[serde(default)]
-    pub navigate_down: Vec<SerializableKeyEvent>,
-    #[serde(default)]
-    pub navigate_up: Vec<SerializableKeyEvent>,
-    #[serde(default)]
-    pub navigate_left: Vec<SerializableKeyEvent>,
-    #[serde(default)]
-    pub navigate_right: Vec<SerializableKeyEvent>,
-    #[serde(default)]
-    pub navigate_home: Vec<SerializableKeyEvent>,
-    #[serde(default)]
-    pub navigate_end: Vec<SerializableKeyEvent>,
-    #[serde(default)]
-    pub focus_next: Vec<SerializableKeyEvent>,
-    #[serde(default)]
-    pub focus_previous: Vec<SerializableKeyEvent>,
-    #[serde(default)]
-    pub toggle_wizard: Vec<SerializableKeyEvent>,
+    #[serde(flatten, deserialize_with = "deserialize_action_bindings")]
+    pub global: ActionBindings,
+    #[serde(default, deserialize_with = "deserialize_action_bindings")]
+    pub normal: ActionBindings, // vim normal mode
+    #[serde(default, deserialize_with = "deserialize_action_bindings")]
+    pub insert: ActionBindings, // vim insert mode
+    #[serde(default, deserialize_with = "deserialize_action_bindings")]
+    pub hint: ActionBindings, // while hint labels are shown, replaces all other bindings
+    #[serde(default, deserialize_with = "deserialize_action_bindings")]
+    pub completion: ActionBindings, // while a completion is shown, on top of the other bindings
+    #[serde(default, deserialize_with = "deserialize_action_bindings")]
+    pub messages: ActionBindings, // while the messages view is open, replaces all other bindings
+    #[serde(default, deserialize_with = "deserialize_action_bindings")]
+    pub help: ActionBindings, // while the help overlay is open, replaces all other bindings
+}
+
+// accept a single key as well as a list, so `--set keybinds.quit="Ctrl + c"` works
+fn deserialize_action_bindings<'de, D>(deserializer: D) -> Result<ActionBindings, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(KeySequence),
+        Many(Vec<KeySequence>),
+    }
+    let bindings: BTreeMap<String, OneOrMany> = Deserialize::deserialize(deserializer)?;
+    Ok(bindings
+        .into_iter()
+        .map(|(action, keys)| match keys {
+            OneOrMany::One(key) => (action, vec![key]),
+            OneOrMany::Many(keys) => (action, keys),
+        })
+        .collect())
 }
 impl KeyBindings {
-    pub fn get_event_mapping(&self) -> HashMap<SerializableKeyEvent, Action> {
-        let mut mapping = HashMap::new();
+    pub fn get_event_mapping(&self) -> HashMap<KeySequence, Action> {
+        Self::mapping(&self.global, &Self::default().global)
+    }
+
+    pub fn get_mode_mapping(&self, mode: InputMode) -> HashMap<KeySequence, Action> {
+        let defaults = Self::default();
+        match mode {
+            InputMode::Normal => Self::mapping(&self.normal, &defaults.normal),
+            InputMode::Insert => Self::mapping(&self.insert, &defaults.insert),
+            InputMode::Hint => Self::mapping(&self.hint, &defaults.hint),
+            InputMode::Messages => Self::mapping(&self.messages, &defaults.messages),
+            InputMode::Help => Self::mapping(&self.help, &defaults.help),
+        }
+    }
 
-        let bindings = [
-            (&self.quit, "quit"),
-            (&self.navigate_down, "navigate_down"),
-            (&self.navigate_up, "navigate_up"),
-            (&self.navigate_left, "navigate_left"),
-            (&self.navigate_right, "navigate_right"),
-            (&self.navigate_home, "navigate_home"),
-            (&self.navigate_end, "navigate_end"),
-            (&self.focus_next, "focus_next"),
-            (&self.focus_previous, "focus_previous"),
-            (&self.toggle_wizard, "toggle_wizard"),
-        ];
-
-        for (keys, action_str) in bindings {
+    pub fn get_completion_mapping(&self) -> HashMap<KeySequence, Action> {
+        Self::mapping(&self.completion, &Self::default().completion)
+    }
+
+    /// The keys bound to an action outside of any mode, single keys before combinations.
+    pub fn keys_for(&self, action: &Action) -> Vec<KeySequence> {
+        let mut keys: Vec<KeySequence> = self
+            .get_event_mapping()
+            .into_iter()
+            .filter(|(_, bound)| bound == action)
+            .map(|(keys, _)| keys)
+            .collect();
+        keys.sort_by_key(|keys| (keys.to_string().len(), keys.to_string()));
+        keys
+    }
+
+    /// The bindings whose action doesn't parse, with why, in every mode.
+    pub fn invalid(&self) -> Vec<String> {
+        [
+            &self.global,
+            &self.normal,
+            &self.insert,
+            &self.hint,
+            &self.completion,
+            &self.messages,
+            &self.help,
+        ]
+        .into_iter()
+        .flat_map(|bindings| bindings.keys())
+        .filter_map(|action_str| {
+            let err = Action::parse(action_str).err()?;
+            Some(format!("Ignoring keybinding {:?}: {}", action_str, err))
+        })
+        .collect()
+    }
+
+    // the settings are already merged with the defaults, so actions whose keys are still the
+    // default ones go first, and a key the user bound to another action replaces them
+    fn mapping(
+        bindings: &ActionBindings,
+        defaults: &ActionBindings,
+    ) -> HashMap<KeySequence, Action> {
+        let (unchanged, changed): (Vec<_>, Vec<_>) = bindings
+            .iter()
+            .partition(|(action_str, keys)| defaults.get(*action_str) == Some(*keys));
+        let mut mapping = HashMap::new();
+        let mut user_keys = HashSet::new();
+        for (by_user, (action_str, keys)) in unchanged
+            .into_iter()
+            .map(|binding| (false, binding))
+            .chain(changed.into_iter().map(|binding| (true, binding)))
+        {
+            let action = match Action::parse(action_str) {
+                Ok(action) => action,
+                Err(err) => {
+                    log::warn!("Ignoring keybinding {:?}: {}", action_str, err);
+                    continue;
+                }
+            };
             for key in keys {
-                mapping.insert(key.clone(), Action::from(action_str));
+                let replaced = mapping.insert(key.clone(), action.clone());
+                // a user binding replacing a default one is expected, two of a kind aren't
+                let overrides_default = by_user && user_keys.insert(key.clone());
+                match replaced {
+                    Some(replaced) if overrides_default => {
+                        log::debug!("{} is bound to {:?} over {:?}", key, action, replaced);
+                    }
+                    Some(replaced) => {
+                        log::warn!("{} is bound to both {:?} and {:?}", key, replaced, action);
+                    }
+                    None => {}
+                }
             }
         }
-
         mapping
     }
 }
+fn action_bindings(bindings: &[(&str, &[&str])]) -> ActionBindings {
+    bindings
+        .iter()
+        .map(|(action, keys)| {
+            (
+                action.to_string(),
+                keys.iter().map(|k| keybinding(k)).collect(),
+            )
+        })
+        .collect()
+}
 impl Default for KeyBindings {
     fn default() -> Self {
         Self {
-            quit: vec![keybinding("Ctrl + q"), keybinding("Esc")],
-            navigate_down: vec![SerializableKeyEvent::default()],
-            navigate_up: vec![SerializableKeyEvent::default()],
-            navigate_left: vec![SerializableKeyEvent::default()],
-            navigate_right: vec![SerializableKeyEvent::default()],
-            navigate_home: vec![SerializableKeyEvent::default()],
-            navigate_end: vec![SerializableKeyEvent::default()],
-            focus_next: vec![keybinding("Tab")],
-            focus_previous: vec![keybinding("Shift + Tab")],
-            toggle_wizard: vec![keybinding("Ctrl + b")],
-        }
-    }
-}
-
-impl<'de> Deserialize<'de> for KeyBindings {
-    // deserialise from a mapping of action to Vec<keys>
-    //
-    // in toml this is
-    // [keybindings]
-    // quit = ["Ctrl + q", "Esc"]
-    //
-    // this maps to a HashMap<Action, Vec<SerializableKeyEvent>>
-    // if value is missing, use default keybinding for that action
-    //
-    // this function only gets run when [keybindings] is present in the toml, other it just uses default as defined by #[serde(default)] in settings struct
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let mapping: HashMap<Action, Vec<SerializableKeyEvent>> =
-            Deserialize::deserialize(deserializer)?;
-        let default = KeyBindings::default();
-
-        let keybindings = Self {
-            quit: mapping.get(&Action::Quit).cloned().unwrap_or(default.quit),
-            navigate_down: mapping
-                .get(&Action::Navigate(crate::action::NavigateDirection::Down, 1))
-                .cloned()
-                .unwrap_or(default.navigate_down),
-            navigate_up: mapping
-                .get(&Action::Navigate(crate::action::NavigateDirection::Up, 1))
-                .cloned()
-                .unwrap_or(default.navigate_up),
-            navigate_left: mapping
-                .get(&Action::Navigate(crate::action::NavigateDirection::Left, 1))
-                .cloned()
-                .unwrap_or(default.navigate_left),
-            navigate_right: mapping
-                .get(&Action::Navigate(
-                    crate::action::NavigateDirection::Right,
-                    1,
-                ))
-                .cloned()
-                .unwrap_or(default.navigate_right),
-            navigate_home: mapping
-                .get(&Action::Navigate(crate::action::NavigateDirection::Home, 1))
-                .cloned()
-                .unwrap_or(default.navigate_home),
-            navigate_end: mapping
-                .get(&Action::Navigate(crate::action::NavigateDirection::End, 1))
-                .cloned()
-                .unwrap_or(default.navigate_end),
-            focus_next: mapping
-                .get(&Action::FocusNext)
-                .cloned()
-                .unwrap_or(default.focus_next),
-            focus_previous: mapping
-                .get(&Action::FocusPrevious)
-                .cloned()
-                .unwrap_or(default.focus_previous),
-            toggle_wizard: mapping
-                .get(&Action::ToggleWizard)
-                .cloned()
-                .unwrap_or(default.toggle_wizard),
-        };
-
-        log::debug!("Deserialized KeyBindings: {:#?}", keybindings);
-        Ok(keybindings)
+            global: action_bindings(&[
+                ("quit", &["Ctrl + q", "Esc"]),
+                ("navigate_up", &["Up"]),
+                ("navigate_down", &["Down"]),
+                ("next_group", &["Ctrl + Down"]),
+                ("page_up", &["PageUp"]),
+                ("page_down", &["PageDown"]),
+                ("previous_group", &["Ctrl + Up"]),
+                ("execute", &["Enter"]),
+                // needs a terminal that reports Shift + Enter apart from Enter, i.e. kitty
+                ("execute_keep_open", &["Shift + Enter"]),
+                ("focus_next", &["Tab"]),
+                ("focus_previous", &["Shift + Tab"]),
+                ("toggle_wizard", &["Ctrl + b"]),
+                ("show_hints", &["Ctrl + f"]),
+                ("history_previous", &["Ctrl + r"]),
+                ("delete_word_back", &["Ctrl + w", "Alt + Backspace"]),
+                ("delete_to_start", &["Ctrl + u"]),
+                ("delete_to_end", &["Ctrl + k"]),
+                ("word_back", &["Alt + b", "Ctrl + Left"]),
+                ("word_forward", &["Alt + f", "Ctrl + Right"]),
+                ("line_start", &["Ctrl + a", "Home"]),
+                ("line_end", &["Ctrl + e", "End"]),
+                ("toggle_messages", &["F2"]),
+                ("toggle_help", &["F1"]),
+                // Space types into the query, it marks in vim normal mode
+                ("toggle_mark", &["Ctrl + Space"]),
+            ]),
+            normal: action_bindings(&[
+                ("navigate_down", &["j"]),
+                ("navigate_up", &["k"]),
+                ("navigate_home", &["g g"]),
+                ("navigate_end", &["G"]),
+                ("next_group", &["}"]),
+                ("half_page_down", &["Ctrl + d"]),
+                ("half_page_up", &["Ctrl + u"]),
+                ("previous_group", &["{"]),
+                ("insert_mode", &["i", "a", "/"]),
+                ("show_hints", &["f"]),
+                ("toggle_help", &["?"]),
+                ("toggle_mark", &["Space"]),
+                ("quit", &["q", "Esc"]),
+            ]),
+            insert: action_bindings(&[("normal_mode", &["Esc"])]),
+            hint: action_bindings(&[("hide_hints", &["Esc"])]),
+            completion: action_bindings(&[("accept_completion", &["Right", "Tab"])]),
+            messages: action_bindings(&[
+                ("navigate_up", &["Up", "k"]),
+                ("navigate_down", &["Down", "j"]),
+                ("page_up", &["PageUp"]),
+                ("page_down", &["PageDown"]),
+                ("navigate_home", &["Home", "g g"]),
+                ("navigate_end", &["End", "G"]),
+                ("toggle_messages", &["Esc", "q", "F2"]),
+            ]),
+            help: action_bindings(&[
+                ("navigate_up", &["Up"]),
+                ("navigate_down", &["Down"]),
+                ("page_up", &["PageUp"]),
+                ("page_down", &["PageDown"]),
+                ("toggle_help", &["Esc", "F1"]),
+            ]),
+        }
     }
 }
 
-fn keybinding(text: &str) -> SerializableKeyEvent {
-    serde_json::from_str::<SerializableKeyEvent>(&format!("\"{}\"", text))
-        .unwrap_or_else(|_| SerializableKeyEvent::default())
+pub fn keybinding(text: &str) -> KeySequence {
+    text.parse().unwrap_or_default()
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
@@ -757,22 +1265,38 @@ pub struct Settings {
     pub ui: UISettings,
     #[serde(default)]
     pub keybinds: KeyBindings,
+    #[serde(default)]
+    pub input: InputSettings,
+    #[serde(default)]
+    pub launch: LaunchSettings,
+    #[serde(default)]
+    pub calculator: CalculatorSettings,
 }
 
 impl Settings {
-    pub fn new() -> Self {
-        let path = get_settings_path();
-        let config_file = path.join("settings.toml");
-
-        if config_file.exists() {
-            return Self::read_settings(config_file);
-        } else {
-            Self::read_settings(config_file);
+    /// Load settings by merging every settings layer, see [`crate::settings::layers`].
+    ///
+    /// Writes the default settings to the user settings file if it doesn't exist yet.
+    pub fn load(overrides: &SettingsOverrides) -> Result<Self> {
+        let config_file = user_settings_file();
+        if !config_file.exists() {
+            log::warn!(
+                "Could not find settings file at {:?}, writing default settings",
+                config_file
+            );
+            Settings::default().write_default_settings(config_file);
         }
 
-        Self::default()
+        let layers = collect_layers(overrides)?;
+        for layer in layers.iter() {
+            log::info!("Reading settings layer {}", layer.name);
+        }
+        let settings = merge_layers(&layers)?;
+        log::trace!("Deserialized settings: {:?}", settings);
+        Ok(settings)
     }
 
+    #[allow(unused)]
     fn read_settings(_config_file: PathBuf) -> Self {
         log::info!("Reading settings from {:?}", _config_file);
 
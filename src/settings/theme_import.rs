@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use serde::Deserialize;
+use toml::{Table, Value};
+
+// import colours from other theming tools, selected with
+//
+//   ui.theme = "base16:~/.config/base16/nord.yaml"
+//   ui.theme = "pywal"                              (reads ~/.cache/wal/colors.json)
+//   ui.theme = "pywal:/path/to/colors.json"
+//
+// both can also be used as `inherits = "..."` in a theme file, to tweak an imported scheme
+//
+// base16 mapping, following the base16 styling guidelines
+//   background          base00  default background
+//   muted_dark          base01  lighter background
+//   muted               base03  comments
+//   text_muted          base04  dark foreground
+//   text                base05  default foreground
+//   title               base06  light foreground
+//   highlight, border   base0D  blue
+//   accent, text_accent base0C  cyan
+//   error               base08  red
+//   warning             base0A  yellow
+//   search.caret        base0A  yellow
+//   search.pre_query_text base0B green
+//   results.highlight   base02  selection background
+//   results.text_accent base0E  magenta
+//
+// pywal mapping
+//   background          special.background
+//   text                special.foreground
+//   search.caret        special.cursor
+//   muted_dark          color0
+//   muted               color8
+//   text_muted          color7
+//   title               color15
+//   highlight, border   color4
+//   accent, text_accent color6
+//   error               color1
+//   warning             color3
+//   search.pre_query_text color2
+//   results.highlight   color8
+//   results.text_accent color5
+
+pub const BASE16_PREFIX: &str = "base16:";
+pub const PYWAL_PREFIX: &str = "pywal";
+
+pub fn default_pywal_path() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_default()
+        .join("wal")
+        .join("colors.json")
+}
+
+// expand a leading ~ to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Returns the file an imported theme is read from, if the theme name is an import.
+pub fn import_path(name: &str) -> Option<PathBuf> {
+    if let Some(path) = name.strip_prefix(BASE16_PREFIX) {
+        return Some(expand_home(path.trim()));
+    }
+    match name.strip_prefix(PYWAL_PREFIX) {
+        Some("") => Some(default_pywal_path()),
+        Some(path) => path.strip_prefix(':').map(|p| expand_home(p.trim())),
+        None => None,
+    }
+}
+
+/// Build a theme table from an imported theme name, or `None` if the name is not an import.
+pub fn import_theme_table(name: &str) -> Option<Result<Table>> {
+    let path = import_path(name)?;
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) => return Some(Err(eyre!("Could not read theme {:?}: {}", path, err))),
+    };
+    if name.starts_with(BASE16_PREFIX) {
+        Some(base16_theme_table(&contents))
+    } else {
+        Some(pywal_theme_table(&contents))
+    }
+}
+
+// convert "#2e3440" or "2E3440" to the "r,g,b" format used in settings
+fn hex_to_rgb(hex: &str) -> Result<String> {
+    let hex = hex.trim().trim_start_matches('#');
+    // checked as bytes, so slicing below can't split a multibyte character
+    if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(eyre!("Invalid hex colour {:?}", hex));
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+    Ok(format!("{},{},{}", channel(0)?, channel(2)?, channel(4)?))
+}
+
+// build a theme table from (section, key, colour) triples
+fn theme_table(colors: &[(Option<&str>, &str, String)]) -> Table {
+    let mut table = Table::new();
+    for (section, key, color) in colors {
+        let target = match section {
+            Some(section) => table
+                .entry(section.to_string())
+                .or_insert_with(|| Value::Table(Table::new()))
+                .as_table_mut()
+                .expect("theme section is a table"),
+            None => &mut table,
+        };
+        target.insert(key.to_string(), Value::String(color.clone()));
+    }
+    table
+}
+
+/// Map a base16 scheme onto a theme table.
+///
+/// Only the `baseXX: "hex"` lines are read, so both the original flat format and the
+/// newer format with a nested `palette:` work without a full yaml parser.
+pub fn base16_theme_table(yaml: &str) -> Result<Table> {
+    let mut palette: HashMap<String, String> = HashMap::new();
+    for line in yaml.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        if key.len() != 6
+            || !key.starts_with("base")
+            || !key[4..].chars().all(|c| c.is_ascii_hexdigit())
+        {
+            continue;
+        }
+        // drop trailing comments, i.e. base00: "2e3440" # background
+        let value = value.split(" #").next().unwrap_or_default();
+        if let Ok(rgb) = hex_to_rgb(value.trim().trim_matches(['"', '\''])) {
+            palette.insert(key, rgb);
+        }
+    }
+    let base = |key: &str| {
+        palette
+            .get(key)
+            .cloned()
+            .ok_or_else(|| eyre!("base16 scheme is missing {}", key))
+    };
+
+    Ok(theme_table(&[
+        (None, "background", base("base00")?),
+        (None, "muted_dark", base("base01")?),
+        (None, "muted", base("base03")?),
+        (None, "text_muted", base("base04")?),
+        (None, "text", base("base05")?),
+        (None, "title", base("base06")?),
+        (None, "highlight", base("base0d")?),
+        (None, "border", base("base0d")?),
+        (None, "accent", base("base0c")?),
+        (None, "text_accent", base("base0c")?),
+        (None, "error", base("base08")?),
+        (None, "warning", base("base0a")?),
+        (Some("search"), "caret", base("base0a")?),
+        (Some("search"), "pre_query_text", base("base0b")?),
+        (Some("results"), "highlight", base("base02")?),
+        (Some("results"), "text_accent", base("base0e")?),
+    ]))
+}
+
+#[derive(Debug, Deserialize)]
+struct PywalColors {
+    special: HashMap<String, String>,
+    colors: HashMap<String, String>,
+}
+
+/// Map a pywal `colors.json` onto a theme table.
+pub fn pywal_theme_table(json: &str) -> Result<Table> {
+    let wal: PywalColors = serde_json::from_str(json)?;
+    let special = |key: &str| {
+        wal.special
+            .get(key)
+            .ok_or_else(|| eyre!("pywal colours are missing special.{}", key))
+            .and_then(|hex| hex_to_rgb(hex))
+    };
+    let color = |n: u8| {
+        wal.colors
+            .get(&format!("color{}", n))
+            .ok_or_else(|| eyre!("pywal colours are missing color{}", n))
+            .and_then(|hex| hex_to_rgb(hex))
+    };
+
+    Ok(theme_table(&[
+        (None, "background", special("background")?),
+        (None, "text", special("foreground")?),
+        (None, "muted_dark", color(0)?),
+        (None, "muted", color(8)?),
+        (None, "text_muted", color(7)?),
+        (None, "title", color(15)?),
+        (None, "highlight", color(4)?),
+        (None, "border", color(4)?),
+        (None, "accent", color(6)?),
+        (None, "text_accent", color(6)?),
+        (None, "error", color(1)?),
+        (None, "warning", color(3)?),
+        (Some("search"), "caret", special("cursor")?),
+        (Some("search"), "pre_query_text", color(2)?),
+        (Some("results"), "highlight", color(8)?),
+        (Some("results"), "text_accent", color(5)?),
+    ]))
+}
+
+/// Tracks the modification times of the files a theme was loaded from, including the themes it
+/// inherits from, so it can be reloaded when one of them changes.
+#[derive(Debug, Clone)]
+pub struct ThemeWatcher {
+    files: Vec<(PathBuf, Option<std::time::SystemTime>)>,
+}
+
+impl ThemeWatcher {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let modified = Self::modified_time(&path);
+                (path, modified)
+            })
+            .collect();
+        Self { files }
+    }
+
+    fn modified_time(path: &Path) -> Option<std::time::SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    /// Returns true once each time any of the files is modified.
+    pub fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (path, modified) in self.files.iter_mut() {
+            let now = Self::modified_time(path);
+            if now != *modified {
+                *modified = now;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NORD_BASE16: &str = r#"
+scheme: "Nord"
+author: "arcticicestudio"
+base00: "2E3440"
+base01: "3B4252"
+base02: "434C5E"
+base03: "4C566A"
+base04: "D8DEE9"
+base05: "E5E9F0"
+base06: "ECEFF4"
+base07: "8FBCBB"
+base08: "BF616A"
+base09: "D08770"
+base0A: "EBCB8B"
+base0B: "A3BE8C"
+base0C: "88C0D0"
+base0D: "81A1C1"
+base0E: "B48EAD"
+base0F: "5E81AC"
+"#;
+
+    #[test]
+    fn test_base16_mapping() {
+        let table = base16_theme_table(NORD_BASE16).unwrap();
+        assert_eq!(table["background"].as_str(), Some("46,52,64"));
+        assert_eq!(table["text"].as_str(), Some("229,233,240"));
+        assert_eq!(table["search"]["caret"].as_str(), Some("235,203,139"));
+
+        // nested palette format
+        let nested = NORD_BASE16.replace("\nbase", "\n  base");
+        assert_eq!(base16_theme_table(&nested).unwrap(), table);
+
+        assert!(base16_theme_table("base00: \"2E3440\"").is_err());
+    }
+
+    #[test]
+    fn test_pywal_mapping() {
+        let mut colors = String::new();
+        for n in 0..16 {
+            colors.push_str(&format!("\"color{}\": \"#0000{:02x}\",", n, n));
+        }
+        let json = format!(
+            r##"{{
+                "wallpaper": "/tmp/wall.png",
+                "special": {{ "background": "#101010", "foreground": "#f0f0f0", "cursor": "#ff0000" }},
+                "colors": {{ {} }}
+            }}"##,
+            colors.trim_end_matches(',')
+        );
+        let table = pywal_theme_table(&json).unwrap();
+        assert_eq!(table["background"].as_str(), Some("16,16,16"));
+        assert_eq!(table["highlight"].as_str(), Some("0,0,4"));
+        assert_eq!(table["search"]["caret"].as_str(), Some("255,0,0"));
+    }
+
+    #[test]
+    fn test_hex_to_rgb() {
+        assert_eq!(hex_to_rgb("#2e3440").unwrap(), "46,52,64");
+        assert_eq!(hex_to_rgb(" 2E3440 ").unwrap(), "46,52,64");
+        assert!(hex_to_rgb("2e344").is_err());
+        assert!(hex_to_rgb("2e344g").is_err());
+        // 6 bytes, but slicing by byte would split the é
+        assert!(hex_to_rgb("é1234").is_err());
+    }
+
+    #[test]
+    fn test_watcher_sees_parent_changes() {
+        let dir = std::env::temp_dir().join(format!("rook-themes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (child, parent) = (dir.join("child.toml"), dir.join("parent.toml"));
+        std::fs::write(&child, "inherits = \"parent\"").unwrap();
+        std::fs::write(&parent, "accent = \"Red\"").unwrap();
+
+        let mut watcher = ThemeWatcher::new(vec![child, parent.clone()]);
+        assert!(!watcher.changed());
+        let file = std::fs::File::options().write(true).open(&parent).unwrap();
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+        file.set_modified(later).unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_path() {
+        assert_eq!(import_path("pywal"), Some(default_pywal_path()));
+        assert_eq!(
+            import_path("pywal:/tmp/colors.json"),
+            Some(PathBuf::from("/tmp/colors.json"))
+        );
+        assert_eq!(
+            import_path("base16:/tmp/nord.yaml"),
+            Some(PathBuf::from("/tmp/nord.yaml"))
+        );
+        assert_eq!(import_path("nord"), None);
+        assert_eq!(import_path("pywalish"), None);
+    }
+}
@@ -0,0 +1,297 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Deserializer};
+use toml::{Table, Value};
+
+use crate::settings::settings::{ThemeSettings, get_settings_path};
+use crate::settings::theme_import::{
+    PYWAL_PREFIX, default_pywal_path, import_path, import_theme_table,
+};
+
+// themes can be selected by name with `ui.theme = "nord"` in settings.toml
+// themes are looked up in ~/.config/rook/themes/<name>.toml first, then the built in themes below
+// base16 and pywal colours can be imported too, see theme_import.rs
+//
+// a theme file has the same keys as [ui.theme], plus an optional `inherits = "<name>"`
+// any key missing from a theme is taken from the theme it inherits from, or the default theme
+
+const MAX_INHERITANCE_DEPTH: usize = 8;
+
+pub const BUILTIN_THEMES: &[(&str, &str)] = &[
+    ("default", ""),
+    ("nord", include_str!("themes/nord.toml")),
+    ("nord-frost", include_str!("themes/nord-frost.toml")),
+    ("gruvbox-dark", include_str!("themes/gruvbox-dark.toml")),
+    (
+        "catppuccin-mocha",
+        include_str!("themes/catppuccin-mocha.toml"),
+    ),
+    ("dracula", include_str!("themes/dracula.toml")),
+    ("solarized-dark", include_str!("themes/solarized-dark.toml")),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThemeSource {
+    Builtin,
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThemeEntry {
+    pub name: String,
+    pub source: ThemeSource,
+}
+
+pub fn themes_path() -> PathBuf {
+    get_settings_path().join("themes")
+}
+
+/// List every available theme, user themes first, sorted by name.
+/// A user theme with the same name as a built in theme replaces it.
+pub fn list_themes() -> Vec<ThemeEntry> {
+    let mut themes: Vec<ThemeEntry> = vec![];
+    if let Ok(entries) = std::fs::read_dir(themes_path()) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("toml") {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                themes.push(ThemeEntry {
+                    name: name.to_string(),
+                    source: ThemeSource::File(path.clone()),
+                });
+            }
+        }
+    }
+    themes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for (name, _) in BUILTIN_THEMES {
+        if !themes.iter().any(|t| t.name == *name) {
+            themes.push(ThemeEntry {
+                name: name.to_string(),
+                source: ThemeSource::Builtin,
+            });
+        }
+    }
+
+    let pywal = default_pywal_path();
+    if pywal.exists() {
+        themes.push(ThemeEntry {
+            name: PYWAL_PREFIX.to_string(),
+            source: ThemeSource::File(pywal),
+        });
+    }
+    themes
+}
+
+// theme names are file names in the themes directory, so they can't point outside of it
+fn theme_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    if name.contains(['/', '\\']) || name.contains("..") {
+        return Err(eyre!("Invalid theme name {:?}", name));
+    }
+    Ok(dir.join(format!("{}.toml", name)))
+}
+
+/// Returns the file a theme is read from, built in themes have no file.
+fn theme_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    if let Some(path) = import_path(name) {
+        return Some(path);
+    }
+    let path = theme_path(dir, name).ok()?;
+    path.exists().then_some(path)
+}
+
+/// Returns the files a theme and the themes it inherits from are read from, used to reload the
+/// theme when one of them changes.
+pub fn theme_files(name: &str) -> Vec<PathBuf> {
+    let dir = themes_path();
+    let mut files = vec![];
+    let mut next = Some(name.to_string());
+    for _ in 0..=MAX_INHERITANCE_DEPTH {
+        let Some(name) = next.take() else {
+            break;
+        };
+        files.extend(theme_file(&dir, &name));
+        // a theme that doesn't parse is still watched, so fixing it reloads it
+        next = read_theme_table(&dir, &name)
+            .ok()
+            .and_then(|table| table.get("inherits")?.as_str().map(str::to_string));
+    }
+    files
+}
+
+fn read_theme_table(dir: &Path, name: &str) -> Result<Table> {
+    if let Some(table) = import_theme_table(name) {
+        return table;
+    }
+    let path = theme_path(dir, name)?;
+    let contents = if path.exists() {
+        std::fs::read_to_string(&path)?
+    } else if let Some((_, contents)) = BUILTIN_THEMES.iter().find(|(n, _)| *n == name) {
+        contents.to_string()
+    } else {
+        return Err(eyre!("Theme {:?} not found in {:?}", name, dir));
+    };
+    contents
+        .parse::<Table>()
+        .map_err(|err| eyre!("Could not parse theme {:?}: {}", name, err))
+}
+
+// merge `overlay` on top of `base`, merging nested tables i.e. [search]
+fn merge_tables(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+// follow `inherits` down to the default theme, returning the fully merged table
+fn flatten_theme_table(dir: &Path, mut table: Table, depth: usize) -> Result<Table> {
+    if depth > MAX_INHERITANCE_DEPTH {
+        return Err(eyre!("Theme inheritance is too deep, is there a cycle?"));
+    }
+    let mut base = match table.remove("inherits") {
+        Some(Value::String(parent)) => {
+            flatten_theme_table(dir, read_theme_table(dir, &parent)?, depth + 1)?
+        }
+        Some(_) => return Err(eyre!("Theme `inherits` must be the name of a theme")),
+        None => Table::try_from(ThemeSettings::default())?,
+    };
+    merge_tables(&mut base, table);
+    Ok(base)
+}
+
+/// Resolve a theme table, as written inline in settings.toml or in a theme file.
+pub fn resolve_theme_table(table: Table) -> Result<ThemeSettings> {
+    resolve_theme_table_in(&themes_path(), table)
+}
+
+fn resolve_theme_table_in(dir: &Path, table: Table) -> Result<ThemeSettings> {
+    let theme: ThemeSettings = flatten_theme_table(dir, table, 0)?.try_into()?;
+    Ok(theme)
+}
+
+/// Load a theme by name from the themes directory or the built in themes.
+pub fn load_theme(name: &str) -> Result<ThemeSettings> {
+    load_theme_in(&themes_path(), name)
+}
+
+fn load_theme_in(dir: &Path, name: &str) -> Result<ThemeSettings> {
+    let mut theme = resolve_theme_table_in(dir, read_theme_table(dir, name)?)?;
+    theme.name = Some(name.to_string());
+    Ok(theme)
+}
+
+// strip unset values, i.e. the `None` colours in the default settings layer, which toml can't represent
+fn strip_nulls(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, strip_nulls(v)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+// deserialize `ui.theme` from either a theme name or an inline table
+pub fn deserialize_theme<'de, D>(deserializer: D) -> std::result::Result<ThemeSettings, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match strip_nulls(serde_json::Value::deserialize(deserializer)?) {
+        serde_json::Value::String(name) => load_theme(&name).map_err(serde::de::Error::custom),
+        value @ serde_json::Value::Object(_) => {
+            let table = Table::try_from(value).map_err(serde::de::Error::custom)?;
+            resolve_theme_table(table).map_err(serde::de::Error::custom)
+        }
+        _ => Err(serde::de::Error::custom(
+            "ui.theme must be a theme name or a table of colours",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+    use ratatui::widgets::BorderType;
+
+    // an empty themes directory per test, so themes in ~/.config/rook/themes don't change the
+    // results, it doesn't need to exist
+    fn test_themes_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rook-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_builtin_themes_load() {
+        let dir = test_themes_dir("builtin-themes");
+        for (name, _) in BUILTIN_THEMES {
+            let theme = load_theme_in(&dir, name).expect("Could not load built in theme");
+            assert_eq!(theme.name.as_deref(), Some(*name));
+        }
+        assert!(load_theme_in(&dir, "does-not-exist").is_err());
+    }
+
+    #[test]
+    fn test_theme_inheritance() {
+        let dir = test_themes_dir("theme-inheritance");
+        let nord = load_theme_in(&dir, "nord").unwrap();
+        let frost = load_theme_in(&dir, "nord-frost").unwrap();
+        // overridden keys
+        assert_eq!(frost.accent, Color::Rgb(129, 161, 193));
+        assert_eq!(frost.border_type, BorderType::Plain);
+        // inherited keys
+        assert_eq!(frost.background, nord.background);
+        assert_eq!(
+            frost.get_search_colors().caret,
+            nord.get_search_colors().caret
+        );
+    }
+
+    #[test]
+    fn test_user_theme_replaces_builtin() {
+        let dir = test_themes_dir("user-themes");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("nord.toml"), "accent = \"Red\"").unwrap();
+        // nord-frost inherits the user's nord
+        assert_eq!(load_theme_in(&dir, "nord").unwrap().accent, Color::Red);
+        let frost = load_theme_in(&dir, "nord-frost").unwrap();
+        assert_eq!(frost.text, ThemeSettings::default().text);
+        assert_eq!(theme_file(&dir, "nord"), Some(dir.join("nord.toml")));
+        assert_eq!(theme_file(&dir, "dracula"), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_theme_names() {
+        let dir = test_themes_dir("invalid-themes");
+        for name in ["../nord", "/etc/rook", "themes/nord", "themes\\nord", ".."] {
+            let err = load_theme_in(&dir, name).unwrap_err();
+            assert!(err.to_string().starts_with("Invalid theme name"), "{}", err);
+            assert_eq!(theme_file(&dir, name), None);
+        }
+        let table: Table = "inherits = \"../../nord\"".parse().unwrap();
+        assert!(resolve_theme_table_in(&dir, table).is_err());
+    }
+
+    #[test]
+    fn test_inline_theme_falls_back_to_default() {
+        let table: Table = "accent = \"Red\"".parse().unwrap();
+        let theme = resolve_theme_table(table).unwrap();
+        assert_eq!(theme.accent, Color::Red);
+        assert_eq!(theme.text, ThemeSettings::default().text);
+    }
+}
@@ -11,12 +11,15 @@ use crossterm::{
     cursor,
     event::{
         DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
-        Event as CrosstermEvent, EventStream, KeyEvent, KeyEventKind, MouseEvent,
+        Event as CrosstermEvent, EventStream, KeyEvent, KeyEventKind, KeyboardEnhancementFlags,
+        MouseEvent, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
     },
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
 use futures::{FutureExt, StreamExt};
-use ratatui::backend::CrosstermBackend as Backend;
+use ratatui::{
+    TerminalOptions, Viewport, backend::CrosstermBackend as Backend, layout::Constraint,
+};
 use serde::{Deserialize, Serialize};
 use tokio::{
     sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
@@ -56,6 +59,7 @@ pub struct Tui {
     pub tick_rate: f64,
     pub mouse: bool,
     pub paste: bool,
+    pub inline: bool, // drawn in a viewport below the prompt instead of the alternate screen
 }
 
 impl Tui {
@@ -71,6 +75,7 @@ impl Tui {
             tick_rate: 4.0,
             mouse: true,
             paste: false,
+            inline: false,
         })
     }
 
@@ -94,6 +99,25 @@ impl Tui {
         self
     }
 
+    /// Draw in a viewport of the given height below the prompt, a number of lines or a
+    /// percentage of the terminal, leaving the scrollback as it is.
+    pub fn inline(mut self, height: Constraint) -> Result<Self> {
+        let (_, rows) = crossterm::terminal::size()?;
+        let height = match height {
+            Constraint::Percentage(p) => rows * p / 100,
+            Constraint::Length(n) => n,
+            _ => rows,
+        };
+        self.terminal = ratatui::Terminal::with_options(
+            Backend::new(stdout()),
+            TerminalOptions {
+                viewport: Viewport::Inline(height.clamp(1, rows.max(1))),
+            },
+        )?;
+        self.inline = true;
+        Ok(self)
+    }
+
     pub fn start(&mut self) {
         self.cancel(); // Cancel any existing task
         self.cancellation_token = CancellationToken::new();
@@ -170,13 +194,23 @@ impl Tui {
 
     pub fn enter(&mut self) -> Result<()> {
         crossterm::terminal::enable_raw_mode()?;
-        crossterm::execute!(stdout(), EnterAlternateScreen, cursor::Hide)?;
+        if self.inline {
+            crossterm::execute!(stdout(), cursor::Hide)?;
+        } else {
+            crossterm::execute!(stdout(), EnterAlternateScreen, cursor::Hide)?;
+        }
         if self.mouse {
             crossterm::execute!(stdout(), EnableMouseCapture)?;
         }
         if self.paste {
             crossterm::execute!(stdout(), EnableBracketedPaste)?;
         }
+        // lets terminals that support it report keys like Ctrl + 1, ignored by the rest
+        crossterm::execute!(
+            stdout(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        )
+        .ok();
         self.start();
         Ok(())
     }
@@ -185,13 +219,21 @@ impl Tui {
         self.stop()?;
         if crossterm::terminal::is_raw_mode_enabled()? {
             self.flush()?;
+            crossterm::execute!(stdout(), PopKeyboardEnhancementFlags).ok();
             if self.paste {
                 crossterm::execute!(stdout(), DisableBracketedPaste)?;
             }
             if self.mouse {
                 crossterm::execute!(stdout(), DisableMouseCapture)?;
             }
-            crossterm::execute!(stdout(), LeaveAlternateScreen, cursor::Show)?;
+            if self.inline {
+                // wipe the viewport and leave the cursor where it started
+                self.terminal.clear()?;
+                let area = self.terminal.get_frame().area();
+                crossterm::execute!(stdout(), cursor::MoveTo(0, area.y), cursor::Show)?;
+            } else {
+                crossterm::execute!(stdout(), LeaveAlternateScreen, cursor::Show)?;
+            }
             crossterm::terminal::disable_raw_mode()?;
         }
         Ok(())